@@ -7,15 +7,20 @@ use crate::plugins::network::Network;
 use lumen::{
     address::Address,
     fixture_set::ResolvedFixtureMap,
-    output::{sacn::ACN_SDT_MULTICAST_PORT, NetworkState},
+    output::{sacn::ACN_SDT_MULTICAST_PORT, NetworkOutput, NetworkState},
     parameter::{Param, Parameter},
     patch::FixtureProfile,
+    scheduler::{OutputScheduler, SchedulerHandle},
     timecode::Source,
-    universe::Multiverse,
     Environment, Patch,
 };
 use lux::{evaluator::Evaluator, parser::parse};
-use std::{fmt::Write, sync::Mutex, thread, time::Duration};
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
 use tauri::{State, Window};
 
 mod plugins;
@@ -51,13 +56,13 @@ fn on_text_change(source: String, lockable_environment: State<LockableEnvironmen
 }
 
 #[tauri::command]
-fn get_current_time(source: State<Mutex<Source>>) -> String {
+fn get_current_time(source: State<Arc<Mutex<Source>>>) -> String {
     let source = source.lock().unwrap();
     source.time().tc_string(source.fps())
 }
 
 #[tauri::command]
-fn start_time(source: State<Mutex<Source>>) -> String {
+fn start_time(source: State<Arc<Mutex<Source>>>) -> String {
     let mut source = source.lock().unwrap();
 
     if source.paused() {
@@ -70,14 +75,14 @@ fn start_time(source: State<Mutex<Source>>) -> String {
 }
 
 #[tauri::command]
-fn pause_time(source: State<Mutex<Source>>) -> String {
+fn pause_time(source: State<Arc<Mutex<Source>>>) -> String {
     let mut source = source.lock().unwrap();
     source.pause();
     source.time().tc_string(source.fps())
 }
 
 #[tauri::command]
-fn stop_time(source: State<Mutex<Source>>) -> String {
+fn stop_time(source: State<Arc<Mutex<Source>>>) -> String {
     let mut source = source.lock().unwrap();
     source.stop();
     source.time().tc_string(source.fps())
@@ -91,19 +96,20 @@ fn init_tick(window: Window) {
     });
 }
 
-// TODO: This has become a very large general tick function, which it should
-//       not be, and all this should be factored out.
+// The output scheduler resolves and sends on its own thread, so the UI only
+// observes the last frame and looks after the network connection.
 #[tauri::command]
 fn resolve(
     window: Window,
-    lockable_environment: State<LockableEnvironment>,
-    source: State<Mutex<Source>>,
+    scheduler: State<SchedulerHandle>,
     network: State<Mutex<Network>>,
 ) -> ResolvedFixtureMap {
-    let mut env = lockable_environment.env.lock().unwrap();
-    let source = source.lock().unwrap();
     let mut network = network.lock().unwrap();
 
+    if network.disconnected() {
+        window.emit("network/disconnected", ()).unwrap();
+    }
+
     if network.state() == NetworkState::Bound {
         network.try_connect(format!("127.0.0.1:{}", ACN_SDT_MULTICAST_PORT));
 
@@ -114,47 +120,47 @@ fn resolve(
         }
     }
 
+    scheduler.status().resolved
+}
+
+struct LockableEnvironment {
+    env: Arc<Mutex<Environment>>,
+    patch: Arc<Mutex<Patch>>,
+}
+
+// TODO: This is a temporary patch until patches can be loaded from a show.
+fn default_patch() -> Patch {
     let mut dimmer = FixtureProfile::new();
     dimmer.set_parameter(Param::Intensity, Parameter::simple(0));
+    let dimmer = Arc::new(dimmer);
+
     let mut quad = FixtureProfile::new();
     quad.set_colorspace(lumen::color::Colorspace::RGBA);
     quad.set_parameter(Param::Red, Parameter::simple(0));
     quad.set_parameter(Param::Green, Parameter::simple(1));
     quad.set_parameter(Param::Blue, Parameter::simple(2));
     quad.set_parameter(Param::Amber, Parameter::simple(3));
+    let quad = Arc::new(quad);
+
     let mut patch = Patch::new();
 
     for n in 1..=10 {
-        patch.patch(n, Address::new(1, n as u16), &dimmer);
+        patch
+            .patch(n, Address::new(1, n as u16), Arc::clone(&dimmer))
+            .expect("dimmers should patch without conflicts");
     }
 
     for n in 1..=9 {
-        patch.patch(100 + n, Address::new(1, (91 + (n * 10)) as u16), &quad);
-    }
-
-    let t = source.time();
-    env.run_to_time(t, &patch);
-    let resolved_map = env.fixtures.resolve(t, &patch);
-
-    // TODO: Very temporary dmx generation of the multiverse, should really be
-    //       under some much cleaner interface, when we rewrite the candela app
-    //       to have much clearer responsibilities.
-    let mut multiverse = Multiverse::new();
-    for (id, resolved_fixture) in resolved_map.iter() {
-        let dmx_string = patch.get_profile(id).to_dmx(resolved_fixture);
-        multiverse.map_string(patch.get_address(id), &dmx_string);
-    }
-
-    if network.state() == NetworkState::Connected && network.output_multiverse(&multiverse).is_err()
-    {
-        window.emit("network/disconnected", ()).unwrap();
+        patch
+            .patch(
+                100 + n,
+                Address::new(1, (91 + (n * 10)) as u16),
+                Arc::clone(&quad),
+            )
+            .expect("quads should patch without conflicts");
     }
 
-    resolved_map
-}
-
-struct LockableEnvironment {
-    env: Mutex<Environment>,
+    patch
 }
 
 fn main() {
@@ -169,12 +175,26 @@ fn main() {
         environment.fixtures.create_with_id(n);
     }
 
+    let lockable_environment = LockableEnvironment {
+        env: Arc::new(Mutex::new(environment)),
+        patch: Arc::new(Mutex::new(default_patch())),
+    };
+    let source = Arc::new(Mutex::new(source));
+    let output = Arc::new(Mutex::new(NetworkOutput::new()));
+
+    let scheduler = OutputScheduler::new(
+        Arc::clone(&lockable_environment.env),
+        Arc::clone(&lockable_environment.patch),
+        Arc::clone(&source),
+        Arc::clone(&output),
+    )
+    .start();
+
     tauri::Builder::default()
-        .plugin(plugins::network::init())
-        .manage(LockableEnvironment {
-            env: Mutex::new(environment),
-        })
-        .manage(Mutex::new(source))
+        .plugin(plugins::network::init(output))
+        .manage(lockable_environment)
+        .manage(source)
+        .manage(scheduler)
         .invoke_handler(tauri::generate_handler![
             init_tick,
             on_text_change,
@@ -1,21 +1,20 @@
 use lumen::output::NetworkState;
 use std::{
     net::ToSocketAddrs,
-    sync::Mutex,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 use tauri::Manager;
 
-use lumen::{
-    output::{sacn::ACN_SDT_MULTICAST_PORT, NetworkOutput},
-    universe::Multiverse,
-};
+use lumen::output::{sacn::ACN_SDT_MULTICAST_PORT, NetworkOutput};
 use tauri::{plugin::Builder, plugin::TauriPlugin, Runtime};
 
-pub fn init<R: Runtime>() -> TauriPlugin<R> {
+// The output is shared with the output scheduler, which does the sending, so
+// the plugin only manages the connection.
+pub fn init<R: Runtime>(output: Arc<Mutex<NetworkOutput>>) -> TauriPlugin<R> {
     Builder::new("network")
-        .setup(|app_handle| {
-            let mut network = Network::new();
+        .setup(move |app_handle| {
+            let mut network = Network::new(output);
             network.bind("127.0.0.1:12345");
             network.connect(format!("127.0.0.1:{}", ACN_SDT_MULTICAST_PORT));
             app_handle.emit_all("network/connected", ()).unwrap();
@@ -26,20 +25,22 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
 }
 
 pub struct Network {
-    output: NetworkOutput,
+    output: Arc<Mutex<NetworkOutput>>,
     last_connection_attempt: Instant,
+    connected: bool,
 }
 
 impl Network {
-    fn new() -> Self {
+    fn new(output: Arc<Mutex<NetworkOutput>>) -> Self {
         Self {
-            output: NetworkOutput::new(),
+            output,
             last_connection_attempt: Instant::now(),
+            connected: false,
         }
     }
 
     fn bind(&mut self, addr: impl ToSocketAddrs) {
-        match self.output.bind(addr) {
+        match self.output.lock().unwrap().bind(addr) {
             Ok(()) => println!("bound to address"),
             Err(e) => eprintln!("could not bind: {e}"),
         }
@@ -53,21 +54,27 @@ impl Network {
 
     fn connect(&mut self, addr: impl ToSocketAddrs) {
         self.last_connection_attempt = Instant::now();
-        match self.output.connect(addr) {
-            Ok(()) => println!("connected to sacn..."),
+        match self.output.lock().unwrap().connect(addr) {
+            Ok(()) => {
+                self.connected = true;
+                println!("connected to sacn...")
+            }
             Err(e) => println!("failed to connect to sacn: {e}"),
         }
     }
 
-    pub fn output_multiverse(&mut self, multiverse: &Multiverse) -> Result<(), std::io::Error> {
-        for universe in multiverse.universes() {
-            self.output.send_data(universe)?;
+    // True the first time we see the output has dropped its connection after
+    // failing to send.
+    pub fn disconnected(&mut self) -> bool {
+        if self.connected && self.state() != NetworkState::Connected {
+            self.connected = false;
+            return true;
         }
 
-        Ok(())
+        false
     }
 
     pub fn state(&self) -> NetworkState {
-        self.output.state
+        self.output.lock().unwrap().state
     }
 }
@@ -24,4 +24,8 @@ function pretty_value(value) {
   if ("Percentage" in value) {
     return value["Percentage"]["percentage"].toFixed(2);
   }
+
+  if ("Slot" in value) {
+    return `"${value["Slot"]}"`;
+  }
 }
\ No newline at end of file
@@ -16,7 +16,13 @@ const COLORS = {
 export function setUp(monaco) {
   monaco.languages.register({id: "lux"});
   monaco.languages.setMonarchTokensProvider("lux", {
-    keywords: [],
+    keywords: [
+      "home", "sine", "square", "saw", "random",
+      "phase", "delay", "selection", "blocks", "wings",
+      "fan", "linear", "center", "mirror",
+      "ease_in", "ease_out", "s_curve", "snap_start", "snap_end",
+      "time", "up", "down",
+    ],
     tokenizer: {
       root: [
         [ /[a-zA-Z][\w$]*/, {
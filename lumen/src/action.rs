@@ -1,6 +1,8 @@
 use crate::{
+    fixture::FixtureID,
     parameter::Param,
     query::Query,
+    spread::{Selection, Spread},
     timecode::time::Time,
     value::{generator::BoxedGenerator, Values},
 };
@@ -9,6 +11,8 @@ use crate::{
 pub struct Apply {
     pub parameter: Param,
     pub generator: BoxedGenerator,
+    // When set, the generator is distributed across the fixtures selected.
+    pub spread: Option<Spread>,
 }
 
 impl Apply {
@@ -16,9 +20,29 @@ impl Apply {
         Self {
             parameter,
             generator,
+            spread: None,
         }
     }
 
+    pub fn set_spread(&mut self, spread: Spread) {
+        self.spread = Some(spread)
+    }
+
+    // Spreads the generator for a fixture in the selection of the apply.
+    // Without a spread, the fixture is still given its position, in the same
+    // order as a spread.
+    pub fn spread_over(&mut self, id: &FixtureID, selection: &Selection) {
+        let spread = self.spread.unwrap_or_default();
+
+        let position = match spread.position(id, selection) {
+            Some(position) => position,
+            None => return,
+        };
+
+        self.generator.set_position(&position);
+        self.generator = spread.apply(self.generator.clone(), &position);
+    }
+
     pub fn set_start_time(&mut self, time: Time) {
         self.generator.set_start_time(time);
     }
@@ -30,10 +54,20 @@ impl Apply {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum ApplyStep {
+    Apply(Apply),
+    // Sends every parameter programmed on the selected fixtures so far home
+    // with the generator.
+    Home(BoxedGenerator),
+}
+
 #[derive(Debug, Clone)]
 pub struct ApplyGroup {
     pub query: Query,
-    pub applies: Vec<Apply>,
+    // The steps are applied in the order they were added, so a home only
+    // affects what was programmed before it.
+    pub applies: Vec<ApplyStep>,
 }
 
 impl ApplyGroup {
@@ -45,7 +79,11 @@ impl ApplyGroup {
     }
 
     pub fn add_apply(&mut self, apply: Apply) {
-        self.applies.push(apply)
+        self.applies.push(ApplyStep::Apply(apply))
+    }
+
+    pub fn add_home(&mut self, generator: BoxedGenerator) {
+        self.applies.push(ApplyStep::Home(generator))
     }
 }
 
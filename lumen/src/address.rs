@@ -1,8 +1,10 @@
-use std::ops::Add;
+use std::{fmt::Display, ops::Add};
+
+use serde::{Deserialize, Serialize};
 
 const DMX_MAX_ADDRESS: u16 = 512;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct Address {
     universe: u16,
     address: u16,
@@ -13,6 +15,26 @@ impl Address {
         Self { universe, address }
     }
 
+    pub fn universe(&self) -> u16 {
+        self.universe
+    }
+
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    // Universes and addresses are counted from 1, and a universe only has 512
+    // addresses.
+    pub fn is_valid(&self) -> bool {
+        self.universe >= 1 && (1..=DMX_MAX_ADDRESS).contains(&self.address)
+    }
+
+    // Whether a footprint of this many channels starting here ends within the
+    // same universe.
+    pub fn fits(&self, footprint: usize) -> bool {
+        self.address as usize + footprint <= DMX_MAX_ADDRESS as usize + 1
+    }
+
     pub fn universe_index(&self) -> usize {
         // Humans use 1.001 as the first universe, but its index would be -1 of
         // the human readable format.
@@ -26,6 +48,12 @@ impl Address {
     }
 }
 
+impl Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:03}", self.universe, self.address)
+    }
+}
+
 impl Add<Address> for Address {
     type Output = Address;
 
@@ -78,6 +106,21 @@ mod tests {
         assert_eq!(a + b, c);
     }
 
+    #[test]
+    fn valid_addresses() {
+        assert!(dmx!(1 / 1).is_valid());
+        assert!(dmx!(2 / 512).is_valid());
+        assert!(!dmx!(0 / 1).is_valid());
+        assert!(!dmx!(1 / 0).is_valid());
+        assert!(!dmx!(1 / 513).is_valid());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(dmx!(1 / 1).to_string(), "1.001");
+        assert_eq!(dmx!(12 / 345).to_string(), "12.345");
+    }
+
     #[test]
     fn adding_universes() {
         let a = dmx!(1 / 234);
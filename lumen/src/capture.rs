@@ -0,0 +1,303 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    net::UdpSocket,
+    path::Path,
+    time::Duration,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{
+    output::Output,
+    timecode::time::Time,
+    universe::{Multiverse, Universe},
+};
+
+// A capture file starts with a header of the magic bytes and a version,
+// followed by every recorded frame:
+//
+//   time in milliseconds    u64
+//   number of universes     u16
+//   for each universe:
+//     universe number       u16
+//     number of slots       u16
+//     slots                 u8 * number of slots
+//
+// Trailing slots that are zero are not written, so dark universes are small.
+const MAGIC: &[u8; 6] = b"LUMCAP";
+const VERSION: u8 = 1;
+const UNIVERSE_SIZE: usize = 512;
+
+#[derive(Debug, PartialEq)]
+pub enum CaptureError {
+    Io(String),
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    Truncated,
+    InvalidFrame(String),
+    // A frame at the time in milliseconds came after a later frame.
+    OutOfOrder(u64),
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::Io(err) => write!(f, "could not access capture: {}", err),
+            CaptureError::InvalidHeader => write!(f, "not a capture file"),
+            CaptureError::UnsupportedVersion(version) => {
+                write!(f, "unsupported capture version {}", version)
+            }
+            CaptureError::Truncated => write!(f, "capture ends part way through a frame"),
+            CaptureError::InvalidFrame(reason) => write!(f, "invalid frame: {}", reason),
+            CaptureError::OutOfOrder(milliseconds) => {
+                write!(
+                    f,
+                    "frame at {}ms is before the frame it follows",
+                    milliseconds
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::UnexpectedEof => CaptureError::Truncated,
+            _ => CaptureError::Io(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CaptureFrame {
+    pub time: Time,
+    pub multiverse: Multiverse,
+}
+
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+    // The time of the last frame recorded, as frames must be in order.
+    last_time: Option<u64>,
+}
+
+impl CaptureWriter<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, CaptureError> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self, CaptureError> {
+        writer.write_all(MAGIC)?;
+        writer.write_u8(VERSION)?;
+
+        Ok(Self {
+            writer,
+            last_time: None,
+        })
+    }
+
+    pub fn record(&mut self, time: Time, multiverse: &Multiverse) -> Result<(), CaptureError> {
+        let milliseconds = Duration::from(time).as_millis() as u64;
+        check_order(&mut self.last_time, milliseconds)?;
+
+        let mut universes: Vec<&Universe> = multiverse.universes().collect();
+        universes.sort_by_key(|universe| universe.universe_number());
+
+        self.writer.write_u64::<NetworkEndian>(milliseconds)?;
+        self.writer
+            .write_u16::<NetworkEndian>(universes.len() as u16)?;
+
+        for universe in universes {
+            let bytes = universe.bytes();
+            let slots = bytes
+                .iter()
+                .rposition(|byte| *byte != 0)
+                .map_or(0, |last| last + 1);
+
+            self.writer
+                .write_u16::<NetworkEndian>(universe.universe_number() as u16)?;
+            self.writer.write_u16::<NetworkEndian>(slots as u16)?;
+            self.writer.write_all(&bytes[..slots])?;
+        }
+
+        Ok(())
+    }
+
+    // Flushes any buffered frames and gives back the writer.
+    pub fn finish(mut self) -> Result<W, CaptureError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+pub struct CaptureReader<R: Read> {
+    reader: R,
+    last_time: Option<u64>,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CaptureError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, CaptureError> {
+        let mut magic = [0; 6];
+        match reader.read_exact(&mut magic) {
+            Ok(()) if &magic == MAGIC => {}
+            _ => return Err(CaptureError::InvalidHeader),
+        }
+
+        let version = reader.read_u8().map_err(|_| CaptureError::InvalidHeader)?;
+        if version != VERSION {
+            return Err(CaptureError::UnsupportedVersion(version));
+        }
+
+        Ok(Self {
+            reader,
+            last_time: None,
+        })
+    }
+
+    fn read_frame(&mut self) -> Result<Option<CaptureFrame>, CaptureError> {
+        // The end of the capture is only valid between frames.
+        let mut first = [0; 1];
+        if self.reader.read(&mut first)? == 0 {
+            return Ok(None);
+        }
+
+        let mut rest = [0; 7];
+        self.reader.read_exact(&mut rest)?;
+        let mut time = [0; 8];
+        time[0] = first[0];
+        time[1..].copy_from_slice(&rest);
+        let milliseconds = u64::from_be_bytes(time);
+        check_order(&mut self.last_time, milliseconds)?;
+
+        let mut multiverse = Multiverse::new();
+
+        for _ in 0..self.reader.read_u16::<NetworkEndian>()? {
+            let universe_number = self.reader.read_u16::<NetworkEndian>()?;
+            let slots = self.reader.read_u16::<NetworkEndian>()? as usize;
+
+            if universe_number == 0 {
+                return Err(CaptureError::InvalidFrame("universe 0".to_string()));
+            }
+
+            if slots > UNIVERSE_SIZE {
+                return Err(CaptureError::InvalidFrame(format!(
+                    "{} slots in universe {}",
+                    slots, universe_number
+                )));
+            }
+
+            let mut bytes = [0; UNIVERSE_SIZE];
+            self.reader.read_exact(&mut bytes[..slots])?;
+
+            multiverse.insert(Universe::from_bytes(universe_number as usize, &bytes));
+        }
+
+        Ok(Some(CaptureFrame {
+            time: Time::at(0, 0, 0, milliseconds as u128),
+            multiverse,
+        }))
+    }
+}
+
+// Frames can share a time, but can't go back in time, as playback looks up
+// frames by time.
+fn check_order(last_time: &mut Option<u64>, milliseconds: u64) -> Result<(), CaptureError> {
+    if last_time.is_some_and(|last_time| milliseconds < last_time) {
+        return Err(CaptureError::OutOfOrder(milliseconds));
+    }
+
+    *last_time = Some(milliseconds);
+    Ok(())
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureFrame, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+// Plays back a capture through an output, following the time of a timecode
+// source or any other clock.
+pub struct CapturePlayer {
+    frames: Vec<CaptureFrame>,
+    last_sent: Option<usize>,
+}
+
+impl CapturePlayer {
+    pub fn new(frames: Vec<CaptureFrame>) -> Result<Self, CaptureError> {
+        let mut last_time = None;
+        for frame in frames.iter() {
+            check_order(
+                &mut last_time,
+                Duration::from(frame.time).as_millis() as u64,
+            )?;
+        }
+
+        Ok(Self {
+            frames,
+            last_sent: None,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CaptureError> {
+        Self::from_reader(CaptureReader::open(path)?)
+    }
+
+    pub fn from_reader<R: Read>(reader: CaptureReader<R>) -> Result<Self, CaptureError> {
+        Self::new(reader.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    pub fn frames(&self) -> &[CaptureFrame] {
+        &self.frames
+    }
+
+    // The last frame recorded at or before the time.
+    pub fn frame_at(&self, time: Time) -> Option<&CaptureFrame> {
+        self.index_at(time).map(|index| &self.frames[index])
+    }
+
+    // Sends the frame for the time through the output, if it is not the frame
+    // that was last sent. Returns true if a frame was sent.
+    pub fn play(
+        &mut self,
+        time: Time,
+        output: &mut dyn Output,
+        socket: &UdpSocket,
+    ) -> Result<bool, std::io::Error> {
+        let index = match self.index_at(time) {
+            Some(index) if Some(index) != self.last_sent => index,
+            _ => return Ok(false),
+        };
+
+        output.send_multiverse(&self.frames[index].multiverse, socket)?;
+        self.last_sent = Some(index);
+
+        Ok(true)
+    }
+
+    // Forget the last frame sent, so the next call to play always sends, for
+    // example after seeking.
+    pub fn reset(&mut self) {
+        self.last_sent = None;
+    }
+
+    fn index_at(&self, time: Time) -> Option<usize> {
+        self.frames
+            .partition_point(|frame| frame.time <= time)
+            .checked_sub(1)
+    }
+}
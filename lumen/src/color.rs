@@ -1,25 +1,341 @@
 use std::collections::HashMap;
 
-use crate::{fixture::ParameterMap, parameter::Param, value::Values};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy)]
+use crate::{
+    fixture::ParameterMap,
+    parameter::{Param, Parameter, ParameterBuilder},
+    patch::FixtureProfile,
+    timecode::time::Time,
+    value::Values,
+};
+
+// The mix of red and green that an amber emitter adds to a color
+const AMBER_GREEN: f64 = 0.75;
+
+// The range of color temperatures that can be converted, in kelvin
+const MIN_KELVIN: f64 = 1000.0;
+const MAX_KELVIN: f64 = 12000.0;
+const DEFAULT_KELVIN: f64 = 6500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Colorspace {
     RGB,
     RGBA,
+    RGBW,
+    RGBAW,
+    CMY,
+    HSI,
+    // A color temperature in kelvin, which is usually only programmed and
+    // converted to the colorspace of the fixture.
+    Kelvin,
 }
 
 impl Colorspace {
-    pub fn detect(_parameters: &ParameterMap) -> Colorspace {
-        // TODO: Detect colorspace
-        Colorspace::RGBA
+    // Detects the colorspace that a fixture is being programmed in from the
+    // color params that have generators. When params from more than one
+    // colorspace are programmed, the one with the latest generator is used,
+    // and if they start at the same time, the last color param applied.
+    pub fn detect(parameters: &ParameterMap, last_applied: Option<Param>) -> Option<Colorspace> {
+        let latest_param = parameters
+            .iter()
+            .filter(|(param, generators)| param.is_color() && !generators.is_empty())
+            .map(|(param, generators)| {
+                let latest = generators
+                    .iter()
+                    .map(|generator| generator.start_time())
+                    .max()
+                    .unwrap_or_else(|| Time::at(0, 0, 0, 0));
+
+                (latest, last_applied == Some(*param), *param)
+            })
+            .max_by_key(|(latest, is_last_applied, _)| (*latest, *is_last_applied))
+            .map(|(_, _, param)| param)?;
+
+        // Like the latest param, only params with generators are programmed.
+        let programmed = |param: &Param| {
+            parameters
+                .get(param)
+                .is_some_and(|generators| !generators.is_empty())
+        };
+
+        if Self::Kelvin.params().contains(&latest_param) {
+            return Some(Colorspace::Kelvin);
+        }
+
+        if Self::HSI.params().contains(&latest_param) {
+            return Some(Colorspace::HSI);
+        }
+
+        if Self::CMY.params().contains(&latest_param) {
+            return Some(Colorspace::CMY);
+        }
+
+        let colorspace = match (programmed(&Param::Amber), programmed(&Param::White)) {
+            (true, true) => Colorspace::RGBAW,
+            (true, false) => Colorspace::RGBA,
+            (false, true) => Colorspace::RGBW,
+            (false, false) => Colorspace::RGB,
+        };
+
+        Some(colorspace)
     }
 
     pub fn params_for_colorspace(colorspace: &Colorspace) -> Vec<Param> {
-        match colorspace {
-            Colorspace::RGB => vec![Param::Red, Param::Green, Param::Blue],
-            Colorspace::RGBA => vec![Param::Red, Param::Green, Param::Blue, Param::Amber],
+        colorspace.params().to_vec()
+    }
+
+    pub fn params(&self) -> &'static [Param] {
+        match self {
+            Colorspace::RGB => &[Param::Red, Param::Green, Param::Blue],
+            Colorspace::RGBA => &[Param::Red, Param::Green, Param::Blue, Param::Amber],
+            Colorspace::RGBW => &[Param::Red, Param::Green, Param::Blue, Param::White],
+            Colorspace::RGBAW => &[
+                Param::Red,
+                Param::Green,
+                Param::Blue,
+                Param::Amber,
+                Param::White,
+            ],
+            Colorspace::CMY => &[Param::Cyan, Param::Magenta, Param::Yellow],
+            Colorspace::HSI => &[Param::Hue, Param::Saturation, Param::Brightness],
+            Colorspace::Kelvin => &[Param::ColorTemperature],
+        }
+    }
+
+    // The parameter used for a color param that the profile doesn't have, so
+    // that a fixture can be programmed in any colorspace.
+    pub fn virtual_parameter(param: &Param) -> Parameter {
+        match *param {
+            Param::Hue => Parameter::new(0, 0.0, 360.0),
+            Param::ColorTemperature => ParameterBuilder::new(0)
+                .min(MIN_KELVIN)
+                .max(MAX_KELVIN)
+                .default(DEFAULT_KELVIN)
+                .build(),
+            _ => Parameter::new(0, 0.0, 100.0),
+        }
+    }
+
+    fn parameter_for(profile: &FixtureProfile, param: &Param) -> Parameter {
+        profile
+            .get_parameter(param)
+            .cloned()
+            .unwrap_or_else(|| Self::virtual_parameter(param))
+    }
+
+    // Converts the components of the colorspace to red, green and blue
+    // factors. Components are factors between 0 and 1, except for a color
+    // temperature which is in kelvin.
+    fn rgb_from_components(&self, factors: &[f64]) -> Rgb {
+        match self {
+            Colorspace::RGB => Rgb::new(factors[0], factors[1], factors[2]),
+            Colorspace::RGBA => Rgb::new(factors[0], factors[1], factors[2]).add_amber(factors[3]),
+            Colorspace::RGBW => Rgb::new(factors[0], factors[1], factors[2]).add_white(factors[3]),
+            Colorspace::RGBAW => Rgb::new(factors[0], factors[1], factors[2])
+                .add_amber(factors[3])
+                .add_white(factors[4]),
+            Colorspace::CMY => Rgb::new(1.0 - factors[0], 1.0 - factors[1], 1.0 - factors[2]),
+            Colorspace::HSI => Rgb::from_hsi(factors[0], factors[1], factors[2]),
+            Colorspace::Kelvin => Rgb::from_kelvin(factors[0]),
+        }
+    }
+
+    // Converts red, green and blue factors to the components of the
+    // colorspace.
+    fn components_from_rgb(&self, rgb: Rgb) -> Vec<f64> {
+        match self {
+            Colorspace::RGB => vec![rgb.red, rgb.green, rgb.blue],
+            Colorspace::RGBA => {
+                let (rgb, amber) = rgb.extract_amber();
+                vec![rgb.red, rgb.green, rgb.blue, amber]
+            }
+            Colorspace::RGBW => {
+                let (rgb, white) = rgb.extract_white();
+                vec![rgb.red, rgb.green, rgb.blue, white]
+            }
+            Colorspace::RGBAW => {
+                let (rgb, white) = rgb.extract_white();
+                let (rgb, amber) = rgb.extract_amber();
+                vec![rgb.red, rgb.green, rgb.blue, amber, white]
+            }
+            Colorspace::CMY => vec![1.0 - rgb.red, 1.0 - rgb.green, 1.0 - rgb.blue],
+            Colorspace::HSI => {
+                let (hue, saturation, intensity) = rgb.to_hsi();
+                vec![hue, saturation, intensity]
+            }
+            Colorspace::Kelvin => vec![rgb.to_kelvin()],
+        }
+    }
+
+    fn component(param: &Param, value: &Values, parameter: &Parameter) -> f64 {
+        let factor = value.to_factor(parameter);
+
+        match *param {
+            Param::ColorTemperature => {
+                parameter.min() + (parameter.max() - parameter.min()) * factor
+            }
+            _ => factor,
         }
     }
+
+    fn component_value(param: &Param, component: f64, parameter: &Parameter) -> Values {
+        let value = match *param {
+            Param::ColorTemperature => component,
+            _ => parameter.min() + (parameter.max() - parameter.min()) * component,
+        };
+
+        Values::make_literal(value.clamp(parameter.min(), parameter.max()))
+    }
+}
+
+// Red, green and blue factors, each between 0 and 1, which every colorspace
+// is converted through.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rgb {
+    red: f64,
+    green: f64,
+    blue: f64,
+}
+
+impl Rgb {
+    fn new(red: f64, green: f64, blue: f64) -> Self {
+        Self {
+            red: red.clamp(0.0, 1.0),
+            green: green.clamp(0.0, 1.0),
+            blue: blue.clamp(0.0, 1.0),
+        }
+    }
+
+    fn add_amber(self, amber: f64) -> Self {
+        Self::new(
+            self.red + amber,
+            self.green + amber * AMBER_GREEN,
+            self.blue,
+        )
+    }
+
+    fn add_white(self, white: f64) -> Self {
+        Self::new(self.red + white, self.green + white, self.blue + white)
+    }
+
+    fn extract_amber(self) -> (Self, f64) {
+        let amber = self.red.min(self.green / AMBER_GREEN).min(1.0);
+
+        let rgb = Self::new(
+            self.red - amber,
+            self.green - amber * AMBER_GREEN,
+            self.blue,
+        );
+
+        (rgb, amber)
+    }
+
+    fn extract_white(self) -> (Self, f64) {
+        let white = self.red.min(self.green).min(self.blue);
+
+        let rgb = Self::new(self.red - white, self.green - white, self.blue - white);
+
+        (rgb, white)
+    }
+
+    // Hue, saturation and intensity are all factors between 0 and 1, where
+    // the hue is the fraction of a turn around the color wheel from red. The
+    // intensity is the brightest of the components, so that a fully saturated
+    // color at full intensity has a component at full.
+    fn from_hsi(hue: f64, saturation: f64, intensity: f64) -> Self {
+        let sector = (hue.rem_euclid(1.0)) * 6.0;
+        let chroma = intensity * saturation;
+        let x = chroma * (1.0 - ((sector % 2.0) - 1.0).abs());
+        let m = intensity - chroma;
+
+        let (red, green, blue) = match sector as usize {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Self::new(red + m, green + m, blue + m)
+    }
+
+    // An approximation of the color of a black body at a temperature in
+    // kelvin, from Tanner Helland's fit of the CIE color matching functions.
+    fn from_kelvin(kelvin: f64) -> Self {
+        let temperature = kelvin.clamp(MIN_KELVIN, MAX_KELVIN) / 100.0;
+
+        let red = if temperature <= 66.0 {
+            255.0
+        } else {
+            329.698727446 * (temperature - 60.0).powf(-0.1332047592)
+        };
+
+        let green = if temperature <= 66.0 {
+            99.4708025861 * temperature.ln() - 161.1195681661
+        } else {
+            288.1221695283 * (temperature - 60.0).powf(-0.0755148492)
+        };
+
+        let blue = if temperature >= 66.0 {
+            255.0
+        } else if temperature <= 19.0 {
+            0.0
+        } else {
+            138.5177312231 * (temperature - 10.0).ln() - 305.0447927307
+        };
+
+        Self::new(red / 255.0, green / 255.0, blue / 255.0)
+    }
+
+    // The correlated color temperature of the color using McCamy's
+    // approximation, treating the factors as sRGB.
+    fn to_kelvin(self) -> f64 {
+        let linear = |factor: f64| {
+            if factor <= 0.04045 {
+                factor / 12.92
+            } else {
+                ((factor + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (red, green, blue) = (linear(self.red), linear(self.green), linear(self.blue));
+
+        let x = 0.4124 * red + 0.3576 * green + 0.1805 * blue;
+        let y = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+        let z = 0.0193 * red + 0.1192 * green + 0.9505 * blue;
+
+        let sum = x + y + z;
+        if sum == 0.0 {
+            return DEFAULT_KELVIN;
+        }
+
+        let (x, y) = (x / sum, y / sum);
+        let n = (x - 0.3320) / (0.1858 - y);
+        let kelvin = 449.0 * n.powi(3) + 3525.0 * n.powi(2) + 6823.3 * n + 5520.33;
+
+        kelvin.clamp(MIN_KELVIN, MAX_KELVIN)
+    }
+
+    fn to_hsi(self) -> (f64, f64, f64) {
+        let max = self.red.max(self.green).max(self.blue);
+        let min = self.red.min(self.green).min(self.blue);
+        let chroma = max - min;
+
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == self.red {
+            ((self.green - self.blue) / chroma).rem_euclid(6.0)
+        } else if max == self.green {
+            (self.blue - self.red) / chroma + 2.0
+        } else {
+            (self.red - self.green) / chroma + 4.0
+        };
+
+        let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+
+        (hue / 6.0, saturation, max)
+    }
 }
 
 #[derive(Debug)]
@@ -36,9 +352,48 @@ impl Color {
         }
     }
 
-    pub fn convert_to(self, _target_colorspace: &Colorspace) -> Self {
-        // TODO: Do the actual color conversion
-        self
+    // Converts the color to the target colorspace, using the parameters of
+    // the profile to know the range of each value. Colors already in the
+    // target colorspace are left as they are.
+    pub fn convert_to(self, target_colorspace: &Colorspace, profile: &FixtureProfile) -> Self {
+        if self.colorspace == *target_colorspace {
+            return self;
+        }
+
+        // Any components that haven't been programmed are at their default
+        let components: Vec<f64> = self
+            .colorspace
+            .params()
+            .iter()
+            .map(|param| {
+                let parameter = Colorspace::parameter_for(profile, param);
+                let value = match self.get_value(param) {
+                    Some(value) => value.clone(),
+                    None => Values::make_literal(parameter.default()),
+                };
+
+                Colorspace::component(param, &value, &parameter)
+            })
+            .collect();
+
+        let rgb = self.colorspace.rgb_from_components(&components);
+
+        let mut color = Color::new(*target_colorspace);
+
+        for (param, component) in target_colorspace
+            .params()
+            .iter()
+            .zip(target_colorspace.components_from_rgb(rgb))
+        {
+            let parameter = Colorspace::parameter_for(profile, param);
+
+            color.set(
+                *param,
+                Colorspace::component_value(param, component, &parameter),
+            );
+        }
+
+        color
     }
 
     pub fn set(&mut self, parameter: Param, value: Values) {
@@ -52,4 +407,92 @@ impl Color {
     pub fn values(&self) -> &HashMap<Param, Values> {
         &self.values
     }
+
+    pub fn colorspace(&self) -> &Colorspace {
+        &self.colorspace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rgb_eq(a: Rgb, b: Rgb) {
+        assert!((a.red - b.red).abs() < 1e-9, "{:?} != {:?}", a, b);
+        assert!((a.green - b.green).abs() < 1e-9, "{:?} != {:?}", a, b);
+        assert!((a.blue - b.blue).abs() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn hsi_primaries() {
+        assert_rgb_eq(Rgb::from_hsi(0.0, 1.0, 1.0), Rgb::new(1.0, 0.0, 0.0));
+        assert_rgb_eq(Rgb::from_hsi(1.0 / 3.0, 1.0, 1.0), Rgb::new(0.0, 1.0, 0.0));
+        assert_rgb_eq(Rgb::from_hsi(2.0 / 3.0, 1.0, 1.0), Rgb::new(0.0, 0.0, 1.0));
+        assert_rgb_eq(Rgb::from_hsi(0.5, 0.0, 0.5), Rgb::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn every_colorspace_round_trips_through_rgb() {
+        let rgb = Rgb::new(0.9, 0.6, 0.2);
+
+        for colorspace in [
+            Colorspace::RGB,
+            Colorspace::RGBA,
+            Colorspace::RGBW,
+            Colorspace::RGBAW,
+            Colorspace::CMY,
+            Colorspace::HSI,
+        ] {
+            let factors = colorspace.components_from_rgb(rgb);
+
+            assert_eq!(factors.len(), colorspace.params().len());
+            assert_rgb_eq(colorspace.rgb_from_components(&factors), rgb);
+        }
+    }
+
+    #[test]
+    fn white_is_extracted_before_amber() {
+        let factors = Colorspace::RGBAW.components_from_rgb(Rgb::new(1.0, 1.0, 1.0));
+
+        assert_eq!(factors, vec![0.0, 0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn params_without_generators_are_not_programmed() {
+        use crate::value::generator::{BoxedGenerator, Static};
+
+        let red: BoxedGenerator = Box::new(Static::new(Values::make_literal(100.0)));
+        let mut parameters = ParameterMap::new();
+        parameters.insert(Param::Red, vec![red]);
+        parameters.insert(Param::Amber, Vec::new());
+
+        assert_eq!(Colorspace::detect(&parameters, None), Some(Colorspace::RGB));
+    }
+
+    #[test]
+    fn kelvin_to_rgb() {
+        assert_rgb_eq(Rgb::from_kelvin(6600.0), Rgb::new(1.0, 1.0, 1.0));
+
+        let tungsten = Rgb::from_kelvin(3200.0);
+        assert_eq!(tungsten.red, 1.0);
+        assert!(tungsten.green > tungsten.blue);
+        assert!(tungsten.blue > 0.0);
+
+        assert_eq!(Rgb::from_kelvin(1500.0).blue, 0.0);
+    }
+
+    #[test]
+    fn rgb_to_kelvin() {
+        for kelvin in [2700.0, 3200.0, 4300.0, 5600.0] {
+            let converted = Rgb::from_kelvin(kelvin).to_kelvin();
+            assert!(
+                (converted - kelvin).abs() < kelvin * 0.1,
+                "{} converted to {}",
+                kelvin,
+                converted
+            );
+        }
+
+        assert_eq!(Rgb::new(0.0, 0.0, 0.0).to_kelvin(), DEFAULT_KELVIN);
+    }
 }
@@ -12,6 +12,19 @@ impl Dmx {
         Self((255.0 * factor).round() as u8)
     }
 
+    // Splits a factor over multiple channels, with the most significant byte
+    // first, e.g. a resolution of 2 gives a 16 bit coarse and fine pair.
+    pub fn from_factor_with_resolution(factor: f64, resolution: usize) -> Vec<Self> {
+        let bits = 8 * resolution as u32;
+        let max = ((1_u64 << bits) - 1) as f64;
+        let value = (max * factor.clamp(0.0, 1.0)).round() as u64;
+
+        (0..resolution)
+            .rev()
+            .map(|byte| Self((value >> (8 * byte)) as u8))
+            .collect()
+    }
+
     pub fn byte(&self) -> u8 {
         self.0
     }
@@ -41,7 +54,7 @@ impl DmxString {
         self.len() == 0
     }
 
-    pub fn iter(&self) -> Iter<Dmx> {
+    pub fn iter(&self) -> Iter<'_, Dmx> {
         self.string.iter()
     }
 }
@@ -8,7 +8,7 @@ use std::collections::hash_map::Iter;
 use std::collections::HashMap;
 use std::fmt::Debug;
 
-use crate::parameter::{Param, Parameter};
+use crate::parameter::Param;
 use crate::patch::FixtureProfile;
 use crate::value::Values;
 
@@ -21,6 +21,9 @@ type ResolvedParameterMap = HashMap<Param, Values>;
 pub struct Fixture {
     id: FixtureID,
     parameters: ParameterMap,
+    // The last color param applied, to know which colorspace is being used
+    // when more than one is programmed at the same time.
+    last_color: Option<Param>,
 }
 
 impl Fixture {
@@ -28,6 +31,7 @@ impl Fixture {
         Self {
             id,
             parameters: ParameterMap::new(),
+            last_color: None,
         }
     }
 
@@ -36,6 +40,10 @@ impl Fixture {
     }
 
     pub fn apply(&mut self, apply: &Apply) {
+        if apply.parameter.is_color() {
+            self.last_color = Some(apply.parameter);
+        }
+
         self.set(apply.parameter, apply.generator.clone());
     }
 
@@ -63,6 +71,12 @@ impl Fixture {
         &self.parameters
     }
 
+    // The colorspace the fixture is being programmed in, if any color params
+    // have been programmed.
+    pub fn colorspace(&self) -> Option<Colorspace> {
+        Colorspace::detect(&self.parameters, self.last_color)
+    }
+
     pub fn resolve(&mut self, time: &Time, profile: &FixtureProfile) -> ResolvedFixture {
         let mut resolved_fixture = ResolvedFixture::new(self.id);
 
@@ -118,47 +132,58 @@ impl Fixture {
         time: &Time,
         profile: &FixtureProfile,
     ) {
-        if let Some(profile_colorspace) = profile.colorspace() {
-            // detect from params which colorspace is being used
-            let current_colorspace = Colorspace::detect(&self.parameters);
+        // detect from params which colorspace is being used
+        let current_colorspace = match self.colorspace() {
+            Some(colorspace) => colorspace,
+            None => return,
+        };
 
+        if let Some(profile_colorspace) = profile.colorspace() {
             // construct a resolved color object from colors that are present from that colorspace
             let mut color = Color::new(current_colorspace);
 
-            for (param, generators) in self.parameters.iter_mut().filter(|(p, _g)| {
-                Colorspace::params_for_colorspace(&current_colorspace).contains(p)
-            }) {
-                if let Some(parameter) = profile.get_parameter(param) {
-                    let mut latest_time = Time::at(0, 0, 0, 0);
-
-                    for generator in generators {
-                        // Resolve any current values with the current parameter value
-                        match color.get_value(param) {
-                            Some(value) => {
-                                generator.resolve(value, time);
-                            }
-                            None => {
-                                generator.resolve(&Values::make_literal(parameter.default()), time);
-                            }
+            for (param, generators) in self
+                .parameters
+                .iter_mut()
+                .filter(|(p, _g)| current_colorspace.params().contains(p))
+            {
+                // The profile may not have the params of the colorspace being
+                // programmed, which are resolved against a virtual parameter
+                // before being converted.
+                let parameter = profile
+                    .get_parameter(param)
+                    .cloned()
+                    .unwrap_or_else(|| Colorspace::virtual_parameter(param));
+
+                let mut latest_time = Time::at(0, 0, 0, 0);
+
+                for generator in generators {
+                    // Resolve any current values with the current parameter value
+                    match color.get_value(param) {
+                        Some(value) => {
+                            generator.resolve(value, time);
+                        }
+                        None => {
+                            generator.resolve(&Values::make_literal(parameter.default()), time);
                         }
+                    }
 
-                        // If a generator returns None, we keep the previous value
-                        if let Some(value) = generator.generate(time, parameter) {
-                            if generator.start_time() >= latest_time {
-                                latest_time = generator.start_time();
-                                color.set(*param, value);
-                            }
+                    // If a generator returns None, we keep the previous value
+                    if let Some(value) = generator.generate(time, &parameter) {
+                        if generator.start_time() >= latest_time {
+                            latest_time = generator.start_time();
+                            color.set(*param, value);
                         }
                     }
                 }
             }
 
             // convert that color object to profile colorspace if needed
-            let color = color.convert_to(profile_colorspace);
+            let color = color.convert_to(profile_colorspace, profile);
 
             // apply those colors to the resolving fixture
             for (param, value) in color.values() {
-                resolved_fixture.set(*param, *value)
+                resolved_fixture.set(*param, value.clone())
             }
         }
     }
@@ -180,6 +205,7 @@ impl Debug for Fixture {
 impl Clone for Fixture {
     fn clone(&self) -> Self {
         let mut fixture = Fixture::new(self.id);
+        fixture.last_color = self.last_color;
 
         for (param, generators) in self.parameters.iter() {
             for generator in generators {
@@ -191,7 +217,7 @@ impl Clone for Fixture {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Clone, PartialEq, Serialize)]
 pub struct ResolvedFixture {
     id: FixtureID,
     parameters: ResolvedParameterMap,
@@ -213,7 +239,11 @@ impl ResolvedFixture {
         self.parameters.get(parameter)
     }
 
-    pub fn values(&self) -> Iter<Param, Values> {
+    pub fn remove(&mut self, parameter: &Param) -> Option<Values> {
+        self.parameters.remove(parameter)
+    }
+
+    pub fn values(&self) -> Iter<'_, Param, Values> {
         self.parameters.iter()
     }
 }
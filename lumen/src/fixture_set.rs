@@ -1,4 +1,9 @@
-use crate::{action::Action, query::Query, timecode::time::Time};
+use crate::{
+    action::{Action, Apply, ApplyStep},
+    query::Query,
+    spread::Selection,
+    timecode::time::Time,
+};
 use std::collections::{
     hash_map::{Iter, IterMut},
     HashMap, HashSet,
@@ -34,11 +39,11 @@ impl FixtureSet {
         self.fixtures.insert(id, fixture);
     }
 
-    pub fn all(&mut self) -> IterMut<FixtureID, Fixture> {
+    pub fn all(&mut self) -> IterMut<'_, FixtureID, Fixture> {
         self.fixtures.iter_mut()
     }
 
-    pub fn all_ref(&self) -> Iter<FixtureID, Fixture> {
+    pub fn all_ref(&self) -> Iter<'_, FixtureID, Fixture> {
         self.fixtures.iter()
     }
 
@@ -49,10 +54,17 @@ impl FixtureSet {
         }
     }
 
+    // Fixtures that aren't patched have no profile to resolve against, so
+    // are left out of the resolved map.
     pub fn resolve(&mut self, time: Time, patch: &Patch) -> ResolvedFixtureMap {
         self.fixtures
             .iter_mut()
-            .map(|(i, f)| (*i, f.resolve(&time, patch.get_profile(i))))
+            .filter_map(|(i, f)| {
+                patch
+                    .get_profile(i)
+                    .ok()
+                    .map(|profile| (*i, f.resolve(&time, profile)))
+            })
             .collect()
     }
 
@@ -65,26 +77,48 @@ impl FixtureSet {
         let current_state = self.resolve(time, patch);
 
         for apply_group in action.apply_groups.iter() {
-            for (id, fixture) in self.query(&apply_group.query) {
-                for apply in apply_group.applies.iter() {
-                    let mut apply = apply.clone();
-
-                    // If we are applying to a fixture, parameter pair for the first time in this apply,
-                    // we should empty it of previous generators.
-                    if !visited.contains(&(*id, apply.parameter)) {
-                        fixture.clear_parameter(&apply.parameter);
-                        visited.push((*id, apply.parameter));
-
-                        // If we are visiting a parameter pair for the first time, then we should resolve the generator with
-                        // the current value.
-                        apply.resolve(
-                            current_state.get(id).unwrap().get_value(&apply.parameter),
-                            &time,
-                        )
+            // The selection is only ordered once, for every apply to it.
+            let selection = Selection::new(&apply_group.query.evaluate_ordered(&self.ids()));
+
+            for (id, fixture) in self
+                .fixtures
+                .iter_mut()
+                .filter(|(id, _)| selection.contains(id))
+            {
+                for step in apply_group.applies.iter() {
+                    let applies = match step {
+                        ApplyStep::Apply(apply) => vec![apply.clone()],
+                        // Homing applies to whatever has been programmed on
+                        // the fixture so far, as anything else is already at
+                        // home.
+                        ApplyStep::Home(generator) => fixture
+                            .parameters()
+                            .keys()
+                            .map(|param| Apply::new(*param, generator.clone()))
+                            .collect(),
+                    };
+
+                    for mut apply in applies {
+                        // If we are applying to a fixture, parameter pair for the first time in this apply,
+                        // we should empty it of previous generators.
+                        if !visited.contains(&(*id, apply.parameter)) {
+                            fixture.clear_parameter(&apply.parameter);
+                            visited.push((*id, apply.parameter));
+
+                            // If we are visiting a parameter pair for the first time, then we should resolve the generator with
+                            // the current value.
+                            apply.resolve(
+                                current_state
+                                    .get(id)
+                                    .and_then(|fixture| fixture.get_value(&apply.parameter)),
+                                &time,
+                            )
+                        }
+
+                        apply.spread_over(id, &selection);
+                        apply.set_start_time(time);
+                        fixture.apply(&apply);
                     }
-
-                    apply.set_start_time(time);
-                    fixture.apply(&apply);
                 }
             }
         }
@@ -0,0 +1,514 @@
+// Specification: https://tsp.esta.org/tsp/documents/docs/ANSI_E1-31-2018.pdf
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    net::{Ipv4Addr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use byteorder::{ByteOrder, NetworkEndian};
+use uuid::Uuid;
+
+use crate::{
+    output::sacn::{
+        multicast_address, ACN_PACKET_IDENTIFIER, DMP_ADDRESS_DATA_TYPE, DMX_START_CODE,
+        MAX_PACKET_LENGTH, OPT_PREVIEW_DATA, OPT_STREAM_TERMINATED, VECTOR_DMP_SET_PROPERTY,
+        VECTOR_E131_DATA_PACKET, VECTOR_ROOT_E131_DATA,
+    },
+    universe::Universe,
+};
+
+// Appendix A: Defined Parameters
+// A source is lost when no data has been received from it for this long.
+pub const E131_NETWORK_DATA_LOSS_TIMEOUT: Duration = Duration::from_millis(2500);
+
+// The root layer up to its vector, which tells us the type of packet.
+const ROOT_LAYER_LENGTH: usize = 22;
+// The smallest data packet, carrying only the start code.
+const MIN_DATA_PACKET_LENGTH: usize = 126;
+
+#[derive(Debug, PartialEq)]
+pub enum PacketError {
+    TooShort(usize),
+    InvalidIdentifier,
+    UnsupportedVector(u32),
+    UnsupportedStartCode(u8),
+    InvalidUniverse(u16),
+}
+
+impl Display for PacketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PacketError::TooShort(length) => write!(f, "packet of {} bytes is too short", length),
+            PacketError::InvalidIdentifier => write!(f, "packet is not an ACN packet"),
+            PacketError::UnsupportedVector(vector) => {
+                write!(f, "unsupported vector {:#010x}", vector)
+            }
+            PacketError::UnsupportedStartCode(start_code) => {
+                write!(f, "unsupported start code {:#04x}", start_code)
+            }
+            PacketError::InvalidUniverse(universe) => write!(f, "invalid universe {}", universe),
+        }
+    }
+}
+
+impl std::error::Error for PacketError {}
+
+#[derive(Debug)]
+pub enum ReceiveError {
+    Io(std::io::Error),
+    Packet(PacketError),
+}
+
+impl Display for ReceiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReceiveError::Io(err) => write!(f, "could not receive: {}", err),
+            ReceiveError::Packet(err) => write!(f, "could not parse: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReceiveError {}
+
+impl From<std::io::Error> for ReceiveError {
+    fn from(err: std::io::Error) -> Self {
+        ReceiveError::Io(err)
+    }
+}
+
+impl From<PacketError> for ReceiveError {
+    fn from(err: PacketError) -> Self {
+        ReceiveError::Packet(err)
+    }
+}
+
+// The parts of an E1.31 data packet we need to receive a universe.
+#[derive(Debug, PartialEq)]
+pub struct ReceivedPacket {
+    pub cid: Uuid,
+    pub source_name: String,
+    pub priority: u8,
+    pub sync_address: u16,
+    pub seq_number: u8,
+    pub options: u8,
+    pub universe: Universe,
+}
+
+impl ReceivedPacket {
+    pub fn parse(buf: &[u8]) -> Result<Self, PacketError> {
+        if buf.len() < ROOT_LAYER_LENGTH {
+            return Err(PacketError::TooShort(buf.len()));
+        }
+
+        if buf[4..16] != ACN_PACKET_IDENTIFIER {
+            return Err(PacketError::InvalidIdentifier);
+        }
+
+        // Synchronization and discovery packets use the extended vector, we
+        // only receive data.
+        let root_vector = NetworkEndian::read_u32(&buf[18..22]);
+        if root_vector != VECTOR_ROOT_E131_DATA {
+            return Err(PacketError::UnsupportedVector(root_vector));
+        }
+
+        if buf.len() < MIN_DATA_PACKET_LENGTH {
+            return Err(PacketError::TooShort(buf.len()));
+        }
+
+        let framing_vector = NetworkEndian::read_u32(&buf[40..44]);
+        if framing_vector != VECTOR_E131_DATA_PACKET {
+            return Err(PacketError::UnsupportedVector(framing_vector));
+        }
+
+        if buf[117] != VECTOR_DMP_SET_PROPERTY || buf[118] != DMP_ADDRESS_DATA_TYPE {
+            return Err(PacketError::UnsupportedVector(buf[117] as u32));
+        }
+
+        // Alternate start codes, such as per-slot priority, are not dmx.
+        if buf[125] != DMX_START_CODE {
+            return Err(PacketError::UnsupportedStartCode(buf[125]));
+        }
+
+        let universe = NetworkEndian::read_u16(&buf[113..115]);
+        if !(1..=63999).contains(&universe) {
+            return Err(PacketError::InvalidUniverse(universe));
+        }
+
+        // The property value count includes the start code.
+        let slots = (NetworkEndian::read_u16(&buf[123..125]) as usize).saturating_sub(1);
+        let end = MIN_DATA_PACKET_LENGTH + slots.min(512);
+        if buf.len() < end {
+            return Err(PacketError::TooShort(buf.len()));
+        }
+
+        let mut cid = [0; 16];
+        cid.copy_from_slice(&buf[22..38]);
+
+        Ok(Self {
+            cid: Uuid::from_bytes(cid),
+            source_name: parse_source_name(&buf[44..108]),
+            priority: buf[108],
+            sync_address: NetworkEndian::read_u16(&buf[109..111]),
+            seq_number: buf[111],
+            options: buf[112],
+            universe: Universe::from_bytes(universe as usize, &buf[MIN_DATA_PACKET_LENGTH..end]),
+        })
+    }
+
+    pub fn universe_number(&self) -> u16 {
+        self.universe.universe_number() as u16
+    }
+}
+
+fn parse_source_name(buf: &[u8]) -> String {
+    let name = buf.split(|byte| *byte == 0x00).next().unwrap_or_default();
+    String::from_utf8_lossy(name).trim_end().to_string()
+}
+
+// The latest data from one source for one universe.
+#[derive(Debug)]
+pub struct Source {
+    cid: Uuid,
+    name: String,
+    priority: u8,
+    seq_number: u8,
+    last_received: Instant,
+    universe: Universe,
+}
+
+impl Source {
+    pub fn cid(&self) -> Uuid {
+        self.cid
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+
+    pub fn last_received(&self) -> Instant {
+        self.last_received
+    }
+}
+
+pub struct SacnReceiver {
+    socket: UdpSocket,
+    sources: HashMap<(Uuid, u16), Source>,
+    timeout: Duration,
+}
+
+impl SacnReceiver {
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self, std::io::Error> {
+        Ok(Self::from_socket(UdpSocket::bind(addr)?))
+    }
+
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            sources: HashMap::new(),
+            timeout: E131_NETWORK_DATA_LOSS_TIMEOUT,
+        }
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    // Universes are multicast to their own group, which we have to join to
+    // receive them.
+    pub fn join_universe(&self, universe: u16) -> Result<(), std::io::Error> {
+        self.socket
+            .join_multicast_v4(&multicast_address(universe), &Ipv4Addr::UNSPECIFIED)
+    }
+
+    pub fn leave_universe(&self, universe: u16) -> Result<(), std::io::Error> {
+        self.socket
+            .leave_multicast_v4(&multicast_address(universe), &Ipv4Addr::UNSPECIFIED)
+    }
+
+    // Blocks for the next packet on the socket, returning the universe number
+    // if it updated a source. Packets that are not dmx data are ignored.
+    pub fn receive(&mut self) -> Result<Option<u16>, ReceiveError> {
+        let mut buf = [0; MAX_PACKET_LENGTH];
+        let length = self.socket.recv(&mut buf)?;
+
+        match ReceivedPacket::parse(&buf[..length]) {
+            Ok(packet) => Ok(self.process(packet, Instant::now())),
+            Err(PacketError::UnsupportedVector(_)) | Err(PacketError::UnsupportedStartCode(_)) => {
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn process(&mut self, packet: ReceivedPacket, now: Instant) -> Option<u16> {
+        let universe = packet.universe_number();
+        let key = (packet.cid, universe);
+
+        // 6.2.6 Preview data shall not be used to generate live output.
+        if packet.options & OPT_PREVIEW_DATA != 0 {
+            return None;
+        }
+
+        // 6.2.6 A terminated stream is lost straight away, rather than waiting
+        // for it to time out.
+        if packet.options & OPT_STREAM_TERMINATED != 0 {
+            self.sources.remove(&key);
+            return None;
+        }
+
+        if let Some(source) = self.sources.get(&key) {
+            if is_out_of_order(source.seq_number, packet.seq_number) {
+                return None;
+            }
+        }
+
+        self.sources.insert(
+            key,
+            Source {
+                cid: packet.cid,
+                name: packet.source_name,
+                priority: packet.priority,
+                seq_number: packet.seq_number,
+                last_received: now,
+                universe: packet.universe,
+            },
+        );
+
+        Some(universe)
+    }
+
+    // Removes every source that has not been heard from within the timeout,
+    // returning the sources that were lost.
+    pub fn expire_sources(&mut self, now: Instant) -> Vec<Source> {
+        let lost: Vec<(Uuid, u16)> = self
+            .sources
+            .iter()
+            .filter(|(_, source)| now.duration_since(source.last_received) > self.timeout)
+            .map(|(key, _)| *key)
+            .collect();
+
+        lost.iter()
+            .filter_map(|key| self.sources.remove(key))
+            .collect()
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &Source> {
+        self.sources.values()
+    }
+
+    pub fn sources_for(&self, universe: u16) -> impl Iterator<Item = &Source> {
+        self.sources
+            .iter()
+            .filter(move |((_, source_universe), _)| *source_universe == universe)
+            .map(|(_, source)| source)
+    }
+}
+
+// 6.7.2 Sequence Numbering
+// A packet is out of order if it is not newer than the last packet, but is
+// within 20 packets of it, otherwise the source is assumed to have restarted.
+fn is_out_of_order(last: u8, received: u8) -> bool {
+    let difference = received.wrapping_sub(last) as i8;
+    difference <= 0 && difference > -20
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{
+        sacn::{DataPacket, SyncPacket, SYNC_PACKET_LENGTH},
+        Output, SACN,
+    };
+
+    const CID: [u8; 16] = [0xab; 16];
+
+    fn packet(universe: usize, seq_number: u8, options: u8, first: u8) -> ReceivedPacket {
+        let mut bytes = [0; 512];
+        bytes[0] = first;
+        let universe = Universe::from_bytes(universe, &bytes);
+
+        let mut buf = [0; MAX_PACKET_LENGTH];
+        DataPacket::new(&universe, "Backup", Some(120), 0, seq_number, options, &CID)
+            .pack(&mut buf);
+
+        ReceivedPacket::parse(&buf).unwrap()
+    }
+
+    fn receiver() -> SacnReceiver {
+        SacnReceiver::bind("127.0.0.1:0").unwrap()
+    }
+
+    #[test]
+    fn parses_data_packets() {
+        let packet = packet(3, 9, 0, 255);
+
+        assert_eq!(packet.cid, Uuid::from_bytes(CID));
+        assert_eq!(packet.source_name, "Backup");
+        assert_eq!(packet.priority, 120);
+        assert_eq!(packet.seq_number, 9);
+        assert_eq!(packet.universe_number(), 3);
+        assert_eq!(packet.universe.bytes()[0], 255);
+        assert_eq!(packet.universe.bytes()[1], 0);
+    }
+
+    #[test]
+    fn parses_partial_universes() {
+        let universe = Universe::from_bytes(1, &[10; 512]);
+        let mut buf = [0; MAX_PACKET_LENGTH];
+        DataPacket::new(&universe, "Backup", None, 0, 0, 0, &CID).pack(&mut buf);
+
+        // Only send 4 slots, plus the start code
+        NetworkEndian::write_u16(&mut buf[123..125], 5);
+        let packet = ReceivedPacket::parse(&buf[..130]).unwrap();
+
+        assert_eq!(&packet.universe.bytes()[0..5], &[10, 10, 10, 10, 0]);
+    }
+
+    #[test]
+    fn rejects_packets_that_are_not_data() {
+        let mut buf = [0; MAX_PACKET_LENGTH];
+
+        assert_eq!(
+            ReceivedPacket::parse(&buf[..20]),
+            Err(PacketError::TooShort(20))
+        );
+        assert_eq!(
+            ReceivedPacket::parse(&buf),
+            Err(PacketError::InvalidIdentifier)
+        );
+
+        SyncPacket::new(1, 0, &CID).pack(&mut buf);
+        assert_eq!(
+            ReceivedPacket::parse(&buf[..SYNC_PACKET_LENGTH]),
+            Err(PacketError::UnsupportedVector(0x0000_0008))
+        );
+    }
+
+    #[test]
+    fn rejects_alternate_start_codes() {
+        let universe = Universe::from_bytes(1, &[]);
+        let mut buf = [0; MAX_PACKET_LENGTH];
+        DataPacket::new(&universe, "Backup", None, 0, 0, 0, &CID).pack(&mut buf);
+        buf[125] = 0xdd;
+
+        assert_eq!(
+            ReceivedPacket::parse(&buf),
+            Err(PacketError::UnsupportedStartCode(0xdd))
+        );
+    }
+
+    #[test]
+    fn tracks_sources_by_cid_and_universe() {
+        let mut receiver = receiver();
+        let now = Instant::now();
+
+        assert_eq!(receiver.process(packet(1, 0, 0, 10), now), Some(1));
+        assert_eq!(receiver.process(packet(2, 0, 0, 20), now), Some(2));
+        assert_eq!(receiver.process(packet(1, 1, 0, 30), now), Some(1));
+
+        assert_eq!(receiver.sources().count(), 2);
+
+        let source = receiver.sources_for(1).next().unwrap();
+        assert_eq!(source.name(), "Backup");
+        assert_eq!(source.priority(), 120);
+        assert_eq!(source.universe().bytes()[0], 30);
+    }
+
+    #[test]
+    fn drops_out_of_order_packets() {
+        let mut receiver = receiver();
+        let now = Instant::now();
+
+        receiver.process(packet(1, 10, 0, 10), now);
+
+        assert_eq!(receiver.process(packet(1, 9, 0, 20), now), None);
+        assert_eq!(receiver.process(packet(1, 10, 0, 20), now), None);
+        assert_eq!(
+            receiver.sources_for(1).next().unwrap().universe().bytes()[0],
+            10
+        );
+
+        // A large jump back is a restarted source
+        assert_eq!(receiver.process(packet(1, 200, 0, 30), now), Some(1));
+        // Sequence numbers wrap around
+        assert_eq!(receiver.process(packet(1, 5, 0, 40), now), Some(1));
+    }
+
+    #[test]
+    fn ignores_preview_data() {
+        let mut receiver = receiver();
+
+        let updated = receiver.process(packet(1, 0, OPT_PREVIEW_DATA, 10), Instant::now());
+
+        assert_eq!(updated, None);
+        assert_eq!(receiver.sources().count(), 0);
+    }
+
+    #[test]
+    fn terminated_streams_are_removed() {
+        let mut receiver = receiver();
+        let now = Instant::now();
+
+        receiver.process(packet(1, 0, 0, 10), now);
+        receiver.process(packet(1, 1, OPT_STREAM_TERMINATED, 10), now);
+
+        assert_eq!(receiver.sources().count(), 0);
+    }
+
+    #[test]
+    fn detects_lost_sources() {
+        let mut receiver = receiver();
+        let start = Instant::now();
+
+        receiver.process(packet(1, 0, 0, 10), start);
+        receiver.process(packet(2, 0, 0, 10), start + Duration::from_secs(2));
+
+        let lost = receiver.expire_sources(start + Duration::from_secs(3));
+
+        assert_eq!(lost.len(), 1);
+        assert_eq!(lost[0].universe().universe_number(), 1);
+        assert_eq!(receiver.sources().count(), 1);
+    }
+
+    #[test]
+    fn receives_from_the_network() {
+        let mut receiver = receiver();
+        receiver
+            .socket()
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender
+            .connect(receiver.socket().local_addr().unwrap())
+            .unwrap();
+
+        let mut sacn = SACN::new("Media Server".to_string(), Uuid::from_bytes(CID));
+        sacn.set_sync_address(Some(5));
+        sacn.send_universe(&Universe::from_bytes(4, &[1, 2, 3]), &sender)
+            .unwrap();
+        sacn.send_multiverse(&Default::default(), &sender).unwrap();
+
+        assert_eq!(receiver.receive().unwrap(), Some(4));
+        // The sync packet is not data and is ignored
+        assert_eq!(receiver.receive().unwrap(), None);
+
+        let source = receiver.sources_for(4).next().unwrap();
+        assert_eq!(source.name(), "Media Server");
+        assert_eq!(&source.universe().bytes()[0..4], &[1, 2, 3, 0]);
+    }
+}
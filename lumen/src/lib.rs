@@ -4,6 +4,7 @@ mod environment;
 pub use environment::Environment;
 pub mod action;
 pub mod address;
+pub mod capture;
 pub mod color;
 pub mod dmx;
 pub mod fixture;
@@ -11,6 +12,7 @@ pub mod fixture_set;
 pub mod history;
 pub mod parameter;
 pub mod patch;
+pub mod render;
 pub mod timecode;
 pub mod track;
 pub mod value;
@@ -20,4 +22,8 @@ pub use query::Query;
 pub mod universe;
 pub use query::query_builder::QueryBuilder;
 pub use query::query_builder::Step;
+pub mod input;
+pub mod merge;
 pub mod output;
+pub mod scheduler;
+pub mod spread;
@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::{
+    input::sacn::SacnReceiver,
+    parameter::Category,
+    universe::{Multiverse, Universe},
+    Patch,
+};
+
+const UNIVERSE_SIZE: usize = 512;
+
+// Sources share the default sACN priority unless they are given their own.
+pub const DEFAULT_PRIORITY: u8 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SourceId {
+    Engine,
+    Sacn(Uuid),
+    Named(String),
+}
+
+struct MergeSource {
+    priority: u8,
+    universe: Universe,
+    // The merge clock at which each channel last changed, so the latest
+    // change wins for LTP channels.
+    changed: [u64; UNIVERSE_SIZE],
+}
+
+// Combines the universes of many sources into a single Multiverse.
+//
+// Only the sources with the highest priority for a universe take part in its
+// merge. Between those, intensity channels are merged highest takes
+// precedence (HTP), and every other channel is merged latest takes precedence
+// (LTP).
+pub struct Merge {
+    sources: HashMap<(SourceId, usize), MergeSource>,
+    intensity: HashMap<usize, [bool; UNIVERSE_SIZE]>,
+    clock: u64,
+}
+
+impl Merge {
+    pub fn new() -> Self {
+        Self {
+            sources: HashMap::new(),
+            intensity: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn with_patch(patch: &Patch) -> Self {
+        let mut merge = Self::new();
+        merge.set_patch(patch);
+        merge
+    }
+
+    // Finds the intensity channels of every patched fixture, which are
+    // merged HTP.
+    pub fn set_patch(&mut self, patch: &Patch) {
+        self.intensity.clear();
+
+        for id in patch.fixtures() {
+            let (address, profile) = match (patch.get_address(id), patch.get_profile(id)) {
+                (Ok(address), Ok(profile)) => (address, profile),
+                _ => continue,
+            };
+
+            let channels = self
+                .intensity
+                .entry(address.universe() as usize)
+                .or_insert([false; UNIVERSE_SIZE]);
+
+            for (param, parameter) in profile.parameters() {
+                if param.category() != Category::Intensity {
+                    continue;
+                }
+
+                for offset in parameter.offsets() {
+                    if let Some(channel) = channels.get_mut(address.address_index() + offset) {
+                        *channel = true;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn is_intensity(&self, universe_number: usize, channel_index: usize) -> bool {
+        self.intensity
+            .get(&universe_number)
+            .is_some_and(|channels| channels[channel_index])
+    }
+
+    pub fn update(&mut self, source: SourceId, priority: u8, universe: &Universe) {
+        self.clock += 1;
+        let clock = self.clock;
+        let key = (source, universe.universe_number());
+
+        match self.sources.get_mut(&key) {
+            Some(existing) => {
+                let previous = existing.universe.bytes();
+
+                for (channel, (old, new)) in previous.iter().zip(universe.bytes()).enumerate() {
+                    if *old != new {
+                        existing.changed[channel] = clock;
+                    }
+                }
+
+                existing.priority = priority;
+                existing.universe = universe.clone();
+            }
+            None => {
+                self.sources.insert(
+                    key,
+                    MergeSource {
+                        priority,
+                        universe: universe.clone(),
+                        changed: [clock; UNIVERSE_SIZE],
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn update_multiverse(&mut self, source: SourceId, priority: u8, multiverse: &Multiverse) {
+        for universe in multiverse.universes() {
+            self.update(source.clone(), priority, universe);
+        }
+    }
+
+    // Takes the latest data from every source the receiver is tracking, and
+    // drops any source it has lost, as they have expired or terminated.
+    pub fn update_sacn(&mut self, receiver: &SacnReceiver) {
+        let mut tracked = HashSet::new();
+
+        for source in receiver.sources() {
+            let id = SourceId::Sacn(source.cid());
+            let key = (id.clone(), source.universe().universe_number());
+
+            // A source can change its priority without changing its data,
+            // which isn't a change to any channel.
+            match self.sources.get_mut(&key) {
+                Some(existing) if existing.universe == *source.universe() => {
+                    existing.priority = source.priority();
+                }
+                _ => self.update(id, source.priority(), source.universe()),
+            }
+
+            tracked.insert(key);
+        }
+
+        self.sources
+            .retain(|key, _| !matches!(key.0, SourceId::Sacn(_)) || tracked.contains(key));
+    }
+
+    pub fn remove_source(&mut self, source: &SourceId) {
+        self.sources.retain(|(id, _), _| id != source);
+    }
+
+    pub fn remove_universe(&mut self, source: &SourceId, universe_number: usize) {
+        self.sources.remove(&(source.clone(), universe_number));
+    }
+
+    pub fn merge(&self) -> Multiverse {
+        let mut merged: HashMap<usize, Vec<&MergeSource>> = HashMap::new();
+
+        for ((_, universe_number), source) in &self.sources {
+            let sources = merged.entry(*universe_number).or_default();
+
+            // Only the highest priority sources of a universe are merged.
+            match sources.first().map(|first| first.priority) {
+                Some(priority) if priority > source.priority => {}
+                Some(priority) if priority < source.priority => *sources = vec![source],
+                _ => sources.push(source),
+            }
+        }
+
+        let mut multiverse = Multiverse::new();
+
+        for (universe_number, sources) in merged {
+            multiverse.insert(self.merge_universe(universe_number, &sources));
+        }
+
+        multiverse
+    }
+
+    fn merge_universe(&self, universe_number: usize, sources: &[&MergeSource]) -> Universe {
+        let bytes: Vec<[u8; UNIVERSE_SIZE]> = sources
+            .iter()
+            .map(|source| source.universe.bytes())
+            .collect();
+
+        let mut merged = [0; UNIVERSE_SIZE];
+
+        for (channel, value) in merged.iter_mut().enumerate() {
+            *value = if self.is_intensity(universe_number, channel) {
+                bytes.iter().map(|bytes| bytes[channel]).max()
+            } else {
+                sources
+                    .iter()
+                    .zip(&bytes)
+                    .max_by_key(|(source, _)| source.changed[channel])
+                    .map(|(_, bytes)| bytes[channel])
+            }
+            .unwrap_or(0);
+        }
+
+        Universe::from_bytes(universe_number, &merged)
+    }
+}
+
+impl Default for Merge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
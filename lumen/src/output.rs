@@ -1,15 +1,59 @@
-use std::net::{ToSocketAddrs, UdpSocket};
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::{ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
 
-use crate::universe::Universe;
+use crate::universe::{Multiverse, Universe};
 
-use self::sacn::{DataPacket, MAX_PACKET_LENGTH};
+use self::sacn::{
+    multicast_address, DataPacket, DiscoveryPacket, SyncPacket, ACN_SDT_MULTICAST_PORT,
+    E131_DISCOVERY_UNIVERSE, E131_UNIVERSE_DISCOVERY_INTERVAL, MAX_DISCOVERY_PACKET_LENGTH,
+    MAX_PACKET_LENGTH, OPT_PREVIEW_DATA, OPT_STREAM_TERMINATED, SYNC_PACKET_LENGTH,
+};
 
+pub mod artnet;
 pub mod sacn;
 
 const UUID: &str = "ebe9c992-f874-43b5-a303-91498162881b";
 
-// TODO: Find a common trait for output types to provide same interace for
-//       different protocols, when we have more than just SACN :)
+// A protocol that can send universes of dmx over the network, such as sACN or
+// Art-Net.
+pub trait Output {
+    fn send_universe(
+        &mut self,
+        universe: &Universe,
+        socket: &UdpSocket,
+    ) -> Result<usize, std::io::Error>;
+
+    // Sends a set of universes that belong to the same frame.
+    fn send_universes(
+        &mut self,
+        universes: &[&Universe],
+        socket: &UdpSocket,
+    ) -> Result<(), std::io::Error> {
+        for universe in universes {
+            self.send_universe(universe, socket)?;
+        }
+
+        Ok(())
+    }
+
+    fn send_multiverse(
+        &mut self,
+        multiverse: &Multiverse,
+        socket: &UdpSocket,
+    ) -> Result<(), std::io::Error> {
+        let universes: Vec<&Universe> = multiverse.universes().collect();
+        self.send_universes(&universes, socket)
+    }
+}
+
+pub type BoxedOutput = Box<dyn Output + Send>;
+
+// Universes that haven't changed are still resent this often, so receivers
+// don't think we have gone away.
+pub const DEFAULT_KEEPALIVE: Duration = Duration::from_secs(1);
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum NetworkState {
@@ -20,18 +64,26 @@ pub enum NetworkState {
 
 pub struct NetworkOutput {
     socket: Option<UdpSocket>,
-    protocol: SACN,
+    protocol: BoxedOutput,
+    keepalive: Duration,
+    last_sent: HashMap<usize, Instant>,
     pub state: NetworkState,
 }
 
 impl NetworkOutput {
     pub fn new() -> Self {
+        Self::with_protocol(Box::new(SACN::new(
+            "Candela Test".to_string(),
+            uuid::Uuid::parse_str(UUID).unwrap(),
+        )))
+    }
+
+    pub fn with_protocol(protocol: BoxedOutput) -> Self {
         Self {
             socket: None,
-            protocol: SACN::new(
-                "Candela Test".to_string(),
-                uuid::Uuid::parse_str(UUID).unwrap(),
-            ),
+            protocol,
+            keepalive: DEFAULT_KEEPALIVE,
+            last_sent: HashMap::new(),
             state: NetworkState::Uninitialized,
         }
     }
@@ -59,12 +111,67 @@ impl NetworkOutput {
                 }
             }
         } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotConnected,
-                "no socket",
-            ))
+            Err(Self::no_socket())
+        }
+    }
+
+    pub fn send_multiverse(&mut self, multiverse: &Multiverse) -> Result<(), std::io::Error> {
+        if let Some(socket) = &self.socket {
+            match self.protocol.send_multiverse(multiverse, socket) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    self.state = NetworkState::Bound;
+                    Err(err)
+                }
+            }
+        } else {
+            Err(Self::no_socket())
         }
     }
+
+    pub fn set_keepalive(&mut self, keepalive: Duration) {
+        self.keepalive = keepalive;
+    }
+
+    // Sends only the universes that are dirty, or that haven't been sent
+    // within the keepalive, returning how many universes were sent.
+    pub fn send_changes(&mut self, multiverse: &Multiverse) -> Result<usize, std::io::Error> {
+        let now = Instant::now();
+
+        let universes: Vec<&Universe> = multiverse
+            .universes()
+            .filter(|universe| {
+                universe.is_dirty()
+                    || match self.last_sent.get(&universe.universe_number()) {
+                        Some(last_sent) => now.duration_since(*last_sent) >= self.keepalive,
+                        None => true,
+                    }
+            })
+            .collect();
+
+        if universes.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(socket) = &self.socket {
+            if let Err(err) = self.protocol.send_universes(&universes, socket) {
+                self.state = NetworkState::Bound;
+                return Err(err);
+            }
+        } else {
+            return Err(Self::no_socket());
+        }
+
+        for universe in &universes {
+            self.last_sent.insert(universe.universe_number(), now);
+        }
+
+        Ok(universes.len())
+    }
+
+    fn no_socket() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::NotConnected, "no socket")
+    }
 }
 
 impl Default for NetworkOutput {
@@ -73,13 +180,20 @@ impl Default for NetworkOutput {
     }
 }
 
-// TODO: Implementing a simple unicast for universe output, but should also
-//       support universe multicasting
-
 pub struct SACN {
     source_name: String,
     cid: uuid::Uuid,
-    seq_number: u8,
+    // 6.7.2 Each universe has its own sequence of data packets.
+    seq_numbers: HashMap<u16, u8>,
+    sync_seq_number: u8,
+    priority: Option<u8>,
+    universe_priorities: HashMap<usize, u8>,
+    sync_address: u16,
+    options: u8,
+    multicast: bool,
+    discovery: bool,
+    last_discovery: Option<Instant>,
+    transmitting: BTreeSet<u16>,
 }
 
 impl SACN {
@@ -87,38 +201,214 @@ impl SACN {
         Self {
             source_name,
             cid,
-            seq_number: 0,
+            seq_numbers: HashMap::new(),
+            sync_seq_number: 0,
+            priority: None,
+            universe_priorities: HashMap::new(),
+            sync_address: 0,
+            options: 0x0,
+            multicast: false,
+            discovery: false,
+            last_discovery: None,
+            transmitting: BTreeSet::new(),
         }
     }
 
-    pub fn send_universe(
+    // The priority for any universe without its own priority, None sends the
+    // default priority of 100.
+    pub fn set_priority(&mut self, priority: Option<u8>) {
+        self.priority = priority;
+    }
+
+    pub fn set_universe_priority(&mut self, universe: usize, priority: u8) {
+        self.universe_priorities.insert(universe, priority);
+    }
+
+    pub fn clear_universe_priority(&mut self, universe: usize) {
+        self.universe_priorities.remove(&universe);
+    }
+
+    pub fn set_preview(&mut self, preview: bool) {
+        if preview {
+            self.options |= OPT_PREVIEW_DATA;
+        } else {
+            self.options &= !OPT_PREVIEW_DATA;
+        }
+    }
+
+    // When a sync address is set, receivers hold on to data packets until the
+    // synchronization packet sent after every multiverse arrives, so all
+    // universes change on the same frame.
+    pub fn set_sync_address(&mut self, sync_address: Option<u16>) {
+        self.sync_address = sync_address.unwrap_or(0);
+    }
+
+    // Multicast sends each universe to its own multicast group, rather than to
+    // the address the socket is connected to.
+    pub fn set_multicast(&mut self, multicast: bool) {
+        self.multicast = multicast;
+    }
+
+    // Universe discovery lists the universes we are sending every
+    // E131_UNIVERSE_DISCOVERY_INTERVAL, so they can be seen on the network.
+    // It is off by default, as it is only useful when multicasting, and
+    // would otherwise be sent to a receiver that didn't ask for it.
+    pub fn set_discovery(&mut self, discovery: bool) {
+        self.discovery = discovery;
+    }
+
+    // Lists every universe we have sent data for and not terminated.
+    pub fn send_discovery(&mut self, socket: &UdpSocket) -> Result<(), std::io::Error> {
+        let universes: Vec<u16> = self.transmitting.iter().copied().collect();
+
+        for page in DiscoveryPacket::pages(&self.source_name, &universes, self.cid.as_bytes()) {
+            let mut buf = [0; MAX_DISCOVERY_PACKET_LENGTH];
+            page.pack(&mut buf);
+
+            self.send(
+                &buf[..page.packet_length()],
+                E131_DISCOVERY_UNIVERSE,
+                socket,
+            )?;
+        }
+
+        self.last_discovery = Some(Instant::now());
+
+        Ok(())
+    }
+
+    fn discovery_due(&self) -> bool {
+        self.discovery
+            && match self.last_discovery {
+                Some(last_discovery) => {
+                    last_discovery.elapsed() >= E131_UNIVERSE_DISCOVERY_INTERVAL
+                }
+                None => true,
+            }
+    }
+
+    pub fn pack_data_packet(&mut self, buf: &mut [u8], universe: &Universe) {
+        self.pack_data_packet_with_options(buf, universe, self.options);
+    }
+
+    pub fn pack_sync_packet(&mut self, buf: &mut [u8]) {
+        let packet = SyncPacket::new(self.sync_address, self.sync_seq_number, self.cid.as_bytes());
+
+        packet.pack(buf);
+    }
+
+    // Tell receivers the universe will no longer be sent, rather than letting
+    // them wait for the stream to time out. The specification asks for three
+    // terminating packets to be sent.
+    pub fn terminate_universe(
         &mut self,
         universe: &Universe,
         socket: &UdpSocket,
-    ) -> Result<usize, std::io::Error> {
-        let mut buf = [0; MAX_PACKET_LENGTH];
-        self.pack_data_packet(&mut buf, universe);
+    ) -> Result<(), std::io::Error> {
+        for _ in 0..3 {
+            let mut buf = [0; MAX_PACKET_LENGTH];
+            self.pack_data_packet_with_options(
+                &mut buf,
+                universe,
+                self.options | OPT_STREAM_TERMINATED,
+            );
 
-        self.seq_number = self.seq_number.wrapping_add(1);
+            self.next_seq_number(universe);
+
+            self.send(&buf, universe.universe_number() as u16, socket)?;
+        }
 
-        // TODO: Propogate this error much more nicely and deal with it in the
-        //       common network output, reseting state to an unconnected state
-        //       if necessary.
-        socket.send(&buf)
+        self.transmitting
+            .remove(&(universe.universe_number() as u16));
+
+        Ok(())
     }
 
-    pub fn pack_data_packet(&mut self, buf: &mut [u8], universe: &Universe) {
-        // TODO: Support the various options in the struct below
+    fn seq_number(&self, universe: &Universe) -> u8 {
+        self.seq_numbers
+            .get(&(universe.universe_number() as u16))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn next_seq_number(&mut self, universe: &Universe) {
+        let seq_number = self
+            .seq_numbers
+            .entry(universe.universe_number() as u16)
+            .or_insert(0);
+        *seq_number = seq_number.wrapping_add(1);
+    }
+
+    fn pack_data_packet_with_options(&self, buf: &mut [u8], universe: &Universe, options: u8) {
+        let priority = self
+            .universe_priorities
+            .get(&universe.universe_number())
+            .copied()
+            .or(self.priority);
+
         let packet = DataPacket::new(
             universe,
             &self.source_name,
-            None,
-            0,
-            self.seq_number,
-            0x0,
+            priority,
+            self.sync_address,
+            self.seq_number(universe),
+            options,
             self.cid.as_bytes(),
         );
 
         packet.pack(buf);
     }
+
+    fn send_sync(&mut self, socket: &UdpSocket) -> Result<usize, std::io::Error> {
+        let mut buf = [0; SYNC_PACKET_LENGTH];
+        self.pack_sync_packet(&mut buf);
+
+        self.sync_seq_number = self.sync_seq_number.wrapping_add(1);
+
+        self.send(&buf, self.sync_address, socket)
+    }
+
+    fn send(&self, buf: &[u8], universe: u16, socket: &UdpSocket) -> Result<usize, std::io::Error> {
+        if self.multicast {
+            socket.send_to(buf, (multicast_address(universe), ACN_SDT_MULTICAST_PORT))
+        } else {
+            socket.send(buf)
+        }
+    }
+}
+
+impl Output for SACN {
+    fn send_universe(
+        &mut self,
+        universe: &Universe,
+        socket: &UdpSocket,
+    ) -> Result<usize, std::io::Error> {
+        let mut buf = [0; MAX_PACKET_LENGTH];
+        self.pack_data_packet(&mut buf, universe);
+
+        self.next_seq_number(universe);
+        self.transmitting.insert(universe.universe_number() as u16);
+
+        self.send(&buf, universe.universe_number() as u16, socket)
+    }
+
+    fn send_universes(
+        &mut self,
+        universes: &[&Universe],
+        socket: &UdpSocket,
+    ) -> Result<(), std::io::Error> {
+        for universe in universes {
+            self.send_universe(universe, socket)?;
+        }
+
+        if self.sync_address != 0 {
+            self.send_sync(socket)?;
+        }
+
+        if self.discovery_due() {
+            self.send_discovery(socket)?;
+        }
+
+        Ok(())
+    }
 }
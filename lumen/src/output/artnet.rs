@@ -0,0 +1,222 @@
+// Specification: https://art-net.org.uk/resources/art-net-specification/
+
+use std::net::UdpSocket;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::universe::Universe;
+
+use super::Output;
+
+pub const ARTNET_PORT: u16 = 6454;
+pub const ARTDMX_PACKET_LENGTH: usize = ARTDMX_HEADER_LENGTH + DMX_LENGTH;
+
+// ID - Array of 8 characters, the final character is a null termination.
+const ARTNET_ID: [u8; 8] = *b"Art-Net\0";
+// OpCode - The OpCode defines the class of data following ArtPoll within this
+// UDP packet. Transmitted low byte first.
+const OP_DMX: u16 = 0x5000;
+// ProtVerHi / ProtVerLo - Art-Net protocol revision number, currently 14.
+const PROTOCOL_VERSION: u16 = 14;
+
+const ARTDMX_HEADER_LENGTH: usize = 18;
+const DMX_LENGTH: usize = 512;
+
+// The largest port-address, which is 15 bits made of a 7 bit net, a 4 bit
+// sub-net and a 4 bit universe.
+const MAX_PORT_ADDRESS: u16 = 0x7fff;
+
+pub struct ArtNet {
+    sequence: u8,
+    physical: u8,
+}
+
+impl ArtNet {
+    pub fn new() -> Self {
+        Self {
+            // Sequence - The sequence number is used to ensure that ArtDmx
+            // packets are used in the correct order. The field is set to 0x00
+            // to disable this feature, so we start at 1.
+            sequence: 1,
+            physical: 0,
+        }
+    }
+
+    pub fn pack_dmx_packet(&self, buf: &mut [u8], universe: &Universe) {
+        let packet = DmxPacket::new(universe, self.sequence, self.physical);
+        packet.pack(buf);
+    }
+
+    fn next_sequence(&mut self) {
+        // The sequence wraps from 0xff to 0x01, skipping 0x00 which would
+        // disable sequencing at the receiver.
+        self.sequence = match self.sequence {
+            0xff => 0x01,
+            sequence => sequence + 1,
+        };
+    }
+}
+
+impl Default for ArtNet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Output for ArtNet {
+    fn send_universe(
+        &mut self,
+        universe: &Universe,
+        socket: &UdpSocket,
+    ) -> Result<usize, std::io::Error> {
+        let mut buf = [0; ARTDMX_PACKET_LENGTH];
+        self.pack_dmx_packet(&mut buf, universe);
+
+        self.next_sequence();
+
+        socket.send(&buf)
+    }
+}
+
+pub struct DmxPacket {
+    sequence: u8,
+    physical: u8,
+    port_address: u16,
+    data: [u8; DMX_LENGTH],
+}
+
+impl DmxPacket {
+    pub fn new(universe: &Universe, sequence: u8, physical: u8) -> Self {
+        Self {
+            sequence,
+            physical,
+            port_address: Self::port_address(universe.universe_number()),
+            data: universe.bytes(),
+        }
+    }
+
+    // Art-Net counts universes from 0, where our universes are counted from
+    // 1, so universe 1 is sent on port-address 0.
+    fn port_address(universe_number: usize) -> u16 {
+        (universe_number.saturating_sub(1) as u16).min(MAX_PORT_ADDRESS)
+    }
+
+    pub fn pack(&self, buf: &mut [u8]) {
+        buf[0..8].copy_from_slice(&ARTNET_ID);
+        LittleEndian::write_u16(&mut buf[8..10], OP_DMX);
+        BigEndian::write_u16(&mut buf[10..12], PROTOCOL_VERSION);
+        buf[12] = self.sequence;
+        buf[13] = self.physical;
+        // SubUni - The low byte of the 15 bit port-address, the sub-net in the
+        // high nibble and the universe in the low nibble.
+        buf[14] = (self.port_address & 0xff) as u8;
+        // Net - The top 7 bits of the 15 bit port-address.
+        buf[15] = (self.port_address >> 8) as u8;
+        // Length - The length of the DMX512 data array, high byte first. We
+        // always send full universes.
+        BigEndian::write_u16(&mut buf[16..18], DMX_LENGTH as u16);
+        buf[ARTDMX_HEADER_LENGTH..ARTDMX_PACKET_LENGTH].copy_from_slice(&self.data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        address::Address,
+        dmx::{Dmx, DmxString},
+        universe::Multiverse,
+    };
+
+    fn multiverse(universe: u16) -> Multiverse {
+        let mut dmx_string = DmxString::new(3);
+        dmx_string.set(0, Dmx::new(255));
+        dmx_string.set(1, Dmx::new(128));
+        dmx_string.set(2, Dmx::new(1));
+
+        let mut multiverse = Multiverse::new();
+        multiverse
+            .map_string(&Address::new(universe, 10), &dmx_string)
+            .unwrap();
+        multiverse
+    }
+
+    fn packed(universe: u16) -> [u8; ARTDMX_PACKET_LENGTH] {
+        let multiverse = multiverse(universe);
+        let mut buf = [0; ARTDMX_PACKET_LENGTH];
+        ArtNet::new().pack_dmx_packet(&mut buf, multiverse.universes().next().unwrap());
+        buf
+    }
+
+    #[test]
+    fn packs_the_header() {
+        let buf = packed(1);
+
+        assert_eq!(&buf[0..8], b"Art-Net\0");
+        // OpDmx, low byte first
+        assert_eq!(&buf[8..10], &[0x00, 0x50]);
+        // Protocol version 14, high byte first
+        assert_eq!(&buf[10..12], &[0x00, 0x0e]);
+        assert_eq!(buf[12], 1);
+        assert_eq!(buf[13], 0);
+        // A full universe of 512 slots, high byte first
+        assert_eq!(&buf[16..18], &[0x02, 0x00]);
+    }
+
+    #[test]
+    fn packs_the_dmx_data() {
+        let buf = packed(1);
+
+        assert_eq!(buf[18 + 8], 0);
+        assert_eq!(&buf[(18 + 9)..(18 + 12)], &[255, 128, 1]);
+        assert_eq!(buf[18 + 12], 0);
+    }
+
+    #[test]
+    fn packs_the_port_address() {
+        assert_eq!(&packed(1)[14..16], &[0x00, 0x00]);
+        assert_eq!(&packed(2)[14..16], &[0x01, 0x00]);
+        assert_eq!(&packed(17)[14..16], &[0x10, 0x00]);
+        assert_eq!(&packed(257)[14..16], &[0x00, 0x01]);
+    }
+
+    #[test]
+    fn sequence_skips_zero_when_wrapping() {
+        let mut artnet = ArtNet::new();
+        artnet.sequence = 0xfe;
+
+        artnet.next_sequence();
+        assert_eq!(artnet.sequence, 0xff);
+
+        artnet.next_sequence();
+        assert_eq!(artnet.sequence, 0x01);
+    }
+
+    #[test]
+    fn sends_universes_over_udp() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+        let mut artnet = ArtNet::new();
+        artnet.send_multiverse(&multiverse(3), &sender).unwrap();
+        artnet.send_multiverse(&multiverse(3), &sender).unwrap();
+
+        let mut buf = [0; 1024];
+
+        let length = receiver.recv(&mut buf).unwrap();
+        assert_eq!(length, ARTDMX_PACKET_LENGTH);
+        assert_eq!(buf[12], 1);
+        assert_eq!(buf[14], 2);
+        assert_eq!(&buf[(18 + 9)..(18 + 12)], &[255, 128, 1]);
+
+        receiver.recv(&mut buf).unwrap();
+        assert_eq!(buf[12], 2);
+    }
+}
@@ -1,12 +1,28 @@
 // Specification: https://tsp.esta.org/tsp/documents/docs/ANSI_E1-31-2018.pdf
 
+use std::{net::Ipv4Addr, time::Duration};
+
 use byteorder::{ByteOrder, NetworkEndian};
 
 use crate::universe::Universe;
 
 pub const MAX_PACKET_LENGTH: usize = 638;
+pub const SYNC_PACKET_LENGTH: usize = 49;
+pub const MAX_DISCOVERY_PACKET_LENGTH: usize = 120 + MAX_UNIVERSES_PER_PAGE * 2;
 pub const ACN_SDT_MULTICAST_PORT: u16 = 5568;
 
+// Appendix A: Defined Parameters
+pub const E131_UNIVERSE_DISCOVERY_INTERVAL: Duration = Duration::from_secs(10);
+pub const E131_DISCOVERY_UNIVERSE: u16 = 64214;
+
+// 9.3.1 Allocation of Multicast Addresses
+// Multicast addresses are assigned based on universe number, with the last
+// two octets being the high and low byte of the universe number.
+pub fn multicast_address(universe: u16) -> Ipv4Addr {
+    let [hi, lo] = universe.to_be_bytes();
+    Ipv4Addr::new(239, 255, hi, lo)
+}
+
 pub struct DataPacket<'a> {
     root_layer: RootLayer<'a>,
     framing_layer: DataPacketFramingLayer,
@@ -49,13 +65,88 @@ impl<'a> DataPacket<'a> {
     }
 }
 
+pub struct SyncPacket<'a> {
+    root_layer: RootLayer<'a>,
+    framing_layer: SyncPacketFramingLayer,
+}
+
+impl<'a> SyncPacket<'a> {
+    pub fn new(sync_address: u16, seq_number: u8, cid: &'a [u8; 16]) -> Self {
+        let framing_layer = SyncPacketFramingLayer::new(sync_address, seq_number);
+        let root_layer = RootLayer::new(framing_layer.len() as u16, VECTOR_ROOT_E131_EXTENDED, cid);
+
+        Self {
+            root_layer,
+            framing_layer,
+        }
+    }
+
+    pub fn pack(&self, buf: &mut [u8]) {
+        self.root_layer.pack(buf);
+        self.framing_layer.pack(buf);
+    }
+}
+
+pub struct DiscoveryPacket<'a> {
+    root_layer: RootLayer<'a>,
+    framing_layer: DiscoveryPacketFramingLayer,
+    discovery_layer: UniverseDiscoveryLayer,
+}
+
+impl<'a> DiscoveryPacket<'a> {
+    // Builds every page needed to list the universes, which are sorted as
+    // required by the specification.
+    pub fn pages(source_name: &str, universes: &[u16], cid: &'a [u8; 16]) -> Vec<Self> {
+        let mut universes = universes.to_vec();
+        universes.sort_unstable();
+        universes.dedup();
+
+        let pages: Vec<&[u16]> = if universes.is_empty() {
+            vec![&[]]
+        } else {
+            universes.chunks(MAX_UNIVERSES_PER_PAGE).collect()
+        };
+        let last_page = (pages.len() - 1) as u8;
+
+        pages
+            .iter()
+            .enumerate()
+            .map(|(page, universes)| {
+                let discovery_layer =
+                    UniverseDiscoveryLayer::new(page as u8, last_page, universes.to_vec());
+                let framing_layer =
+                    DiscoveryPacketFramingLayer::new(discovery_layer.len() as u16, source_name);
+                let root_layer =
+                    RootLayer::new(framing_layer.len() as u16, VECTOR_ROOT_E131_EXTENDED, cid);
+
+                Self {
+                    root_layer,
+                    framing_layer,
+                    discovery_layer,
+                }
+            })
+            .collect()
+    }
+
+    pub fn pack(&self, buf: &mut [u8]) {
+        self.root_layer.pack(buf);
+        self.framing_layer.pack(buf);
+        self.discovery_layer.pack(buf);
+    }
+
+    // The number of octets this packet packs into.
+    pub fn packet_length(&self) -> usize {
+        112 + self.discovery_layer.len()
+    }
+}
+
 // 5 use of the ACN Root Layer Protocol
 // 5.1 Preamble Size - Sources shall set the Preamble Size to 0x0010
 const ROOT_LAYER_PREAMBLE_SIZE: u16 = 0x0010;
 // 5.2 Post-amble Size - Sources shall set the Post-amble Size to 0x0000
 const ROOT_LAYER_POSTAMBLE_SIZE: u16 = 0x0000;
 // 5.3 ACN Packet Identifier
-const ACN_PACKET_IDENTIFIER: [u8; 12] = [
+pub(crate) const ACN_PACKET_IDENTIFIER: [u8; 12] = [
     0x41, 0x53, 0x43, 0x2d, 0x45, 0x31, 0x2e, 0x31, 0x37, 0x00, 0x00, 0x00,
 ];
 // 5.4 Flags & Length
@@ -64,8 +155,8 @@ const ROOT_LAYER_FLAGS: u16 = 0x7000;
 // Sources shall set the Root Layer's Vector to VECTOR_ROOT_E131_DATA if the
 // packet contains E1.31 Data, or to VECTOR_ROOT_E131_EXTENDED if the packet is
 // for Universe Discovery or for Synchronization.
-const VECTOR_ROOT_E131_DATA: u32 = 0x0000_0004;
-const _VECTOR_ROOT_E131_EXTENDED: u32 = 0x0000_0008;
+pub(crate) const VECTOR_ROOT_E131_DATA: u32 = 0x0000_0004;
+const VECTOR_ROOT_E131_EXTENDED: u32 = 0x0000_0008;
 
 struct RootLayer<'a> {
     preamble_size: u16,
@@ -100,13 +191,22 @@ impl<'a> RootLayer<'a> {
     }
 }
 
+// A source name is null-terminated and padded to a fixed 64 octets.
+fn pack_source_name(source_name: &str) -> String {
+    let source_name = String::from_utf8(source_name.bytes().take(63).collect())
+        .unwrap_or_else(|_| "bad name".to_string());
+    let mut source_name = format!("{: <63}", source_name);
+    source_name.push(0x00 as char);
+    source_name
+}
+
 // 6.1 Flags & Length
 const DATA_FRAMING_FLAGS: u16 = 0x7000;
 
 // 6.2 E1.31 Data Packet Framing Layer
 // 6.2.1 E1.31 Data Packet: Vector - Sources sending an E1.31 Data Packet shall set
 // the E1.31 Layer's Vector to VECTOR_E131_DATA_PACKET
-const VECTOR_E131_DATA_PACKET: u32 = 0x0000_0002;
+pub(crate) const VECTOR_E131_DATA_PACKET: u32 = 0x0000_0002;
 
 // 6.2.6 Data Packet: Options
 // This bit-oriented field is used to encode optional flags that control how
@@ -157,10 +257,7 @@ impl DataPacketFramingLayer {
         // displaying the identity of a source to a user. There is no mechanism,
         // other than user configuration, to ensure uniqueness of this name. The
         // source name shall be null-terminated.
-        let source_name = String::from_utf8(source_name.bytes().take(63).collect())
-            .unwrap_or_else(|_| "bad name".to_string());
-        let mut source_name = format!("{: <63}", source_name);
-        source_name.push(0x00 as char);
+        let source_name = pack_source_name(source_name);
 
         // 6.2.3 E1.31 Data Packet: Priority
         // Sources that do not support variable priority shall transmit a
@@ -205,7 +302,143 @@ impl DataPacketFramingLayer {
     }
 }
 
-// TODO: 6.3 E1.31 Synchronization Packet Framing Layer
+// 6.3 E1.31 Synchronization Packet Framing Layer
+// 6.3.1 Synchronization Packet: Vector - Sources sending an E1.31
+// Synchronization Packet shall set the E1.31 Layer's Vector to
+// VECTOR_E131_EXTENDED_SYNCHRONIZATION.
+const VECTOR_E131_EXTENDED_SYNCHRONIZATION: u32 = 0x0000_0001;
+const SYNC_FRAMING_FLAGS: u16 = 0x7000;
+
+struct SyncPacketFramingLayer {
+    flags_and_length: u16,
+    vector: u32,
+    seq_number: u8,
+    sync_address: u16,
+}
+
+impl SyncPacketFramingLayer {
+    fn new(sync_address: u16, seq_number: u8) -> Self {
+        // The framing layer is the final layer of a synchronization packet, so
+        // covers octets 38 to 48.
+        let length: u16 = 11;
+
+        // 6.3.3 Synchronization Packet: Synchronization Address
+        // An E1.31 Synchronization Packet shall be sent to the universe number
+        // carried in this field, which is limited to the range 1 to 63999.
+        let sync_address = sync_address.clamp(1, 63999);
+
+        Self {
+            flags_and_length: SYNC_FRAMING_FLAGS | length & 0x0fff,
+            vector: VECTOR_E131_EXTENDED_SYNCHRONIZATION,
+            seq_number,
+            sync_address,
+        }
+    }
+
+    fn pack(&self, buf: &mut [u8]) {
+        NetworkEndian::write_u16(&mut buf[38..40], self.flags_and_length);
+        NetworkEndian::write_u32(&mut buf[40..44], self.vector);
+        buf[44] = self.seq_number;
+        NetworkEndian::write_u16(&mut buf[45..47], self.sync_address);
+        // 6.3.4 Synchronization Packet: Reserved - Transmitters shall send
+        // 0x0000.
+        NetworkEndian::write_u16(&mut buf[47..49], 0x0000);
+    }
+
+    fn len(&self) -> usize {
+        (self.flags_and_length & 0x0fff) as usize
+    }
+}
+
+// 6.4 E1.31 Universe Discovery Packet Framing Layer
+// 6.4.1 Universe Discovery Packet: Vector - Sources sending an E1.31 Universe
+// Discovery Packet shall set the E1.31 Layer's Vector to
+// VECTOR_E131_EXTENDED_DISCOVERY.
+const VECTOR_E131_EXTENDED_DISCOVERY: u32 = 0x0000_0002;
+const DISCOVERY_FRAMING_FLAGS: u16 = 0x7000;
+
+struct DiscoveryPacketFramingLayer {
+    flags_and_length: u16,
+    vector: u32,
+    source_name: String,
+}
+
+impl DiscoveryPacketFramingLayer {
+    fn new(length: u16, source_name: &str) -> Self {
+        let length = length + 74;
+
+        Self {
+            flags_and_length: DISCOVERY_FRAMING_FLAGS | length & 0x0fff,
+            vector: VECTOR_E131_EXTENDED_DISCOVERY,
+            source_name: pack_source_name(source_name),
+        }
+    }
+
+    fn pack(&self, buf: &mut [u8]) {
+        NetworkEndian::write_u16(&mut buf[38..40], self.flags_and_length);
+        NetworkEndian::write_u32(&mut buf[40..44], self.vector);
+        buf[44..108].copy_from_slice(self.source_name.as_bytes());
+        // 6.4.3 Universe Discovery Packet: Reserved - Transmitters shall send
+        // 0x00000000.
+        NetworkEndian::write_u32(&mut buf[108..112], 0x0000_0000);
+    }
+
+    fn len(&self) -> usize {
+        (self.flags_and_length & 0x0fff) as usize
+    }
+}
+
+// 8 Universe Discovery Layer
+// 8.1 Flags & Length
+const DISCOVERY_LAYER_FLAGS: u16 = 0x7000;
+// 8.2 Universe Discovery Layer: Vector - Sources shall set the Universe
+// Discovery Layer's Vector to VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST.
+const VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST: u32 = 0x0000_0001;
+// 8.5 Universe Discovery Layer: List of Universes - A page holds at most 512
+// universes.
+pub const MAX_UNIVERSES_PER_PAGE: usize = 512;
+
+struct UniverseDiscoveryLayer {
+    flags_and_length: u16,
+    vector: u32,
+    page: u8,
+    last_page: u8,
+    universes: Vec<u16>,
+}
+
+impl UniverseDiscoveryLayer {
+    fn new(page: u8, last_page: u8, universes: Vec<u16>) -> Self {
+        let length = 8 + universes.len() * 2;
+
+        Self {
+            flags_and_length: DISCOVERY_LAYER_FLAGS | length as u16 & 0x0fff,
+            vector: VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST,
+            // 8.3 Universe Discovery Layer: Page - The page number of this
+            // packet, starting from 0.
+            page,
+            // 8.4 Universe Discovery Layer: Last Page - The number of the
+            // final page of the universe list.
+            last_page,
+            universes,
+        }
+    }
+
+    fn pack(&self, buf: &mut [u8]) {
+        NetworkEndian::write_u16(&mut buf[112..114], self.flags_and_length);
+        NetworkEndian::write_u32(&mut buf[114..118], self.vector);
+        buf[118] = self.page;
+        buf[119] = self.last_page;
+
+        for (n, universe) in self.universes.iter().enumerate() {
+            let offset = 120 + n * 2;
+            NetworkEndian::write_u16(&mut buf[offset..(offset + 2)], *universe);
+        }
+    }
+
+    fn len(&self) -> usize {
+        8 + self.universes.len() * 2
+    }
+}
 
 // 7 DMP Layer Protocol
 // 7.1 DMP Layer: Flags & Length
@@ -213,10 +446,10 @@ const DMP_LAYER_FLAGS: u16 = 0x7000;
 // 7.2 DMP Layer: Vector
 // The DMP Layer's Vector shall be set to VECTOR_DMP_SET_PROPERTY, which
 // indicates a DMP Set Property message by sources.
-const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+pub(crate) const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
 // 7.3 Address Type and Data Type
 // Sources shall set the DMP Layer's Address Type and Data Type to 0xa1
-const DMP_ADDRESS_DATA_TYPE: u8 = 0xa1;
+pub(crate) const DMP_ADDRESS_DATA_TYPE: u8 = 0xa1;
 // 7.4 First Property Address
 // Sources shall set the DMP Layer's First Property Address to 0x0000
 const FIRST_PROPERTY_ADDRESS: u16 = 0x0000;
@@ -225,7 +458,7 @@ const FIRST_PROPERTY_ADDRESS: u16 = 0x0000;
 const ADDRESS_INCREMENT: u16 = 0x0001;
 // 7.7 Property Values
 // The first octet of the property values field shall be the DMX512-A [DMX] START Code
-const DMX_START_CODE: u8 = 0x00;
+pub(crate) const DMX_START_CODE: u8 = 0x00;
 
 struct DMPLayer {
     flags_and_length: u16,
@@ -273,3 +506,138 @@ impl DMPLayer {
         11 + self.property_values.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        address::Address,
+        dmx::{Dmx, DmxString},
+        universe::Multiverse,
+    };
+
+    const CID: [u8; 16] = [0xab; 16];
+
+    fn data_packet(priority: Option<u8>, sync_address: u16, options: u8) -> [u8; 638] {
+        let mut dmx_string = DmxString::new(1);
+        dmx_string.set(0, Dmx::new(255));
+
+        let mut multiverse = Multiverse::new();
+        multiverse
+            .map_string(&Address::new(258, 1), &dmx_string)
+            .unwrap();
+        let universe = multiverse.universes().next().unwrap();
+
+        let mut buf = [0; MAX_PACKET_LENGTH];
+        DataPacket::new(universe, "test", priority, sync_address, 7, options, &CID).pack(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn packs_data_packets() {
+        let buf = data_packet(None, 0, 0);
+
+        assert_eq!(&buf[4..16], &ACN_PACKET_IDENTIFIER);
+        assert_eq!(&buf[18..22], &[0x00, 0x00, 0x00, 0x04]);
+        assert_eq!(&buf[22..38], &CID);
+        assert_eq!(&buf[44..48], b"test");
+        assert_eq!(buf[108], 100);
+        assert_eq!(&buf[109..111], &[0x00, 0x00]);
+        assert_eq!(buf[111], 7);
+        assert_eq!(buf[112], 0);
+        assert_eq!(&buf[113..115], &[0x01, 0x02]);
+        assert_eq!(buf[125], DMX_START_CODE);
+        assert_eq!(buf[126], 255);
+    }
+
+    #[test]
+    fn packs_priority_sync_address_and_options() {
+        let buf = data_packet(Some(150), 1000, OPT_PREVIEW_DATA | OPT_STREAM_TERMINATED);
+
+        assert_eq!(buf[108], 150);
+        assert_eq!(&buf[109..111], &[0x03, 0xe8]);
+        assert_eq!(buf[112], 0b1100_0000);
+    }
+
+    #[test]
+    fn priority_is_limited_to_200() {
+        assert_eq!(data_packet(Some(255), 0, 0)[108], 200);
+    }
+
+    #[test]
+    fn packs_sync_packets() {
+        let mut buf = [0; SYNC_PACKET_LENGTH];
+        SyncPacket::new(1000, 42, &CID).pack(&mut buf);
+
+        // Root layer length covers octets 16 to 48
+        assert_eq!(&buf[16..18], &[0x70, 33]);
+        assert_eq!(&buf[18..22], &[0x00, 0x00, 0x00, 0x08]);
+        assert_eq!(&buf[22..38], &CID);
+        // Framing layer length covers octets 38 to 48
+        assert_eq!(&buf[38..40], &[0x70, 11]);
+        assert_eq!(&buf[40..44], &[0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(buf[44], 42);
+        assert_eq!(&buf[45..47], &[0x03, 0xe8]);
+        assert_eq!(&buf[47..49], &[0x00, 0x00]);
+    }
+
+    #[test]
+    fn packs_discovery_packets() {
+        let pages = DiscoveryPacket::pages("test", &[3, 1, 2], &CID);
+        assert_eq!(pages.len(), 1);
+
+        let mut buf = [0; MAX_DISCOVERY_PACKET_LENGTH];
+        pages[0].pack(&mut buf);
+
+        assert_eq!(pages[0].packet_length(), 126);
+        // Root layer length covers octets 16 to 125
+        assert_eq!(&buf[16..18], &[0x70, 110]);
+        assert_eq!(&buf[18..22], &[0x00, 0x00, 0x00, 0x08]);
+        // Framing layer length covers octets 38 to 125
+        assert_eq!(&buf[38..40], &[0x70, 88]);
+        assert_eq!(&buf[40..44], &[0x00, 0x00, 0x00, 0x02]);
+        assert_eq!(&buf[44..48], b"test");
+        assert_eq!(&buf[108..112], &[0x00, 0x00, 0x00, 0x00]);
+        // Discovery layer length covers octets 112 to 125
+        assert_eq!(&buf[112..114], &[0x70, 14]);
+        assert_eq!(&buf[114..118], &[0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(buf[118], 0);
+        assert_eq!(buf[119], 0);
+        // Universes are listed in order
+        assert_eq!(&buf[120..126], &[0, 1, 0, 2, 0, 3]);
+    }
+
+    #[test]
+    fn discovery_without_universes_is_a_single_empty_page() {
+        let pages = DiscoveryPacket::pages("test", &[], &CID);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].packet_length(), 120);
+    }
+
+    #[test]
+    fn discovery_is_split_into_pages_of_512_universes() {
+        let universes: Vec<u16> = (1..=1030).collect();
+        let pages = DiscoveryPacket::pages("test", &universes, &CID);
+        assert_eq!(pages.len(), 3);
+
+        let mut buf = [0; MAX_DISCOVERY_PACKET_LENGTH];
+        pages[1].pack(&mut buf);
+
+        assert_eq!(pages[1].packet_length(), MAX_DISCOVERY_PACKET_LENGTH);
+        assert_eq!(buf[118], 1);
+        assert_eq!(buf[119], 2);
+        assert_eq!(&buf[120..122], &513u16.to_be_bytes());
+
+        pages[2].pack(&mut buf);
+        assert_eq!(pages[2].packet_length(), 120 + 6 * 2);
+        assert_eq!(buf[118], 2);
+        assert_eq!(&buf[120..122], &1025u16.to_be_bytes());
+    }
+
+    #[test]
+    fn multicast_addresses_are_allocated_by_universe() {
+        assert_eq!(multicast_address(1), Ipv4Addr::new(239, 255, 0, 1));
+        assert_eq!(multicast_address(258), Ipv4Addr::new(239, 255, 1, 2));
+        assert_eq!(multicast_address(63999), Ipv4Addr::new(239, 255, 249, 255));
+    }
+}
@@ -1,74 +1,166 @@
 use std::fmt::Display;
 
-use serde::{Deserialize, Serialize};
-
-// TODO: A parameter has many options, that need to be built up from time
-//       from a file. We need a ParameterBuilder and also a struct or
-//       something for reading them from files.
-
-#[derive(Eq, Hash, PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
-pub enum Param {
-    Intensity,
-    Pan,
-    Tilt,
-    Red,
-    Green,
-    Blue,
-    Cyan,
-    Magenta,
-    Yellow,
-    Amber,
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+mod registry;
+pub use registry::{Attribute, Category};
+
+// A parameter of a fixture, identified by its GDTF attribute name. See the
+// registry for the attributes that are available, and their metadata.
+#[derive(Eq, Hash, PartialEq, Debug, Copy, Clone)]
+pub struct Param(&'static str);
+
+#[allow(non_upper_case_globals)]
+impl Param {
+    pub const Intensity: Param = Param("Dimmer");
+    pub const Pan: Param = Param("Pan");
+    pub const Tilt: Param = Param("Tilt");
+    pub const PanRotate: Param = Param("PanRotate");
+    pub const TiltRotate: Param = Param("TiltRotate");
+    pub const Red: Param = Param("ColorAdd_R");
+    pub const Green: Param = Param("ColorAdd_G");
+    pub const Blue: Param = Param("ColorAdd_B");
+    pub const Amber: Param = Param("ColorAdd_A");
+    pub const White: Param = Param("ColorAdd_W");
+    pub const Lime: Param = Param("ColorAdd_L");
+    pub const Uv: Param = Param("ColorAdd_UV");
+    pub const Cyan: Param = Param("ColorSub_C");
+    pub const Magenta: Param = Param("ColorSub_M");
+    pub const Yellow: Param = Param("ColorSub_Y");
+    pub const Hue: Param = Param("HSB_Hue");
+    pub const Saturation: Param = Param("HSB_Saturation");
+    pub const Brightness: Param = Param("HSB_Brightness");
+    // Not a GDTF attribute, but an abstract color temperature in kelvin that
+    // is converted to the colorspace of a fixture.
+    pub const ColorTemperature: Param = Param("ColorTemperature");
+    pub const ColorWheel: Param = Param("Color1");
+    pub const Cto: Param = Param("CTO");
+    pub const Ctc: Param = Param("CTC");
+    pub const Ctb: Param = Param("CTB");
+    pub const Shutter: Param = Param("Shutter1");
+    pub const Strobe: Param = Param("Shutter1Strobe");
+    pub const Zoom: Param = Param("Zoom");
+    pub const Focus: Param = Param("Focus1");
+    pub const Iris: Param = Param("Iris");
+    pub const Frost: Param = Param("Frost1");
+    pub const Gobo: Param = Param("Gobo1");
+    pub const GoboRotate: Param = Param("Gobo1PosRotate");
+    pub const Gobo2: Param = Param("Gobo2");
+    pub const Prism: Param = Param("Prism1");
+    pub const PrismRotate: Param = Param("Prism1PosRotate");
+    pub const Control: Param = Param("Control1");
 }
 
 impl Param {
+    // Finds a param from either its lux identifier, or its GDTF attribute
+    // name.
     pub fn from_string(string: &str) -> Option<Param> {
-        match string {
-            "intensity" => Some(Param::Intensity),
-            "pan" => Some(Param::Pan),
-            "tilt" => Some(Param::Tilt),
-            "red" => Some(Param::Red),
-            "blue" => Some(Param::Blue),
-            "green" => Some(Param::Green),
-            "cyan" => Some(Param::Cyan),
-            "magenta" => Some(Param::Magenta),
-            "yellow" => Some(Param::Yellow),
-            "amber" => Some(Param::Amber),
-            _ => None,
-        }
+        registry::by_identifier(string).or_else(|| registry::by_name(string))
     }
 
-    pub fn is_color(param: &Param) -> bool {
-        // FIXME: This is rubbish, must be a better way to define these.
-        let color_params = vec![
-            Param::Red,
-            Param::Green,
-            Param::Blue,
-            Param::Cyan,
-            Param::Magenta,
-            Param::Yellow,
-            Param::Amber,
-        ];
+    pub fn from_name(name: &str) -> Option<Param> {
+        registry::by_name(name)
+    }
 
-        color_params.contains(param)
+    // Registers a new attribute, returning its param. If the attribute is
+    // already registered the existing param is returned, and if the
+    // identifier is used by another attribute nothing is registered.
+    pub fn register(
+        name: &str,
+        identifier: &str,
+        category: Category,
+        color: bool,
+    ) -> Option<Param> {
+        registry::register(name, identifier, category, color)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+
+    pub fn identifier(&self) -> &'static str {
+        self.attribute()
+            .map(|attribute| attribute.identifier())
+            .unwrap_or(self.0)
+    }
+
+    pub fn attribute(&self) -> Option<Attribute> {
+        registry::attribute(self)
+    }
+
+    pub fn category(&self) -> Category {
+        self.attribute()
+            .map(|attribute| attribute.category())
+            .unwrap_or(Category::Control)
+    }
+
+    pub fn is_color(&self) -> bool {
+        self.attribute()
+            .map(|attribute| attribute.is_color())
+            .unwrap_or(false)
     }
 }
 
 impl Display for Param {
+    // Params are displayed as their identifier in title case, for example
+    // gobo_rotate is displayed as "Gobo Rotate".
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        let words: Vec<String> = self
+            .identifier()
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect();
+
+        write!(f, "{}", words.join(" "))
     }
 }
 
-#[derive(Debug)]
+impl Serialize for Param {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.identifier())
+    }
+}
+
+impl<'de> Deserialize<'de> for Param {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+
+        Param::from_string(&string)
+            .ok_or_else(|| D::Error::custom(format!("unknown parameter: {}", string)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Parameter {
     min: f64,
     max: f64,
     offset: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fine: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ultra: Option<usize>,
+    default: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ranges: Vec<ParameterRange>,
 }
 
 impl Parameter {
     pub fn new(offset: usize, min: f64, max: f64) -> Self {
-        Self { min, max, offset }
+        Self {
+            min,
+            max,
+            offset,
+            fine: None,
+            ultra: None,
+            default: 0.0,
+            ranges: Vec::new(),
+        }
     }
 
     pub fn simple(offset: usize) -> Self {
@@ -76,6 +168,10 @@ impl Parameter {
             min: 0.0,
             max: 100.0,
             offset,
+            fine: None,
+            ultra: None,
+            default: 0.0,
+            ranges: Vec::new(),
         }
     }
 
@@ -87,12 +183,148 @@ impl Parameter {
         self.max
     }
 
+    // The offset of the coarse channel
     pub fn offset(&self) -> usize {
         self.offset
     }
 
-    // TODO: Parameter should have a custom default
+    pub fn fine(&self) -> Option<usize> {
+        self.fine
+    }
+
+    pub fn ultra(&self) -> Option<usize> {
+        self.ultra
+    }
+
+    // All the offsets of the parameter, from the most significant channel to
+    // the least significant.
+    pub fn offsets(&self) -> impl Iterator<Item = usize> {
+        std::iter::once(self.offset)
+            .chain(self.fine)
+            .chain(self.fine.and(self.ultra))
+    }
+
+    // The number of channels, and so bytes, that make up the parameter, 1 for
+    // 8 bit, 2 for 16 bit and 3 for 24 bit.
+    pub fn resolution(&self) -> usize {
+        self.offsets().count()
+    }
+
     pub fn default(&self) -> f64 {
-        0.0
+        self.default
+    }
+
+    pub fn ranges(&self) -> &[ParameterRange] {
+        &self.ranges
+    }
+
+    // Finds a named range, ignoring case so "Open" can be selected as "open".
+    pub fn range(&self, name: &str) -> Option<&ParameterRange> {
+        self.ranges
+            .iter()
+            .find(|range| range.name.eq_ignore_ascii_case(name))
+    }
+}
+
+// A named part of the range of a parameter, such as a gobo on a wheel or a
+// mode of a shutter, from and to values of the parameter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterRange {
+    name: String,
+    from: f64,
+    to: f64,
+}
+
+impl ParameterRange {
+    pub fn new(name: &str, from: f64, to: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            from,
+            to,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn from(&self) -> f64 {
+        self.from
+    }
+
+    pub fn to(&self) -> f64 {
+        self.to
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        (self.from..=self.to).contains(&value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParameterBuilder {
+    offset: usize,
+    fine: Option<usize>,
+    ultra: Option<usize>,
+    min: f64,
+    max: f64,
+    default: f64,
+    ranges: Vec<ParameterRange>,
+}
+
+impl ParameterBuilder {
+    pub fn new(offset: usize) -> Self {
+        Self {
+            offset,
+            fine: None,
+            ultra: None,
+            min: 0.0,
+            max: 100.0,
+            default: 0.0,
+            ranges: Vec::new(),
+        }
+    }
+
+    pub fn build(self) -> Parameter {
+        Parameter {
+            min: self.min,
+            max: self.max,
+            offset: self.offset,
+            fine: self.fine,
+            // An ultra channel only makes sense below a fine channel
+            ultra: self.fine.and(self.ultra),
+            default: self.default,
+            ranges: self.ranges,
+        }
+    }
+
+    pub fn fine(mut self, offset: usize) -> Self {
+        self.fine = Some(offset);
+        self
+    }
+
+    pub fn ultra(mut self, offset: usize) -> Self {
+        self.ultra = Some(offset);
+        self
+    }
+
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = min;
+        self
+    }
+
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = max;
+        self
+    }
+
+    pub fn default(mut self, default: f64) -> Self {
+        self.default = default;
+        self
+    }
+
+    pub fn range(mut self, name: &str, from: f64, to: f64) -> Self {
+        self.ranges.push(ParameterRange::new(name, from, to));
+        self
     }
 }
@@ -0,0 +1,268 @@
+//! Attribute Registry
+//!
+//! Every [`Param`] is an attribute in a shared registry, named following the
+//! GDTF attribute definitions so that imported fixtures, profiles and lux all
+//! talk about the same things. Each attribute has a lux identifier, and some
+//! metadata about how it should be treated.
+//!
+//! The registry starts with the built in attributes, and more can be
+//! registered at runtime.
+//!
+//! # Examples
+//!
+//! ```
+//! use lumen::parameter::{Category, Param};
+//!
+//! let chase = Param::register("Effects1Rate", "chase", Category::Control, false).unwrap();
+//!
+//! assert_eq!(Param::from_string("chase"), Some(chase));
+//! assert_eq!(chase.name(), "Effects1Rate");
+//! assert_eq!(chase.category(), Category::Control);
+//! ```
+
+use std::{collections::HashMap, sync::RwLock};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use super::Param;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Category {
+    Intensity,
+    Position,
+    Color,
+    Beam,
+    Control,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute {
+    param: Param,
+    identifier: &'static str,
+    category: Category,
+    color: bool,
+}
+
+impl Attribute {
+    const fn new(param: Param, identifier: &'static str, category: Category, color: bool) -> Self {
+        Self {
+            param,
+            identifier,
+            category,
+            color,
+        }
+    }
+
+    pub fn param(&self) -> Param {
+        self.param
+    }
+
+    // The GDTF attribute name
+    pub fn name(&self) -> &'static str {
+        self.param.0
+    }
+
+    // The identifier used in lux and profile files
+    pub fn identifier(&self) -> &'static str {
+        self.identifier
+    }
+
+    pub fn category(&self) -> Category {
+        self.category
+    }
+
+    // Whether the attribute is a component of a color mix, and so resolved as
+    // part of a colorspace.
+    pub fn is_color(&self) -> bool {
+        self.color
+    }
+}
+
+const BUILT_IN_ATTRIBUTES: &[Attribute] = &[
+    Attribute::new(Param::Intensity, "intensity", Category::Intensity, false),
+    Attribute::new(Param::Pan, "pan", Category::Position, false),
+    Attribute::new(Param::Tilt, "tilt", Category::Position, false),
+    Attribute::new(Param::PanRotate, "pan_rotate", Category::Position, false),
+    Attribute::new(Param::TiltRotate, "tilt_rotate", Category::Position, false),
+    Attribute::new(Param::Red, "red", Category::Color, true),
+    Attribute::new(Param::Green, "green", Category::Color, true),
+    Attribute::new(Param::Blue, "blue", Category::Color, true),
+    Attribute::new(Param::Amber, "amber", Category::Color, true),
+    Attribute::new(Param::White, "white", Category::Color, true),
+    Attribute::new(Param::Lime, "lime", Category::Color, false),
+    Attribute::new(Param::Uv, "uv", Category::Color, false),
+    Attribute::new(Param::Cyan, "cyan", Category::Color, true),
+    Attribute::new(Param::Magenta, "magenta", Category::Color, true),
+    Attribute::new(Param::Yellow, "yellow", Category::Color, true),
+    Attribute::new(Param::Hue, "hue", Category::Color, true),
+    Attribute::new(Param::Saturation, "saturation", Category::Color, true),
+    Attribute::new(Param::Brightness, "brightness", Category::Color, true),
+    Attribute::new(
+        Param::ColorTemperature,
+        "color_temperature",
+        Category::Color,
+        true,
+    ),
+    Attribute::new(Param::ColorWheel, "color_wheel", Category::Color, false),
+    Attribute::new(Param::Cto, "cto", Category::Color, false),
+    Attribute::new(Param::Ctc, "ctc", Category::Color, false),
+    Attribute::new(Param::Ctb, "ctb", Category::Color, false),
+    Attribute::new(Param::Shutter, "shutter", Category::Beam, false),
+    Attribute::new(Param::Strobe, "strobe", Category::Beam, false),
+    Attribute::new(Param::Zoom, "zoom", Category::Beam, false),
+    Attribute::new(Param::Focus, "focus", Category::Beam, false),
+    Attribute::new(Param::Iris, "iris", Category::Beam, false),
+    Attribute::new(Param::Frost, "frost", Category::Beam, false),
+    Attribute::new(Param::Gobo, "gobo", Category::Beam, false),
+    Attribute::new(Param::GoboRotate, "gobo_rotate", Category::Beam, false),
+    Attribute::new(Param::Gobo2, "gobo2", Category::Beam, false),
+    Attribute::new(Param::Prism, "prism", Category::Beam, false),
+    Attribute::new(Param::PrismRotate, "prism_rotate", Category::Beam, false),
+    Attribute::new(Param::Control, "control", Category::Control, false),
+];
+
+struct Registry {
+    attributes: HashMap<&'static str, Attribute>,
+    identifiers: HashMap<&'static str, Param>,
+}
+
+impl Registry {
+    fn new() -> Self {
+        let mut registry = Self {
+            attributes: HashMap::new(),
+            identifiers: HashMap::new(),
+        };
+
+        for attribute in BUILT_IN_ATTRIBUTES {
+            registry.insert(*attribute);
+        }
+
+        registry
+    }
+
+    fn insert(&mut self, attribute: Attribute) {
+        self.attributes.insert(attribute.name(), attribute);
+        self.identifiers
+            .insert(attribute.identifier(), attribute.param());
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(Registry::new());
+}
+
+pub(super) fn attribute(param: &Param) -> Option<Attribute> {
+    REGISTRY.read().unwrap().attributes.get(param.0).copied()
+}
+
+pub(super) fn by_name(name: &str) -> Option<Param> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .attributes
+        .get(name)
+        .map(|attribute| attribute.param())
+}
+
+pub(super) fn by_identifier(identifier: &str) -> Option<Param> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .identifiers
+        .get(identifier)
+        .copied()
+}
+
+pub(super) fn register(
+    name: &str,
+    identifier: &str,
+    category: Category,
+    color: bool,
+) -> Option<Param> {
+    let mut registry = REGISTRY.write().unwrap();
+
+    if let Some(attribute) = registry.attributes.get(name) {
+        return Some(attribute.param());
+    }
+
+    if registry.identifiers.contains_key(identifier) {
+        return None;
+    }
+
+    // Attributes live for the rest of the program, so we can give up the
+    // memory for their names to keep a Param cheap to copy.
+    let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+    let identifier: &'static str = Box::leak(identifier.to_string().into_boxed_str());
+
+    let attribute = Attribute::new(Param(name), identifier, category, color);
+    registry.insert(attribute);
+
+    Some(attribute.param())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_from_identifiers_and_names() {
+        assert_eq!(Param::from_string("intensity"), Some(Param::Intensity));
+        assert_eq!(Param::from_string("Dimmer"), Some(Param::Intensity));
+        assert_eq!(Param::from_string("gobo_rotate"), Some(Param::GoboRotate));
+        assert_eq!(Param::from_string("sparkle"), None);
+
+        assert_eq!(Param::from_name("ColorAdd_R"), Some(Param::Red));
+        assert_eq!(Param::from_name("red"), None);
+    }
+
+    #[test]
+    fn attribute_metadata() {
+        assert_eq!(Param::Intensity.category(), Category::Intensity);
+        assert_eq!(Param::Tilt.category(), Category::Position);
+        assert_eq!(Param::Cto.category(), Category::Color);
+        assert_eq!(Param::Zoom.category(), Category::Beam);
+
+        assert!(Param::Amber.is_color());
+        assert!(Param::Cyan.is_color());
+        assert!(Param::Hue.is_color());
+        assert!(!Param::Uv.is_color());
+        assert!(!Param::ColorWheel.is_color());
+        assert!(!Param::Pan.is_color());
+    }
+
+    #[test]
+    fn params_display_as_title_case_identifiers() {
+        assert_eq!(Param::Intensity.to_string(), "Intensity");
+        assert_eq!(Param::PrismRotate.to_string(), "Prism Rotate");
+    }
+
+    #[test]
+    fn params_serialize_as_identifiers() {
+        let json = serde_json::to_string(&Param::Red).unwrap();
+        assert_eq!(json, "\"red\"");
+
+        let param: Param = serde_json::from_str("\"ColorAdd_G\"").unwrap();
+        assert_eq!(param, Param::Green);
+
+        assert!(serde_json::from_str::<Param>("\"sparkle\"").is_err());
+    }
+
+    #[test]
+    fn registering_attributes() {
+        let fog = Param::register("Fog", "fog", Category::Control, false).unwrap();
+
+        assert_eq!(Param::from_string("fog"), Some(fog));
+        assert_eq!(
+            Param::register("Fog", "fog", Category::Control, false),
+            Some(fog)
+        );
+
+        // An identifier can only belong to one attribute
+        assert_eq!(
+            Param::register("Haze", "intensity", Category::Control, false),
+            None
+        );
+        assert_eq!(Param::from_name("Haze"), None);
+    }
+}
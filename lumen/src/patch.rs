@@ -1,5 +1,13 @@
 use crate::color::Colorspace;
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::Iter, BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 
 use crate::{
     address::Address,
@@ -9,46 +17,343 @@ use crate::{
     value::Values,
 };
 
-pub struct Patch<'a> {
-    patch: HashMap<FixtureID, ProfileMapping<'a>>,
+pub mod gdtf;
+pub mod library;
+pub mod modifiers;
+
+use self::modifiers::FixtureModifiers;
+
+// Fixtures are patched with shared profiles, so that a patch can own its
+// profiles while many fixtures of the same type use one copy of the profile.
+//
+// A patch is serialized as a patch file, where each profile is written once
+// and the fixtures refer to their profile by its index:
+//
+// {
+//   "profiles": [{ "colorspace": null, "parameters": { "intensity": { ... } } }],
+//   "fixtures": { "1": { "address": { "universe": 1, "address": 1 }, "profile": 0 } }
+// }
+//
+// A fixture with modifiers also has a "modifiers" entry, such as
+// { "swap_pan_tilt": true, "parameters": { "pan": { "invert": true } } }.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "PatchFile", try_from = "PatchFile")]
+pub struct Patch {
+    patch: HashMap<FixtureID, ProfileMapping>,
 }
 
-impl<'a> Patch<'a> {
+impl Patch {
     pub fn new() -> Self {
         Self {
             patch: HashMap::new(),
         }
     }
 
-    // TODO: Proper error handling for any failure case where a patch does
-    //       not succeed
-    pub fn patch(&mut self, id: FixtureID, address: Address, profile: &'a FixtureProfile) {
+    pub fn patch(
+        &mut self,
+        id: FixtureID,
+        address: Address,
+        profile: impl Into<Arc<FixtureProfile>>,
+    ) -> Result<(), PatchError> {
+        let profile = profile.into();
+        self.check_patchable(id, &address, &profile)?;
+
         let mapping = ProfileMapping::new(address, profile);
+
+        for (other_id, other) in self.patch.iter() {
+            if let Some(conflict) = PatchConflict::between((id, &mapping), (*other_id, other)) {
+                return Err(PatchError::AddressConflict(conflict));
+            }
+        }
+
         self.patch.insert(id, mapping);
+        Ok(())
+    }
+
+    // Patches a fixture without checking for overlaps with other fixtures,
+    // for when two fixtures deliberately share the same addresses.
+    pub fn force_patch(
+        &mut self,
+        id: FixtureID,
+        address: Address,
+        profile: impl Into<Arc<FixtureProfile>>,
+    ) -> Result<(), PatchError> {
+        let profile = profile.into();
+        self.check_patchable(id, &address, &profile)?;
+
+        self.patch.insert(id, ProfileMapping::new(address, profile));
+        Ok(())
+    }
+
+    pub fn unpatch(&mut self, id: &FixtureID) -> Result<(), PatchError> {
+        match self.patch.remove(id) {
+            Some(_) => Ok(()),
+            None => Err(PatchError::NotPatched(*id)),
+        }
+    }
+
+    pub fn get_profile(&self, id: &FixtureID) -> Result<&FixtureProfile, PatchError> {
+        self.get_mapping(id).map(|mapping| mapping.profile())
+    }
+
+    // A shared handle to the profile of a fixture, for patching other
+    // fixtures with the same profile.
+    pub fn get_shared_profile(&self, id: &FixtureID) -> Result<Arc<FixtureProfile>, PatchError> {
+        self.get_mapping(id)
+            .map(|mapping| Arc::clone(&mapping.profile))
+    }
+
+    pub fn get_address(&self, id: &FixtureID) -> Result<&Address, PatchError> {
+        self.get_mapping(id).map(|mapping| mapping.address())
+    }
+
+    pub fn set_modifiers(
+        &mut self,
+        id: &FixtureID,
+        modifiers: FixtureModifiers,
+    ) -> Result<(), PatchError> {
+        match self.patch.get_mut(id) {
+            Some(mapping) => {
+                mapping.modifiers = modifiers;
+                Ok(())
+            }
+            None => Err(PatchError::NotPatched(*id)),
+        }
+    }
+
+    pub fn get_modifiers(&self, id: &FixtureID) -> Result<&FixtureModifiers, PatchError> {
+        self.get_mapping(id).map(|mapping| &mapping.modifiers)
     }
 
-    pub fn unpatch(&mut self, id: &FixtureID) {
-        self.patch.remove(id);
+    // Converts a resolved fixture to dmx for its profile, after applying the
+    // modifiers it is patched with.
+    pub fn to_dmx(
+        &self,
+        id: &FixtureID,
+        resolved_fixture: &ResolvedFixture,
+    ) -> Result<DmxString, PatchError> {
+        let mapping = self.get_mapping(id)?;
+
+        if mapping.modifiers.is_empty() {
+            return Ok(mapping.profile.to_dmx(resolved_fixture));
+        }
+
+        let modified = mapping.modifiers.apply(resolved_fixture, &mapping.profile);
+        Ok(mapping.profile.to_dmx(&modified))
+    }
+
+    pub fn fixtures(&self) -> impl Iterator<Item = &FixtureID> {
+        self.patch.keys()
     }
 
-    // TODO: Proper error handling for resolving a fixture that hasn't
-    //       been patched
-    pub fn get_profile(&self, id: &FixtureID) -> &FixtureProfile {
-        self.patch.get(id).unwrap().profile()
+    pub fn len(&self) -> usize {
+        self.patch.len()
     }
 
-    pub fn get_address(&self, id: &FixtureID) -> &Address {
-        self.patch.get(id).unwrap().address()
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, PatchError> {
+        // Parsing the file before building the patch keeps the patch errors,
+        // rather than them becoming parse errors inside serde.
+        let file: PatchFile =
+            serde_json::from_str(source).map_err(|err| PatchError::Parse(err.to_string()))?;
+
+        Patch::try_from(file)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a patch should always serialize")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PatchError> {
+        let source = fs::read_to_string(path).map_err(|err| PatchError::Io(err.to_string()))?;
+        Self::from_json(&source)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PatchError> {
+        fs::write(path, self.to_json()).map_err(|err| PatchError::Io(err.to_string()))
+    }
+
+    pub fn is_patched(&self, id: &FixtureID) -> bool {
+        self.patch.contains_key(id)
+    }
+
+    // Reports every pair of fixtures whose footprints overlap in the same
+    // universe, ordered by fixture id.
+    pub fn conflicts(&self) -> Vec<PatchConflict> {
+        let mut mappings: Vec<(FixtureID, &ProfileMapping)> = self
+            .patch
+            .iter()
+            .map(|(id, mapping)| (*id, mapping))
+            .collect();
+        mappings.sort_by_key(|(id, _)| *id);
+
+        let mut conflicts = Vec::new();
+        for (i, fixture) in mappings.iter().enumerate() {
+            for other in mappings.iter().skip(i + 1) {
+                if let Some(conflict) = PatchConflict::between(*fixture, *other) {
+                    conflicts.push(conflict);
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    // Checks the whole patch is ready to output, reporting every fixture that
+    // runs past the end of its universe and every overlap, ordered by fixture
+    // id. Deliberate overlaps from force_patch are reported too.
+    pub fn validate(&self) -> Vec<PatchError> {
+        let mut ids: Vec<&FixtureID> = self.patch.keys().collect();
+        ids.sort();
+
+        ids.into_iter()
+            .filter_map(|id| {
+                let mapping = &self.patch[id];
+                let footprint = mapping.profile.footprint();
+
+                match mapping.address.fits(footprint) {
+                    true => None,
+                    false => Some(PatchError::FootprintOverflow(
+                        *id,
+                        mapping.address,
+                        footprint,
+                    )),
+                }
+            })
+            .chain(
+                self.conflicts()
+                    .into_iter()
+                    .map(PatchError::AddressConflict),
+            )
+            .collect()
+    }
+
+    fn check_patchable(
+        &self,
+        id: FixtureID,
+        address: &Address,
+        profile: &FixtureProfile,
+    ) -> Result<(), PatchError> {
+        if self.patch.contains_key(&id) {
+            return Err(PatchError::AlreadyPatched(id));
+        }
+
+        if !address.is_valid() {
+            return Err(PatchError::InvalidAddress(id, *address));
+        }
+
+        if !address.fits(profile.footprint()) {
+            return Err(PatchError::FootprintOverflow(
+                id,
+                *address,
+                profile.footprint(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn get_mapping(&self, id: &FixtureID) -> Result<&ProfileMapping, PatchError> {
+        self.patch.get(id).ok_or(PatchError::NotPatched(*id))
     }
 }
 
-impl<'a> Default for Patch<'a> {
+impl Default for Patch {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+struct PatchFile {
+    profiles: Vec<FixtureProfile>,
+    fixtures: BTreeMap<FixtureID, PatchFileEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PatchFileEntry {
+    address: Address,
+    profile: usize,
+    #[serde(default, skip_serializing_if = "FixtureModifiers::is_empty")]
+    modifiers: FixtureModifiers,
+}
+
+impl From<Patch> for PatchFile {
+    fn from(patch: Patch) -> Self {
+        let mut profiles: Vec<Arc<FixtureProfile>> = Vec::new();
+        let mut fixtures = BTreeMap::new();
+
+        // Profiles are numbered in the order of the first fixture using them,
+        // so the same patch is always saved the same way.
+        let mappings: BTreeMap<&FixtureID, &ProfileMapping> = patch.patch.iter().collect();
+
+        for (id, mapping) in mappings {
+            let profile = match profiles
+                .iter()
+                .position(|profile| Arc::ptr_eq(profile, &mapping.profile))
+            {
+                Some(index) => index,
+                None => {
+                    profiles.push(Arc::clone(&mapping.profile));
+                    profiles.len() - 1
+                }
+            };
+
+            fixtures.insert(
+                *id,
+                PatchFileEntry {
+                    address: mapping.address,
+                    profile,
+                    modifiers: mapping.modifiers.clone(),
+                },
+            );
+        }
+
+        Self {
+            profiles: profiles
+                .into_iter()
+                .map(|profile| profile.as_ref().clone())
+                .collect(),
+            fixtures,
+        }
+    }
+}
+
+impl TryFrom<PatchFile> for Patch {
+    type Error = PatchError;
+
+    fn try_from(file: PatchFile) -> Result<Self, Self::Error> {
+        // Profiles in a patch file may have been edited by hand, so are
+        // checked like any other profile before they are used.
+        for (index, profile) in file.profiles.iter().enumerate() {
+            library::validate(&format!("profile {}", index), profile)
+                .map_err(|err| PatchError::InvalidProfile(index, err.to_string()))?;
+        }
+
+        let profiles: Vec<Arc<FixtureProfile>> = file.profiles.into_iter().map(Arc::new).collect();
+        let mut patch = Patch::new();
+
+        for (id, entry) in file.fixtures {
+            let profile = profiles
+                .get(entry.profile)
+                .ok_or(PatchError::UnknownProfile(id, entry.profile))?;
+
+            // A saved patch may have deliberate overlaps, which can be found
+            // with conflicts once loaded.
+            patch.force_patch(id, entry.address, Arc::clone(profile))?;
+            patch.set_modifiers(&id, entry.modifiers)?;
+        }
+
+        Ok(patch)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "ProfileFile")]
 pub struct FixtureProfile {
     parameters: HashMap<Param, Parameter>,
     colorspace: Option<Colorspace>,
@@ -65,10 +370,12 @@ impl FixtureProfile {
     }
 
     pub fn set_parameter(&mut self, param: Param, parameter: Parameter) {
-        // TODO: A paramter can be fine, in which case footprint would be
-        //       offset+1 if this is the largest offset we ever found
-        if parameter.offset() > self.footprint {
-            self.footprint = parameter.offset();
+        // Any fine channels of the parameter may extend the footprint beyond
+        // the coarse channel
+        if let Some(offset) = parameter.offsets().max() {
+            if offset > self.footprint {
+                self.footprint = offset;
+            }
         }
 
         self.parameters.insert(param, parameter);
@@ -78,15 +385,21 @@ impl FixtureProfile {
         self.parameters.get(param)
     }
 
+    pub fn parameters(&self) -> Iter<'_, Param, Parameter> {
+        self.parameters.iter()
+    }
+
     pub fn to_dmx(&self, resolved_fixture: &ResolvedFixture) -> DmxString {
         let mut dmx_string = DmxString::new(self.footprint());
 
         for (param, parameter) in self.parameters.iter() {
-            if let Some(value) = resolved_fixture.get_value(param) {
-                dmx_string.set(parameter.offset(), value.to_dmx(parameter));
-            } else {
-                let default = Values::make_literal(parameter.default());
-                dmx_string.set(parameter.offset(), default.to_dmx(parameter));
+            let dmx = match resolved_fixture.get_value(param) {
+                Some(value) => value.to_dmx(parameter),
+                None => Values::make_literal(parameter.default()).to_dmx(parameter),
+            };
+
+            for (offset, dmx) in parameter.offsets().zip(dmx) {
+                dmx_string.set(offset, dmx);
             }
         }
 
@@ -101,7 +414,7 @@ impl FixtureProfile {
         &self.colorspace
     }
 
-    fn footprint(&self) -> usize {
+    pub fn footprint(&self) -> usize {
         // The footprint is 0 indexed to match the 0 indexing of offsets in
         // parameters, so we return +1 for the correct size.
         self.footprint + 1
@@ -114,21 +427,177 @@ impl Default for FixtureProfile {
     }
 }
 
-pub struct ProfileMapping<'a> {
+// Profiles are written with their parameters in offset order, so that patch
+// files are stable and easy to read.
+impl Serialize for FixtureProfile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut parameters: Vec<(&Param, &Parameter)> = self.parameters.iter().collect();
+        parameters.sort_by_key(|(param, parameter)| (parameter.offset(), param.name()));
+
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("colorspace", &self.colorspace)?;
+        map.serialize_entry("parameters", &OrderedParameters(parameters))?;
+        map.end()
+    }
+}
+
+struct OrderedParameters<'a>(Vec<(&'a Param, &'a Parameter)>);
+
+impl<'a> Serialize for OrderedParameters<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().copied())
+    }
+}
+
+// The footprint of a profile is worked out from its parameters, so it is not
+// part of the serialized profile.
+#[derive(Deserialize)]
+struct ProfileFile {
+    #[serde(default)]
+    colorspace: Option<Colorspace>,
+    parameters: HashMap<Param, Parameter>,
+}
+
+impl From<ProfileFile> for FixtureProfile {
+    fn from(file: ProfileFile) -> Self {
+        let mut profile = FixtureProfile::new();
+
+        for (param, parameter) in file.parameters {
+            profile.set_parameter(param, parameter);
+        }
+
+        if let Some(colorspace) = file.colorspace {
+            profile.set_colorspace(colorspace);
+        }
+
+        profile
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfileMapping {
     address: Address,
-    profile: &'a FixtureProfile,
+    profile: Arc<FixtureProfile>,
+    modifiers: FixtureModifiers,
 }
 
-impl<'a> ProfileMapping<'a> {
-    fn new(address: Address, profile: &'a FixtureProfile) -> Self {
-        Self { address, profile }
+impl ProfileMapping {
+    fn new(address: Address, profile: Arc<FixtureProfile>) -> Self {
+        Self {
+            address,
+            profile,
+            modifiers: FixtureModifiers::new(),
+        }
     }
 
     fn profile(&self) -> &FixtureProfile {
-        self.profile
+        &self.profile
     }
 
     fn address(&self) -> &Address {
         &self.address
     }
+
+    // The 0 indexed range of channels the fixture occupies in its universe
+    fn channels(&self) -> std::ops::Range<usize> {
+        let start = self.address.address_index();
+        start..(start + self.profile.footprint())
+    }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchConflict {
+    pub fixture: FixtureID,
+    pub other: FixtureID,
+    pub universe: u16,
+    // The first and last overlapping addresses
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PatchConflict {
+    fn between(
+        (fixture, mapping): (FixtureID, &ProfileMapping),
+        (other, other_mapping): (FixtureID, &ProfileMapping),
+    ) -> Option<Self> {
+        if mapping.address().universe() != other_mapping.address().universe() {
+            return None;
+        }
+
+        let channels = mapping.channels();
+        let other_channels = other_mapping.channels();
+
+        let start = channels.start.max(other_channels.start);
+        let end = channels.end.min(other_channels.end);
+
+        if start >= end {
+            return None;
+        }
+
+        Some(Self {
+            fixture,
+            other,
+            universe: mapping.address().universe(),
+            start: start as u16 + 1,
+            end: end as u16,
+        })
+    }
+}
+
+impl Display for PatchConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "fixtures {} and {} overlap at {}.{:03}",
+            self.fixture, self.other, self.universe, self.start
+        )?;
+
+        if self.end != self.start {
+            write!(f, " to {}.{:03}", self.universe, self.end)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PatchError {
+    NotPatched(FixtureID),
+    AlreadyPatched(FixtureID),
+    InvalidAddress(FixtureID, Address),
+    FootprintOverflow(FixtureID, Address, usize),
+    AddressConflict(PatchConflict),
+    UnknownProfile(FixtureID, usize),
+    InvalidProfile(usize, String),
+    Io(String),
+    Parse(String),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::NotPatched(id) => write!(f, "fixture {} is not patched", id),
+            PatchError::AlreadyPatched(id) => write!(f, "fixture {} is already patched", id),
+            PatchError::InvalidAddress(id, address) => {
+                write!(f, "fixture {} can not be patched to {}", id, address)
+            }
+            PatchError::FootprintOverflow(id, address, footprint) => write!(
+                f,
+                "fixture {} at {} needs {} channels and runs past the end of universe {}",
+                id,
+                address,
+                footprint,
+                address.universe()
+            ),
+            PatchError::AddressConflict(conflict) => write!(f, "{}", conflict),
+            PatchError::UnknownProfile(id, profile) => {
+                write!(f, "fixture {} uses unknown profile {}", id, profile)
+            }
+            PatchError::InvalidProfile(_, err) => write!(f, "invalid {}", err),
+            PatchError::Io(err) => write!(f, "could not read patch file: {}", err),
+            PatchError::Parse(err) => write!(f, "could not parse patch file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
@@ -0,0 +1,405 @@
+//! GDTF Fixture Import
+//!
+//! Manufacturers describe their fixtures with the General Device Type Format,
+//! a zip archive containing a `description.xml`. Each DMX mode of the fixture
+//! type is imported as its own [`FixtureProfile`], with the GDTF attributes
+//! that lumen understands mapped on to [`Param`] values.
+//!
+//! Specification: https://gdtf.eu/gdtf/file-spec/
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    color::Colorspace,
+    parameter::{Param, ParameterBuilder},
+};
+
+use super::{library, FixtureProfile};
+
+const DESCRIPTION_FILE: &str = "description.xml";
+
+#[derive(Debug)]
+pub struct GdtfFixture {
+    pub name: String,
+    pub manufacturer: String,
+    pub modes: Vec<GdtfMode>,
+    // Modes that could not be imported, with the reason why.
+    pub skipped: Vec<(String, GdtfError)>,
+}
+
+#[derive(Debug)]
+pub struct GdtfMode {
+    pub name: String,
+    pub profile: FixtureProfile,
+    // Attributes in the mode that have no lumen parameter, or that repeat an
+    // attribute already mapped, and so are not part of the profile.
+    pub unmapped: Vec<String>,
+}
+
+impl GdtfFixture {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GdtfError> {
+        let file = File::open(path).map_err(|err| GdtfError::Io(err.to_string()))?;
+        Self::from_reader(file)
+    }
+
+    pub fn from_reader(reader: impl Read + Seek) -> Result<Self, GdtfError> {
+        let mut archive =
+            zip::ZipArchive::new(reader).map_err(|err| GdtfError::Archive(err.to_string()))?;
+
+        let mut description = String::new();
+        archive
+            .by_name(DESCRIPTION_FILE)
+            .map_err(|_| GdtfError::MissingDescription)?
+            .read_to_string(&mut description)
+            .map_err(|err| GdtfError::Io(err.to_string()))?;
+
+        Self::from_description(&description)
+    }
+
+    pub fn from_description(description: &str) -> Result<Self, GdtfError> {
+        let document =
+            Document::parse(description).map_err(|err| GdtfError::Xml(err.to_string()))?;
+
+        let fixture_type = document
+            .descendants()
+            .find(|node| node.has_tag_name("FixtureType"))
+            .ok_or_else(|| GdtfError::Invalid("no FixtureType".to_string()))?;
+
+        let name = fixture_type
+            .attribute("Name")
+            .unwrap_or_default()
+            .to_string();
+        let manufacturer = fixture_type
+            .attribute("Manufacturer")
+            .unwrap_or_default()
+            .to_string();
+
+        // A mode that can't be imported is skipped rather than losing the
+        // whole fixture, unless none of its modes can be imported.
+        let mut modes = Vec::new();
+        let mut skipped = Vec::new();
+        for mode in fixture_type
+            .descendants()
+            .filter(|node| node.has_tag_name("DMXMode"))
+        {
+            match Self::import_mode(mode) {
+                Ok(mode) => modes.push(mode),
+                Err(err) => {
+                    skipped.push((mode.attribute("Name").unwrap_or_default().to_string(), err))
+                }
+            }
+        }
+
+        if modes.is_empty() && !skipped.is_empty() {
+            let (_, err) = skipped.remove(0);
+            return Err(err);
+        }
+
+        Ok(Self {
+            name,
+            manufacturer,
+            modes,
+            skipped,
+        })
+    }
+
+    fn import_mode(mode: Node) -> Result<GdtfMode, GdtfError> {
+        let name = mode.attribute("Name").unwrap_or_default().to_string();
+        let mut profile = FixtureProfile::new();
+        let mut unmapped = Vec::new();
+
+        for channel in mode
+            .descendants()
+            .filter(|node| node.has_tag_name("DMXChannel"))
+        {
+            // Channels in other breaks are patched to a separate address,
+            // which a single profile can't describe.
+            if channel.attribute("DMXBreak").unwrap_or("1") != "1" {
+                continue;
+            }
+
+            let offsets = match channel.attribute("Offset") {
+                Some(offsets) => offsets,
+                None => continue,
+            };
+
+            // Virtual channels have no offset and don't generate any dmx.
+            if offsets == "None" || offsets.is_empty() {
+                continue;
+            }
+
+            // Offsets list the coarse channel first followed by any fine
+            // channels, e.g. 1,2 for a 16 bit channel.
+            let offsets = offsets
+                .split(',')
+                .map(|offset| offset.trim().parse::<usize>().ok().filter(|o| *o > 0))
+                .collect::<Option<Vec<usize>>>()
+                .filter(|offsets| (1..=3).contains(&offsets.len()))
+                .ok_or_else(|| GdtfError::Invalid(format!("invalid offset: {}", offsets)))?;
+
+            let logical_channel = channel
+                .children()
+                .find(|node| node.has_tag_name("LogicalChannel"))
+                .ok_or_else(|| GdtfError::Invalid("channel has no LogicalChannel".to_string()))?;
+
+            let attribute = logical_channel.attribute("Attribute").unwrap_or_default();
+
+            let param = match Self::param_for_attribute(attribute) {
+                Some(param) => param,
+                None => {
+                    unmapped.push(attribute.to_string());
+                    continue;
+                }
+            };
+
+            // Fixtures with many cells repeat their attributes for each cell,
+            // and only the first can be controlled.
+            if profile.get_parameter(&param).is_some() {
+                unmapped.push(attribute.to_string());
+                continue;
+            }
+
+            let functions: Vec<Node> = logical_channel
+                .children()
+                .filter(|node| node.has_tag_name("ChannelFunction"))
+                .collect();
+            let function = functions.first().copied();
+
+            let (min, max) = Self::physical_range(function);
+
+            // The default is in the channel function from GDTF 1.1, but was
+            // part of the channel in 1.0.
+            let default = function
+                .and_then(|function| function.attribute("Default"))
+                .or_else(|| channel.attribute("Default"))
+                .and_then(Self::dmx_factor)
+                .map(|factor| min + (max - min) * factor)
+                .unwrap_or(min);
+
+            // GDTF offsets are 1 indexed, but parameter offsets are 0 indexed.
+            let mut builder = ParameterBuilder::new(offsets[0] - 1)
+                .min(min)
+                .max(max)
+                .default(default);
+
+            if let Some(fine) = offsets.get(1) {
+                builder = builder.fine(fine - 1);
+            }
+
+            if let Some(ultra) = offsets.get(2) {
+                builder = builder.ultra(ultra - 1);
+            }
+
+            for (name, from, to) in Self::named_ranges(&functions) {
+                builder = builder.range(&name, min + (max - min) * from, min + (max - min) * to);
+            }
+
+            profile.set_parameter(param, builder.build());
+        }
+
+        if let Some(colorspace) = Self::detect_colorspace(&profile) {
+            profile.set_colorspace(colorspace);
+        }
+
+        // Catches anything else a profile can't have, such as two channels
+        // at the same offset.
+        library::validate(&name, &profile).map_err(|err| GdtfError::Invalid(err.to_string()))?;
+
+        Ok(GdtfMode {
+            name,
+            profile,
+            unmapped,
+        })
+    }
+
+    fn param_for_attribute(attribute: &str) -> Option<Param> {
+        // Params are named after GDTF attributes, but GDTF 1.0 used some
+        // names for color attributes that have since been replaced.
+        match attribute {
+            "ColorRGB_Red" => Some(Param::Red),
+            "ColorRGB_Green" => Some(Param::Green),
+            "ColorRGB_Blue" => Some(Param::Blue),
+            "ColorRGB_Cyan" => Some(Param::Cyan),
+            "ColorRGB_Magenta" => Some(Param::Magenta),
+            "ColorRGB_Yellow" => Some(Param::Yellow),
+            _ => Param::from_name(attribute),
+        }
+    }
+
+    fn physical_range(function: Option<Node>) -> (f64, f64) {
+        let physical = |name| {
+            function
+                .and_then(|function| function.attribute(name))
+                .and_then(|value| value.parse::<f64>().ok())
+        };
+
+        match (physical("PhysicalFrom"), physical("PhysicalTo")) {
+            // GDTF uses a physical range of 0 to 1 for anything without a
+            // real world unit, which we treat as the usual 0 to 100 range.
+            (Some(from), Some(to)) if from == 0.0 && to == 1.0 => (0.0, 100.0),
+            (Some(from), Some(to)) if from < to => (from, to),
+            _ => (0.0, 100.0),
+        }
+    }
+
+    // The named ranges of a channel, as factors of the channel. Each channel
+    // function runs until the next one starts, and the last until the end of
+    // the channel. Named functions, such as the open, closed and strobe
+    // functions of a shutter, are ranges, as are the named channel sets of a
+    // function, such as the gobos of a wheel.
+    fn named_ranges(functions: &[Node]) -> Vec<(String, f64, f64)> {
+        let mut ranges = Vec::new();
+
+        for (index, function) in functions.iter().enumerate() {
+            let end = functions
+                .get(index + 1)
+                .and_then(|next| next.attribute("DMXFrom"))
+                .and_then(Self::dmx_value)
+                .unwrap_or((1.0, 0.0));
+
+            // Functions start at 0 unless they say otherwise.
+            let from = function
+                .attribute("DMXFrom")
+                .map_or(Some((0.0, 0.0)), Self::dmx_value);
+
+            if let (Some(name), Some((from, _))) = (Self::name_of(*function), from) {
+                ranges.push((name.to_string(), from, Self::until(from, end)));
+            }
+
+            ranges.extend(Self::channel_sets(*function, end));
+        }
+
+        // Ranges are found by name ignoring case, so only the first of any
+        // repeated name could ever be selected.
+        let mut names = HashSet::new();
+        ranges.retain(|(name, _, _)| names.insert(name.to_lowercase()));
+
+        ranges
+    }
+
+    // The named channel sets of a channel function. Each set runs until the
+    // next one starts, and the last until the end of the function.
+    fn channel_sets(function: Node, end: (f64, f64)) -> Vec<(String, f64, f64)> {
+        let mut starts: Vec<(Option<&str>, (f64, f64))> = function
+            .children()
+            .filter(|node| node.has_tag_name("ChannelSet"))
+            .filter_map(|set| {
+                let from = Self::dmx_value(set.attribute("DMXFrom")?)?;
+                Some((Self::name_of(set), from))
+            })
+            .collect();
+        starts.push((None, end));
+
+        starts
+            .windows(2)
+            .filter_map(|sets| {
+                let (name, (from, _)) = sets[0];
+                Some((name?.to_string(), from, Self::until(from, sets[1].1)))
+            })
+            .collect()
+    }
+
+    fn name_of<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+        node.attribute("Name").filter(|name| !name.is_empty())
+    }
+
+    // A range runs until the dmx value before the next one starts.
+    fn until(from: f64, (next, step): (f64, f64)) -> f64 {
+        (next - step).max(from)
+    }
+
+    fn dmx_factor(value: &str) -> Option<f64> {
+        Self::dmx_value(value).map(|(factor, _)| factor)
+    }
+
+    // DMX values in GDTF are written as value/bytes, e.g. 32768/2 is half of
+    // a 16 bit channel. Returns the factor of the value in the channel, and
+    // the size of a single step at that resolution.
+    fn dmx_value(value: &str) -> Option<(f64, f64)> {
+        let (value, bytes) = match value.split_once('/') {
+            Some((value, bytes)) => (value, bytes.parse::<i32>().ok()?),
+            None => (value, 1),
+        };
+
+        let value = value.parse::<f64>().ok()?;
+        let max = 256_f64.powi(bytes) - 1.0;
+
+        Some(((value / max).clamp(0.0, 1.0), 1.0 / max))
+    }
+
+    fn detect_colorspace(profile: &FixtureProfile) -> Option<Colorspace> {
+        // Colorspaces with more emitters are preferred, as they can make more
+        // of the colors the fixture is capable of.
+        [
+            Colorspace::RGBAW,
+            Colorspace::RGBA,
+            Colorspace::RGBW,
+            Colorspace::RGB,
+            Colorspace::CMY,
+            Colorspace::HSI,
+        ]
+        .into_iter()
+        .find(|colorspace| {
+            Colorspace::params_for_colorspace(colorspace)
+                .iter()
+                .all(|param| profile.get_parameter(param).is_some())
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum GdtfError {
+    Io(String),
+    Archive(String),
+    MissingDescription,
+    Xml(String),
+    Invalid(String),
+}
+
+impl Display for GdtfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GdtfError::Io(err) => write!(f, "could not read gdtf file: {}", err),
+            GdtfError::Archive(err) => write!(f, "invalid gdtf archive: {}", err),
+            GdtfError::MissingDescription => {
+                write!(f, "gdtf archive has no {}", DESCRIPTION_FILE)
+            }
+            GdtfError::Xml(err) => write!(f, "invalid gdtf description: {}", err),
+            GdtfError::Invalid(err) => write!(f, "invalid gdtf fixture: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for GdtfError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dmx_factor_of_8_bit_values() {
+        assert_eq!(GdtfFixture::dmx_factor("0/1"), Some(0.0));
+        assert_eq!(GdtfFixture::dmx_factor("255/1"), Some(1.0));
+        assert_eq!(GdtfFixture::dmx_factor("255"), Some(1.0));
+    }
+
+    #[test]
+    fn dmx_factor_of_16_bit_values() {
+        assert_eq!(GdtfFixture::dmx_factor("65535/2"), Some(1.0));
+        assert_eq!(GdtfFixture::dmx_factor("0/2"), Some(0.0));
+    }
+
+    #[test]
+    fn dmx_factor_of_invalid_values() {
+        assert_eq!(GdtfFixture::dmx_factor("half"), None);
+        assert_eq!(GdtfFixture::dmx_factor("128/one"), None);
+    }
+}
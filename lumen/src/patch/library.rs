@@ -0,0 +1,496 @@
+//! Fixture Profile Library
+//!
+//! Fixture profiles can be described on disk as JSON files, so that a library
+//! of profiles can be shared between shows and rigs instead of being built in
+//! code. A profile file looks like:
+//!
+//! ```json
+//! {
+//!   "name": "Generic RGBA",
+//!   "colorspace": "RGBA",
+//!   "parameters": {
+//!     "intensity": { "offset": 0, "default": 0 },
+//!     "red": { "offset": 1 },
+//!     "green": { "offset": 2 },
+//!     "blue": { "offset": 3 },
+//!     "amber": { "offset": 4, "min": 0, "max": 100 },
+//!     "shutter": {
+//!       "offset": 5,
+//!       "ranges": [
+//!         { "name": "closed", "from": 0, "to": 10 },
+//!         { "name": "open", "from": 10, "to": 100 }
+//!       ]
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Parameter names are the same identifiers used in lux, offsets are 0
+//! indexed from the start address of the fixture, and `min`, `max` and
+//! `default` are optional. A 16 bit parameter gives the offset of its fine
+//! channel with `fine`, and a 24 bit parameter also gives an `ultra` offset.
+//! Named `ranges` can be selected by name in lux, such as `shutter: "open"`.
+//!
+//! GDTF files can also be loaded into a library, providing a profile for each
+//! of their DMX modes.
+//!
+//! # Examples
+//!
+//! ```
+//! use lumen::parameter::Param;
+//! use lumen::patch::library::ProfileLibrary;
+//!
+//! let mut library = ProfileLibrary::new();
+//! library
+//!     .load_str(r#"{ "name": "Dimmer", "parameters": { "intensity": { "offset": 0 } } }"#)
+//!     .unwrap();
+//!
+//! let dimmer = library.get("Dimmer").unwrap();
+//! assert!(dimmer.get_parameter(&Param::Intensity).is_some());
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::Colorspace,
+    parameter::{Param, Parameter, ParameterBuilder, ParameterRange},
+};
+
+use super::{
+    gdtf::{GdtfError, GdtfFixture},
+    FixtureProfile,
+};
+
+const PROFILE_EXTENSION: &str = "json";
+const GDTF_EXTENSION: &str = "gdtf";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub colorspace: Option<Colorspace>,
+    pub parameters: BTreeMap<String, ParameterDefinition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterDefinition {
+    pub offset: usize,
+    #[serde(default)]
+    pub fine: Option<usize>,
+    #[serde(default)]
+    pub ultra: Option<usize>,
+    #[serde(default = "ParameterDefinition::default_min")]
+    pub min: f64,
+    #[serde(default = "ParameterDefinition::default_max")]
+    pub max: f64,
+    #[serde(default)]
+    pub default: Option<f64>,
+    #[serde(default)]
+    pub ranges: Vec<ParameterRange>,
+}
+
+impl ParameterDefinition {
+    fn default_min() -> f64 {
+        0.0
+    }
+
+    fn default_max() -> f64 {
+        100.0
+    }
+}
+
+impl ProfileDefinition {
+    pub fn from_json(source: &str) -> Result<Self, ProfileError> {
+        serde_json::from_str(source).map_err(|err| ProfileError::Parse(err.to_string()))
+    }
+
+    // Validates the definition, and builds the fixture profile it describes.
+    pub fn build(&self) -> Result<FixtureProfile, ProfileError> {
+        let mut profile = FixtureProfile::new();
+
+        for (name, definition) in self.parameters.iter() {
+            let param = match Param::from_string(name) {
+                Some(param) => param,
+                None => {
+                    return Err(ProfileError::UnknownParameter {
+                        profile: self.name.clone(),
+                        parameter: name.clone(),
+                    })
+                }
+            };
+
+            if definition.ultra.is_some() && definition.fine.is_none() {
+                return Err(ProfileError::UltraWithoutFine {
+                    profile: self.name.clone(),
+                    parameter: param,
+                });
+            }
+
+            // When no default is given, we use 0 or the closest value to it
+            // that is within the range of the parameter.
+            let default = definition
+                .default
+                .unwrap_or_else(|| 0.0_f64.max(definition.min).min(definition.max));
+
+            let mut builder = ParameterBuilder::new(definition.offset)
+                .min(definition.min)
+                .max(definition.max)
+                .default(default);
+
+            if let Some(fine) = definition.fine {
+                builder = builder.fine(fine);
+            }
+
+            if let Some(ultra) = definition.ultra {
+                builder = builder.ultra(ultra);
+            }
+
+            for range in definition.ranges.iter() {
+                builder = builder.range(range.name(), range.from(), range.to());
+            }
+
+            profile.set_parameter(param, builder.build());
+        }
+
+        if let Some(colorspace) = self.colorspace {
+            profile.set_colorspace(colorspace);
+        }
+
+        validate(&self.name, &profile)?;
+
+        Ok(profile)
+    }
+}
+
+// Checks a profile is one that a definition could build, so that profiles
+// from anywhere else, such as a patch file, can be trusted in the same way.
+pub fn validate(name: &str, profile: &FixtureProfile) -> Result<(), ProfileError> {
+    let mut parameters: Vec<(&Param, &Parameter)> = profile.parameters().collect();
+    parameters.sort_by_key(|(param, parameter)| (parameter.offset(), param.name()));
+
+    let mut offsets: HashMap<usize, Param> = HashMap::new();
+
+    for (param, parameter) in parameters {
+        if parameter.min() >= parameter.max() {
+            return Err(ProfileError::InvalidRange {
+                profile: name.to_string(),
+                parameter: *param,
+            });
+        }
+
+        let bounds = parameter.min()..=parameter.max();
+
+        if !bounds.contains(&parameter.default()) {
+            return Err(ProfileError::DefaultOutOfRange {
+                profile: name.to_string(),
+                parameter: *param,
+            });
+        }
+
+        for range in parameter.ranges() {
+            if range.from() > range.to()
+                || !bounds.contains(&range.from())
+                || !bounds.contains(&range.to())
+            {
+                return Err(ProfileError::NamedRangeOutOfBounds {
+                    profile: name.to_string(),
+                    parameter: *param,
+                    name: range.name().to_string(),
+                });
+            }
+        }
+
+        for offset in parameter.offsets() {
+            if let Some(existing) = offsets.insert(offset, *param) {
+                return Err(ProfileError::DuplicateOffset {
+                    profile: name.to_string(),
+                    offset,
+                    parameters: (existing, *param),
+                });
+            }
+        }
+    }
+
+    if let Some(colorspace) = profile.colorspace() {
+        for param in Colorspace::params_for_colorspace(colorspace) {
+            if profile.get_parameter(&param).is_none() {
+                return Err(ProfileError::MissingColorParameter {
+                    profile: name.to_string(),
+                    colorspace: *colorspace,
+                    parameter: param,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(PathBuf, std::io::Error),
+    Parse(String),
+    UnknownParameter {
+        profile: String,
+        parameter: String,
+    },
+    InvalidRange {
+        profile: String,
+        parameter: Param,
+    },
+    DefaultOutOfRange {
+        profile: String,
+        parameter: Param,
+    },
+    UltraWithoutFine {
+        profile: String,
+        parameter: Param,
+    },
+    NamedRangeOutOfBounds {
+        profile: String,
+        parameter: Param,
+        name: String,
+    },
+    DuplicateOffset {
+        profile: String,
+        offset: usize,
+        parameters: (Param, Param),
+    },
+    MissingColorParameter {
+        profile: String,
+        colorspace: Colorspace,
+        parameter: Param,
+    },
+    DuplicateProfile(String),
+    Gdtf(PathBuf, GdtfError),
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileError::Io(path, err) => {
+                write!(f, "could not read {}: {}", path.display(), err)
+            }
+            ProfileError::Parse(err) => write!(f, "could not parse profile: {}", err),
+            ProfileError::UnknownParameter { profile, parameter } => {
+                write!(f, "{}: unknown parameter '{}'", profile, parameter)
+            }
+            ProfileError::InvalidRange { profile, parameter } => {
+                write!(
+                    f,
+                    "{}: {} must have a min below its max",
+                    profile, parameter
+                )
+            }
+            ProfileError::DefaultOutOfRange { profile, parameter } => {
+                write!(
+                    f,
+                    "{}: {} has a default outside its range",
+                    profile, parameter
+                )
+            }
+            ProfileError::UltraWithoutFine { profile, parameter } => {
+                write!(
+                    f,
+                    "{}: {} has an ultra offset but no fine offset",
+                    profile, parameter
+                )
+            }
+            ProfileError::NamedRangeOutOfBounds {
+                profile,
+                parameter,
+                name,
+            } => write!(
+                f,
+                "{}: {} has a range '{}' outside its min and max",
+                profile, parameter, name
+            ),
+            ProfileError::DuplicateOffset {
+                profile,
+                offset,
+                parameters,
+            } => write!(
+                f,
+                "{}: {} and {} share offset {}",
+                profile, parameters.0, parameters.1, offset
+            ),
+            ProfileError::MissingColorParameter {
+                profile,
+                colorspace,
+                parameter,
+            } => write!(
+                f,
+                "{}: colorspace {:?} requires a {} parameter",
+                profile, colorspace, parameter
+            ),
+            ProfileError::DuplicateProfile(name) => {
+                write!(f, "a profile named '{}' already exists", name)
+            }
+            ProfileError::Gdtf(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+#[derive(Debug)]
+pub struct ProfileLibrary {
+    profiles: HashMap<String, Arc<FixtureProfile>>,
+}
+
+impl ProfileLibrary {
+    pub fn new() -> Self {
+        Self {
+            profiles: HashMap::new(),
+        }
+    }
+
+    pub fn from_directory(path: impl AsRef<Path>) -> Result<Self, ProfileError> {
+        let mut library = Self::new();
+        library.load_directory(path)?;
+        Ok(library)
+    }
+
+    // Loads every profile and gdtf file in a directory, returning how many
+    // profiles were loaded. Either every profile in the directory is added, or
+    // none of them are.
+    pub fn load_directory(&mut self, path: impl AsRef<Path>) -> Result<usize, ProfileError> {
+        let path = path.as_ref();
+        let entries = fs::read_dir(path).map_err(|err| ProfileError::Io(path.to_owned(), err))?;
+
+        let mut files = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|err| ProfileError::Io(path.to_owned(), err))?;
+            let file = entry.path();
+
+            if file.is_file() {
+                files.push(file);
+            }
+        }
+
+        // Sort the files so that any errors are reported in a stable order.
+        files.sort();
+
+        let mut loaded = Self::new();
+        for file in files.iter() {
+            match file.extension().and_then(|extension| extension.to_str()) {
+                Some(PROFILE_EXTENSION) => {
+                    loaded.load_file(file)?;
+                }
+                Some(GDTF_EXTENSION) => {
+                    loaded.load_gdtf(file)?;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(name) = loaded
+            .names()
+            .filter(|name| self.profiles.contains_key(*name))
+            .min()
+        {
+            return Err(ProfileError::DuplicateProfile(name.clone()));
+        }
+
+        let count = loaded.len();
+        self.profiles.extend(loaded.profiles);
+
+        Ok(count)
+    }
+
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<String, ProfileError> {
+        let path = path.as_ref();
+        let source =
+            fs::read_to_string(path).map_err(|err| ProfileError::Io(path.to_owned(), err))?;
+
+        self.load_str(&source)
+    }
+
+    // Adds a profile for each mode of a gdtf fixture, named after the fixture
+    // and the mode, returning the names of the new profiles.
+    pub fn load_gdtf(&mut self, path: impl AsRef<Path>) -> Result<Vec<String>, ProfileError> {
+        let path = path.as_ref();
+        let fixture =
+            GdtfFixture::open(path).map_err(|err| ProfileError::Gdtf(path.to_owned(), err))?;
+
+        self.insert_gdtf(fixture)
+    }
+
+    // Either every mode of the fixture is added, or none of them are.
+    pub fn insert_gdtf(&mut self, fixture: GdtfFixture) -> Result<Vec<String>, ProfileError> {
+        let names: Vec<String> = fixture
+            .modes
+            .iter()
+            .map(|mode| format!("{} {} ({})", fixture.manufacturer, fixture.name, mode.name))
+            .collect();
+
+        for (index, name) in names.iter().enumerate() {
+            if self.profiles.contains_key(name) || names[..index].contains(name) {
+                return Err(ProfileError::DuplicateProfile(name.clone()));
+            }
+        }
+
+        for (name, mode) in names.iter().zip(fixture.modes) {
+            self.insert(name.clone(), mode.profile)?;
+        }
+
+        Ok(names)
+    }
+
+    pub fn load_str(&mut self, source: &str) -> Result<String, ProfileError> {
+        let definition = ProfileDefinition::from_json(source)?;
+        let profile = definition.build()?;
+
+        self.insert(definition.name.clone(), profile)?;
+
+        Ok(definition.name)
+    }
+
+    pub fn insert(&mut self, name: String, profile: FixtureProfile) -> Result<(), ProfileError> {
+        if self.profiles.contains_key(&name) {
+            return Err(ProfileError::DuplicateProfile(name));
+        }
+
+        self.profiles.insert(name, Arc::new(profile));
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FixtureProfile> {
+        self.profiles.get(name).map(|profile| profile.as_ref())
+    }
+
+    // A shared handle to a profile, which can be patched to any number of
+    // fixtures without copying the profile.
+    pub fn get_shared(&self, name: &str) -> Option<Arc<FixtureProfile>> {
+        self.profiles.get(name).cloned()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.profiles.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for ProfileLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
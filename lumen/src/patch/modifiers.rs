@@ -0,0 +1,244 @@
+//! Fixture Modifiers
+//!
+//! Fixtures are often rigged in different orientations, hung upside down or
+//! turned on their side, so the same programming would move them in different
+//! directions. Modifiers are set for a fixture in the patch, and change its
+//! resolved values just before they are converted to dmx, so that the show
+//! can be programmed without caring how each fixture is rigged.
+//!
+//! Pan and tilt can be swapped, and any parameter can be inverted within its
+//! range, or offset by an amount in the units of the parameter. A swap
+//! happens first, so the modifiers of pan and tilt apply to the channels
+//! they are set for.
+//!
+//! # Examples
+//!
+//! ```
+//! use lumen::parameter::Param;
+//! use lumen::patch::modifiers::FixtureModifiers;
+//!
+//! let mut modifiers = FixtureModifiers::new();
+//! modifiers.set_swap_pan_tilt(true);
+//! modifiers.set_invert(Param::Pan, true);
+//! modifiers.set_offset(Param::Tilt, -10.0);
+//! ```
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{fixture::ResolvedFixture, parameter::Param, value::Values};
+
+use super::FixtureProfile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct ParameterModifier {
+    #[serde(default)]
+    pub invert: bool,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+impl ParameterModifier {
+    fn is_empty(&self) -> bool {
+        !self.invert && self.offset == 0.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FixtureModifiers {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    swap_pan_tilt: bool,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_in_name_order"
+    )]
+    parameters: HashMap<Param, ParameterModifier>,
+}
+
+// Modifiers are written in the order of their param names, so that patch
+// files are stable.
+fn serialize_in_name_order<S: Serializer>(
+    parameters: &HashMap<Param, ParameterModifier>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut parameters: Vec<(&Param, &ParameterModifier)> = parameters.iter().collect();
+    parameters.sort_by_key(|(param, _)| param.name());
+
+    serializer.collect_map(parameters)
+}
+
+impl FixtureModifiers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_swap_pan_tilt(&mut self, swap: bool) {
+        self.swap_pan_tilt = swap;
+    }
+
+    pub fn swap_pan_tilt(&self) -> bool {
+        self.swap_pan_tilt
+    }
+
+    pub fn set_invert(&mut self, param: Param, invert: bool) {
+        self.modifier_mut(param).invert = invert;
+        self.remove_if_empty(param);
+    }
+
+    pub fn set_offset(&mut self, param: Param, offset: f64) {
+        self.modifier_mut(param).offset = offset;
+        self.remove_if_empty(param);
+    }
+
+    pub fn get(&self, param: &Param) -> Option<&ParameterModifier> {
+        self.parameters.get(param)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.swap_pan_tilt && self.parameters.is_empty()
+    }
+
+    // Returns the resolved fixture as it should be output by the profile.
+    pub fn apply(
+        &self,
+        resolved_fixture: &ResolvedFixture,
+        profile: &FixtureProfile,
+    ) -> ResolvedFixture {
+        let mut modified = resolved_fixture.clone();
+
+        if self.swap_pan_tilt {
+            let pan = modified.remove(&Param::Pan);
+            let tilt = modified.remove(&Param::Tilt);
+
+            if let Some(pan) = pan {
+                modified.set(Param::Tilt, pan);
+            }
+            if let Some(tilt) = tilt {
+                modified.set(Param::Pan, tilt);
+            }
+        }
+
+        for (param, modifier) in self.parameters.iter() {
+            let parameter = match profile.get_parameter(param) {
+                Some(parameter) => parameter,
+                None => continue,
+            };
+
+            // Parameters that aren't programmed still output their default,
+            // which is modified like any other value.
+            let value = modified
+                .get_value(param)
+                .cloned()
+                .unwrap_or_else(|| Values::make_literal(parameter.default()));
+
+            let mut factor = value.to_factor(parameter);
+            if modifier.invert {
+                factor = 1.0 - factor;
+            }
+
+            let range = parameter.max() - parameter.min();
+            let literal = (parameter.min() + factor * range + modifier.offset)
+                .clamp(parameter.min(), parameter.max());
+
+            modified.set(*param, Values::make_literal(literal));
+        }
+
+        modified
+    }
+
+    fn modifier_mut(&mut self, param: Param) -> &mut ParameterModifier {
+        self.parameters.entry(param).or_default()
+    }
+
+    fn remove_if_empty(&mut self, param: Param) {
+        if self.parameters.get(&param).is_some_and(|m| m.is_empty()) {
+            self.parameters.remove(&param);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parameter::Parameter, value::Value};
+
+    use super::*;
+
+    fn moving_head() -> FixtureProfile {
+        let mut profile = FixtureProfile::new();
+        profile.set_parameter(Param::Pan, Parameter::new(0, -270.0, 270.0));
+        profile.set_parameter(Param::Tilt, Parameter::new(1, -135.0, 135.0));
+        profile
+    }
+
+    fn resolved(pan: f64, tilt: f64) -> ResolvedFixture {
+        let mut resolved = ResolvedFixture::new(1);
+        resolved.set(Param::Pan, Values::make_literal(pan));
+        resolved.set(Param::Tilt, Values::make_literal(tilt));
+        resolved
+    }
+
+    // Values are modified through their position in the range of the
+    // parameter, so are rounded to ignore any floating point error.
+    fn value(resolved: &ResolvedFixture, param: Param) -> f64 {
+        match resolved.get_value(&param) {
+            Some(Values::Literal(literal)) => (literal.value() * 1000.0).round() / 1000.0,
+            other => panic!("expected a literal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_modifiers_leave_values_alone() {
+        let resolved = resolved(90.0, 45.0);
+
+        assert_eq!(
+            FixtureModifiers::new().apply(&resolved, &moving_head()),
+            resolved
+        );
+    }
+
+    #[test]
+    fn invert_mirrors_the_value_in_its_range() {
+        let mut modifiers = FixtureModifiers::new();
+        modifiers.set_invert(Param::Pan, true);
+
+        let modified = modifiers.apply(&resolved(90.0, 45.0), &moving_head());
+
+        assert_eq!(value(&modified, Param::Pan), -90.0);
+        assert_eq!(value(&modified, Param::Tilt), 45.0);
+    }
+
+    #[test]
+    fn offset_is_added_and_kept_in_range() {
+        let mut modifiers = FixtureModifiers::new();
+        modifiers.set_offset(Param::Tilt, 20.0);
+
+        let modified = modifiers.apply(&resolved(0.0, 45.0), &moving_head());
+        assert_eq!(value(&modified, Param::Tilt), 65.0);
+
+        let modified = modifiers.apply(&resolved(0.0, 130.0), &moving_head());
+        assert_eq!(value(&modified, Param::Tilt), 135.0);
+    }
+
+    #[test]
+    fn swap_happens_before_other_modifiers() {
+        let mut modifiers = FixtureModifiers::new();
+        modifiers.set_swap_pan_tilt(true);
+        modifiers.set_invert(Param::Tilt, true);
+
+        let modified = modifiers.apply(&resolved(90.0, 45.0), &moving_head());
+
+        assert_eq!(value(&modified, Param::Pan), 45.0);
+        assert_eq!(value(&modified, Param::Tilt), -90.0);
+    }
+
+    #[test]
+    fn unset_modifiers_are_removed() {
+        let mut modifiers = FixtureModifiers::new();
+        modifiers.set_invert(Param::Pan, true);
+        modifiers.set_invert(Param::Pan, false);
+
+        assert!(modifiers.is_empty());
+    }
+}
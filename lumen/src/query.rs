@@ -17,13 +17,22 @@ impl Query {
     }
 
     pub fn evaluate(&self, fixtures: &QueryResult) -> QueryResult {
-        let mut result = QueryResult::new();
+        self.evaluate_ordered(fixtures).into_iter().collect()
+    }
+
+    // Evaluates the query keeping the order fixtures were selected in, where
+    // ids and ranges are in the order they are written, a range may run
+    // backwards, and anything else is in id order.
+    pub fn evaluate_ordered(&self, fixtures: &QueryResult) -> Vec<FixtureID> {
+        let mut result: Vec<FixtureID> = Vec::new();
+        // The fixtures in the result, to find any already selected quickly
+        let mut selected = QueryResult::new();
         let mut fixtures = fixtures.clone();
 
         for (i, step) in self.steps.iter().enumerate() {
             match step {
                 Step::All => {
-                    result.extend(fixtures.clone());
+                    Self::extend(&mut result, &mut selected, Self::sorted(&fixtures));
                 }
 
                 // TODO: The idea expressed here is that if the even / odd step isn't the
@@ -32,23 +41,31 @@ impl Query {
                 //       this with more complex queries
                 Step::Even => {
                     if i == 0 {
-                        result.extend(Self::even(&fixtures));
+                        let even = Self::even(&Self::sorted(&fixtures));
+                        Self::extend(&mut result, &mut selected, even);
                     } else {
                         result = Self::even(&result);
+                        selected = result.iter().cloned().collect();
                     }
                 }
                 Step::Odd => {
                     if i == 0 {
-                        result.extend(Self::odd(&fixtures));
+                        let odd = Self::odd(&Self::sorted(&fixtures));
+                        Self::extend(&mut result, &mut selected, odd);
                     } else {
                         result = Self::odd(&result);
+                        selected = result.iter().cloned().collect();
                     }
                 }
                 Step::Id(id) => {
-                    result.extend(Self::id(id, &fixtures));
+                    Self::extend(&mut result, &mut selected, Self::id(id, &fixtures));
                 }
                 Step::Range(start, end) => {
-                    result.extend(Self::range(start, end, &fixtures));
+                    Self::extend(
+                        &mut result,
+                        &mut selected,
+                        Self::range(start, end, &fixtures),
+                    );
                 }
                 Step::SubQuery(query) => {
                     fixtures = query.evaluate(&fixtures);
@@ -59,29 +76,47 @@ impl Query {
         result
     }
 
-    fn even(fixtures: &QueryResult) -> QueryResult {
+    // Adds fixtures to the result, ignoring any already selected.
+    fn extend(result: &mut Vec<FixtureID>, selected: &mut QueryResult, fixtures: Vec<FixtureID>) {
+        for id in fixtures {
+            if selected.insert(id) {
+                result.push(id);
+            }
+        }
+    }
+
+    fn sorted(fixtures: &QueryResult) -> Vec<FixtureID> {
+        let mut sorted: Vec<FixtureID> = fixtures.iter().cloned().collect();
+        sorted.sort();
+        sorted
+    }
+
+    fn even(fixtures: &[FixtureID]) -> Vec<FixtureID> {
         fixtures.iter().filter(|id| *id % 2 == 0).cloned().collect()
     }
 
-    fn odd(fixtures: &QueryResult) -> QueryResult {
+    fn odd(fixtures: &[FixtureID]) -> Vec<FixtureID> {
         fixtures.iter().filter(|id| *id % 2 != 0).cloned().collect()
     }
 
-    fn id(id: &FixtureID, fixtures: &QueryResult) -> QueryResult {
-        let mut result = QueryResult::new();
-
+    fn id(id: &FixtureID, fixtures: &QueryResult) -> Vec<FixtureID> {
         if fixtures.contains(id) {
-            result.insert(*id);
+            vec![*id]
+        } else {
+            Vec::new()
         }
-
-        result
     }
 
-    fn range(start: &FixtureID, end: &FixtureID, fixtures: &QueryResult) -> QueryResult {
-        fixtures
-            .iter()
-            .filter(|id| (start..=end).contains(id))
-            .cloned()
-            .collect()
+    fn range(start: &FixtureID, end: &FixtureID, fixtures: &QueryResult) -> Vec<FixtureID> {
+        let mut range: Vec<FixtureID> = Self::sorted(fixtures)
+            .into_iter()
+            .filter(|id| (start.min(end)..=start.max(end)).contains(&id))
+            .collect();
+
+        if start > end {
+            range.reverse();
+        }
+
+        range
     }
 }
@@ -37,7 +37,7 @@ impl QueryBuilder {
     }
 
     pub fn odd(mut self) -> Self {
-        self.steps.push(Step::Even);
+        self.steps.push(Step::Odd);
         self
     }
 
@@ -0,0 +1,160 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    address::Address,
+    dmx::DmxString,
+    fixture::{FixtureID, ResolvedFixture},
+    fixture_set::ResolvedFixtureMap,
+    patch::{modifiers::FixtureModifiers, FixtureProfile},
+    universe::{MapError, Multiverse},
+    Patch,
+};
+
+// What was last rendered for a fixture, so we can tell if it has changed.
+struct RenderedFixture {
+    resolved: ResolvedFixture,
+    modifiers: FixtureModifiers,
+    profile: Arc<FixtureProfile>,
+    address: Address,
+    footprint: usize,
+}
+
+impl RenderedFixture {
+    fn overlaps(&self, other: &RenderedFixture) -> bool {
+        let start = self.address.address_index();
+        let other_start = other.address.address_index();
+
+        self.address.universe() == other.address.universe()
+            && start < other_start + other.footprint
+            && other_start < start + self.footprint
+    }
+}
+
+// Renders resolved fixtures into a Multiverse that is kept between frames.
+//
+// Only fixtures whose resolved values, modifiers or patch have changed since the last
+// render are mapped into their universes again, and only universes whose dmx
+// changed are left dirty for outputs to send.
+//
+// Fixtures that don't fit where they are patched are left out, and the reason
+// is kept in errors until they render cleanly.
+pub struct Renderer {
+    fixtures: HashMap<FixtureID, RenderedFixture>,
+    multiverse: Multiverse,
+    errors: HashMap<FixtureID, MapError>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self {
+            fixtures: HashMap::new(),
+            multiverse: Multiverse::new(),
+            errors: HashMap::new(),
+        }
+    }
+
+    pub fn multiverse(&self) -> &Multiverse {
+        &self.multiverse
+    }
+
+    // The fixtures that couldn't be mapped on the last render.
+    pub fn errors(&self) -> &HashMap<FixtureID, MapError> {
+        &self.errors
+    }
+
+    pub fn render(&mut self, resolved_map: &ResolvedFixtureMap, patch: &Patch) -> &Multiverse {
+        self.multiverse.mark_clean();
+        self.errors.clear();
+
+        // Fixtures that are no longer resolved or patched leave their
+        // addresses dark, as do fixtures that have been repatched. These are
+        // all cleared before rendering, so a fixture patched where another
+        // used to be isn't cleared by it.
+        let moved: Vec<FixtureID> = self
+            .fixtures
+            .iter()
+            .filter(|(id, rendered)| {
+                if !resolved_map.contains_key(id) {
+                    return true;
+                }
+
+                match (patch.get_address(id), patch.get_shared_profile(id)) {
+                    (Ok(address), Ok(profile)) => {
+                        *address != rendered.address || !Arc::ptr_eq(&profile, &rendered.profile)
+                    }
+                    _ => true,
+                }
+            })
+            .map(|(id, _)| *id)
+            .collect();
+
+        let cleared: Vec<RenderedFixture> = moved
+            .iter()
+            .filter_map(|id| self.fixtures.remove(id))
+            .collect();
+
+        for rendered in cleared.iter() {
+            self.clear(rendered);
+        }
+
+        // Clearing can darken channels of an overlapping fixture that hasn't
+        // changed, which has to be rendered again.
+        self.fixtures
+            .retain(|_, rendered| !cleared.iter().any(|cleared| cleared.overlaps(rendered)));
+
+        for (id, resolved_fixture) in resolved_map.iter() {
+            let (address, modifiers, profile) = match (
+                patch.get_address(id),
+                patch.get_modifiers(id),
+                patch.get_shared_profile(id),
+            ) {
+                (Ok(address), Ok(modifiers), Ok(profile)) => (*address, modifiers, profile),
+                _ => continue,
+            };
+
+            let unchanged = self.fixtures.get(id).is_some_and(|rendered| {
+                rendered.resolved == *resolved_fixture && rendered.modifiers == *modifiers
+            });
+
+            if unchanged {
+                continue;
+            }
+
+            let dmx_string = match patch.to_dmx(id, resolved_fixture) {
+                Ok(dmx_string) => dmx_string,
+                Err(_) => continue,
+            };
+            if let Err(err) = self.multiverse.map_string(&address, &dmx_string) {
+                // Not remembered as rendered, so it is tried again next frame.
+                self.errors.insert(*id, err);
+                continue;
+            }
+
+            self.fixtures.insert(
+                *id,
+                RenderedFixture {
+                    resolved: resolved_fixture.clone(),
+                    modifiers: modifiers.clone(),
+                    profile,
+                    address,
+                    footprint: dmx_string.len(),
+                },
+            );
+        }
+
+        &self.multiverse
+    }
+
+    fn clear(&mut self, rendered: &RenderedFixture) {
+        // This was mapped when it was rendered, so it always fits.
+        let _ = self
+            .multiverse
+            .map_string(&rendered.address, &DmxString::new(rendered.footprint));
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    fixture_set::ResolvedFixtureMap,
+    output::{NetworkOutput, NetworkState},
+    render::Renderer,
+    timecode::{time::Time, Source},
+    Environment, Patch,
+};
+
+// The fastest rate a full universe of DMX512 can be refreshed.
+pub const DEFAULT_REFRESH_RATE: u32 = 44;
+
+// What the output loop did on its last frame, for anything that wants to
+// observe it, such as a user interface.
+#[derive(Debug, Clone)]
+pub struct OutputStatus {
+    pub time: Time,
+    pub frames: u64,
+    pub resolved: ResolvedFixtureMap,
+    pub network_state: NetworkState,
+    // The last frame that failed to render or send, until a frame is sent
+    // without any errors.
+    pub last_error: Option<String>,
+}
+
+impl Default for OutputStatus {
+    fn default() -> Self {
+        Self {
+            time: Time::at(0, 0, 0, 0),
+            frames: 0,
+            resolved: ResolvedFixtureMap::new(),
+            network_state: NetworkState::Uninitialized,
+            last_error: None,
+        }
+    }
+}
+
+// Runs the environment, resolves it, maps it into universes and sends it at a
+// fixed refresh rate, independent of whoever is observing it.
+pub struct OutputScheduler {
+    environment: Arc<Mutex<Environment>>,
+    patch: Arc<Mutex<Patch>>,
+    source: Arc<Mutex<Source>>,
+    output: Arc<Mutex<NetworkOutput>>,
+    status: Arc<Mutex<OutputStatus>>,
+    renderer: Mutex<Renderer>,
+    refresh_rate: u32,
+}
+
+impl OutputScheduler {
+    pub fn new(
+        environment: Arc<Mutex<Environment>>,
+        patch: Arc<Mutex<Patch>>,
+        source: Arc<Mutex<Source>>,
+        output: Arc<Mutex<NetworkOutput>>,
+    ) -> Self {
+        Self {
+            environment,
+            patch,
+            source,
+            output,
+            status: Arc::new(Mutex::new(OutputStatus::default())),
+            renderer: Mutex::new(Renderer::new()),
+            refresh_rate: DEFAULT_REFRESH_RATE,
+        }
+    }
+
+    pub fn refresh_rate(mut self, refresh_rate: u32) -> Self {
+        self.refresh_rate = refresh_rate.max(1);
+        self
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(1) / self.refresh_rate
+    }
+
+    pub fn status(&self) -> OutputStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    // Runs a single frame of output.
+    pub fn tick(&self) {
+        let time = self.source.lock().unwrap().time();
+
+        let mut renderer = self.renderer.lock().unwrap();
+
+        let resolved = {
+            let mut environment = self.environment.lock().unwrap();
+            let patch = self.patch.lock().unwrap();
+
+            environment.run_to_time(time, &patch);
+            let resolved = environment.fixtures.resolve(time, &patch);
+            renderer.render(&resolved, &patch);
+
+            resolved
+        };
+
+        let render_error = renderer
+            .errors()
+            .iter()
+            .min_by_key(|(id, _)| **id)
+            .map(|(id, err)| format!("fixture {}: {}", id, err));
+
+        let (network_state, sent) = {
+            let mut output = self.output.lock().unwrap();
+
+            // Only universes that changed are sent, unless they are due a
+            // keepalive.
+            let sent = if output.state == NetworkState::Connected {
+                Some(
+                    output
+                        .send_changes(renderer.multiverse())
+                        .map_err(|err| err.to_string()),
+                )
+            } else {
+                None
+            };
+
+            (output.state, sent)
+        };
+
+        let mut status = self.status.lock().unwrap();
+        status.time = time;
+        status.frames += 1;
+        status.resolved = resolved;
+        status.network_state = network_state;
+        match (sent, render_error) {
+            (Some(Err(error)), _) | (_, Some(error)) => status.last_error = Some(error),
+            (Some(Ok(_)), None) => status.last_error = None,
+            // Nothing was sent, so whatever went wrong last still applies.
+            (None, None) => {}
+        }
+    }
+
+    // Starts the output loop on its own thread, which runs until the handle is
+    // stopped or dropped.
+    pub fn start(self) -> SchedulerHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let status = Arc::clone(&self.status);
+        let thread_running = Arc::clone(&running);
+
+        let thread = thread::spawn(move || {
+            let interval = self.interval();
+            let mut next_frame = Instant::now();
+
+            while thread_running.load(Ordering::Relaxed) {
+                self.tick();
+
+                // Schedule from when the frame should have run rather than
+                // when it finished, so the rate doesn't drift. If we have
+                // fallen behind, start again from now instead of bursting.
+                next_frame += interval;
+                let now = Instant::now();
+                if next_frame > now {
+                    thread::sleep(next_frame - now);
+                } else {
+                    next_frame = now;
+                }
+            }
+        });
+
+        SchedulerHandle {
+            running,
+            status,
+            thread: Some(thread),
+        }
+    }
+}
+
+pub struct SchedulerHandle {
+    running: Arc<AtomicBool>,
+    status: Arc<Mutex<OutputStatus>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SchedulerHandle {
+    pub fn status(&self) -> OutputStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    // Stops the output loop and waits for it to finish, reporting if the
+    // output thread panicked.
+    pub fn stop(&mut self) -> Result<(), SchedulerError> {
+        self.running.store(false, Ordering::Relaxed);
+
+        match self.thread.take() {
+            Some(thread) => thread.join().map_err(|_| SchedulerError::OutputPanicked),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for SchedulerHandle {
+    fn drop(&mut self) {
+        // There is nobody to report a panic to while dropping, and panicking
+        // again here would abort.
+        let _ = self.stop();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SchedulerError {
+    OutputPanicked,
+}
+
+impl Display for SchedulerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchedulerError::OutputPanicked => write!(f, "the output thread panicked"),
+        }
+    }
+}
+
+impl std::error::Error for SchedulerError {}
@@ -0,0 +1,306 @@
+//! Spreads
+//!
+//! A spread distributes an apply across the fixtures of a selection, so that
+//! the same effect on a row of fixtures ripples along it rather than running
+//! in unison. Each fixture is given a position in the selection, and the
+//! generator applied to it is shifted in phase or delayed in proportion.
+//! Generators such as a fan are also given the position, to pick a value,
+//! and every apply has a position even without a spread.
+//!
+//! Positions are counted in id order by default, or in the order the fixtures
+//! were selected, so `10..1` runs backwards. Fixtures can be grouped into blocks
+//! that share a position, and the selection can be split into wings, where
+//! every other wing runs in the opposite direction so that the effect is
+//! mirrored.
+//!
+//! # Examples
+//!
+//! ```
+//! use lumen::spread::{Selection, Spread, SpreadOrder};
+//!
+//! // Fixtures 1 and 2 start together, then 3 and 4 half a cycle later
+//! let spread = Spread::phase(1.0).order(SpreadOrder::Selection).blocks(2);
+//! let position = spread.position(&3, &Selection::new(&[1, 2, 3, 4])).unwrap();
+//!
+//! assert_eq!(position.cyclic(), 0.5);
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    fixture::FixtureID,
+    value::generator::{BoxedGenerator, Delay},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpreadKind {
+    // The phase across the whole selection, where 1.0 is one full cycle
+    Phase(f64),
+    // The delay from the first position to the last
+    Delay(Duration),
+    // Only gives each fixture its position, for generators that use it
+    Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpreadOrder {
+    #[default]
+    Id,
+    Selection,
+}
+
+// Where a fixture is in a spread, out of the number of positions there are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    index: usize,
+    count: usize,
+}
+
+impl Position {
+    pub fn new(index: usize, count: usize) -> Self {
+        Self { index, count }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // How far around a cycle the position is, from 0 up to but not including
+    // 1, so that the last position doesn't land back on the first.
+    pub fn cyclic(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        self.index as f64 / self.count as f64
+    }
+
+    // How far along the spread the position is, from 0 for the first to 1
+    // for the last.
+    pub fn linear(&self) -> f64 {
+        if self.count <= 1 {
+            return 0.0;
+        }
+
+        self.index as f64 / (self.count - 1) as f64
+    }
+}
+
+// Where each fixture of a selection is, both in id order and in the order
+// they were selected, so that every apply to the selection can find them.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    // The index of each fixture in the order selected, and in id order
+    indices: HashMap<FixtureID, (usize, usize)>,
+}
+
+impl Selection {
+    // Takes the fixtures in the order they were selected.
+    pub fn new(selected: &[FixtureID]) -> Self {
+        let mut by_id: Vec<(usize, FixtureID)> = selected.iter().cloned().enumerate().collect();
+        by_id.sort_by_key(|(_, id)| *id);
+
+        let indices = by_id
+            .into_iter()
+            .enumerate()
+            .map(|(id_index, (selected_index, id))| (id, (selected_index, id_index)))
+            .collect();
+
+        Self { indices }
+    }
+
+    pub fn contains(&self, id: &FixtureID) -> bool {
+        self.indices.contains_key(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    // The index of a fixture in the selection in the given order, or None if
+    // it isn't selected.
+    pub fn index(&self, id: &FixtureID, order: SpreadOrder) -> Option<usize> {
+        self.indices
+            .get(id)
+            .map(|(selected_index, id_index)| match order {
+                SpreadOrder::Id => *id_index,
+                SpreadOrder::Selection => *selected_index,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spread {
+    kind: SpreadKind,
+    order: SpreadOrder,
+    blocks: usize,
+    wings: usize,
+}
+
+impl Spread {
+    pub fn new(kind: SpreadKind) -> Self {
+        Self {
+            kind,
+            order: SpreadOrder::Id,
+            blocks: 1,
+            wings: 1,
+        }
+    }
+
+    pub fn phase(phase: f64) -> Self {
+        Self::new(SpreadKind::Phase(phase))
+    }
+
+    pub fn delay(delay: Duration) -> Self {
+        Self::new(SpreadKind::Delay(delay))
+    }
+
+    pub fn order(mut self, order: SpreadOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn blocks(mut self, blocks: usize) -> Self {
+        self.blocks = blocks.max(1);
+        self
+    }
+
+    pub fn wings(mut self, wings: usize) -> Self {
+        self.wings = wings.max(1);
+        self
+    }
+
+    pub fn kind(&self) -> SpreadKind {
+        self.kind
+    }
+
+    // The position of a fixture in a selection, or None if the fixture isn't
+    // selected.
+    pub fn position(&self, id: &FixtureID, selection: &Selection) -> Option<Position> {
+        let index = selection.index(id, self.order)?;
+
+        // Every wing has the same number of fixtures, rounding up, so that
+        // an uneven wing still lines up with the others.
+        let wing_size = selection.len().div_ceil(self.wings);
+        let wing = index / wing_size;
+        let mut index = index % wing_size;
+        if wing % 2 == 1 {
+            index = wing_size - 1 - index;
+        }
+
+        Some(Position::new(
+            index / self.blocks,
+            wing_size.div_ceil(self.blocks),
+        ))
+    }
+
+    // Returns the generator for a fixture at the given position.
+    pub fn apply(&self, mut generator: BoxedGenerator, position: &Position) -> BoxedGenerator {
+        match self.kind {
+            SpreadKind::Phase(phase) => {
+                generator.offset_phase(phase * position.cyclic());
+                generator
+            }
+            SpreadKind::Delay(delay) => {
+                let delay = delay.mul_f64(position.linear());
+                if delay.is_zero() {
+                    generator
+                } else {
+                    Box::new(Delay::new(delay, generator))
+                }
+            }
+            SpreadKind::Position => generator,
+        }
+    }
+}
+
+impl Default for Spread {
+    fn default() -> Self {
+        Self::new(SpreadKind::Position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(spread: Spread, selection: &[FixtureID]) -> Vec<usize> {
+        selection
+            .iter()
+            .map(|id| {
+                spread
+                    .position(id, &Selection::new(selection))
+                    .unwrap()
+                    .index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn positions_are_in_id_order_by_default() {
+        let spread = Spread::phase(1.0);
+
+        assert_eq!(indices(spread, &[3, 1, 2]), vec![2, 0, 1]);
+        assert_eq!(
+            indices(spread.order(SpreadOrder::Selection), &[3, 1, 2]),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn blocks_share_a_position() {
+        let spread = Spread::phase(1.0).blocks(2);
+
+        assert_eq!(indices(spread, &[1, 2, 3, 4, 5]), vec![0, 0, 1, 1, 2]);
+        assert_eq!(
+            spread
+                .position(&5, &Selection::new(&[1, 2, 3, 4, 5]))
+                .unwrap()
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn wings_mirror_each_other() {
+        let spread = Spread::phase(1.0).wings(2);
+
+        assert_eq!(indices(spread, &[1, 2, 3, 4, 5, 6]), vec![0, 1, 2, 2, 1, 0]);
+        assert_eq!(
+            indices(spread.wings(3), &[1, 2, 3, 4, 5, 6]),
+            vec![0, 1, 1, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn selections_are_ordered_by_id_and_as_selected() {
+        let selection = Selection::new(&[3, 1, 2]);
+
+        assert_eq!(selection.index(&3, SpreadOrder::Selection), Some(0));
+        assert_eq!(selection.index(&3, SpreadOrder::Id), Some(2));
+        assert_eq!(selection.index(&4, SpreadOrder::Id), None);
+    }
+
+    #[test]
+    fn unselected_fixtures_have_no_position() {
+        assert_eq!(
+            Spread::phase(1.0).position(&4, &Selection::new(&[1, 2, 3])),
+            None
+        );
+    }
+
+    #[test]
+    fn cyclic_positions_never_reach_the_end() {
+        assert_eq!(Position::new(3, 4).cyclic(), 0.75);
+        assert_eq!(Position::new(3, 4).linear(), 1.0);
+        assert_eq!(Position::new(0, 1).linear(), 0.0);
+    }
+}
@@ -173,9 +173,7 @@ impl PartialEq for TrackAction {
     }
 }
 
-impl Eq for TrackAction {
-    fn assert_receiver_is_total_eq(&self) {}
-}
+impl Eq for TrackAction {}
 
 impl PartialOrd for TrackAction {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
@@ -1,10 +1,35 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
 use crate::{
     address::Address,
     dmx::{Dmx, DmxString},
 };
 
+const UNIVERSE_SIZE: usize = 512;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MapError {
+    InvalidAddress(Address),
+    Overflow { address: Address, length: usize },
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::InvalidAddress(address) => write!(f, "{} is not a valid address", address),
+            MapError::Overflow { address, length } => write!(
+                f,
+                "{} channels at {} run past the end of universe {}",
+                length,
+                address,
+                address.universe()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
 #[derive(Debug)]
 pub struct Multiverse {
     universes: HashMap<usize, Universe>,
@@ -17,14 +42,23 @@ impl Multiverse {
         }
     }
 
-    pub fn map_string(&mut self, address: &Address, dmx_string: &DmxString) {
+    pub fn map_string(
+        &mut self,
+        address: &Address,
+        dmx_string: &DmxString,
+    ) -> Result<(), MapError> {
+        if !address.is_valid() {
+            return Err(MapError::InvalidAddress(*address));
+        }
+
         match self.universes.get_mut(&address.universe_index()) {
             Some(universe) => universe.map_string(address, dmx_string),
             None => {
                 // If we don't have that universe allocated, let's make it.
                 let mut universe = Universe::new(address.universe_index());
-                universe.map_string(address, dmx_string);
+                universe.map_string(address, dmx_string)?;
                 self.universes.insert(address.universe_index(), universe);
+                Ok(())
             }
         }
     }
@@ -32,6 +66,27 @@ impl Multiverse {
     pub fn universes(&self) -> impl Iterator<Item = &Universe> {
         self.universes.values()
     }
+
+    pub fn universe(&self, universe_number: usize) -> Option<&Universe> {
+        self.universes.get(&universe_number.checked_sub(1)?)
+    }
+
+    // Replaces any universe with the same number.
+    pub fn insert(&mut self, universe: Universe) {
+        self.universes.insert(universe.index, universe);
+    }
+
+    pub fn dirty_universes(&self) -> impl Iterator<Item = &Universe> {
+        self.universes
+            .values()
+            .filter(|universe| universe.is_dirty())
+    }
+
+    pub fn mark_clean(&mut self) {
+        for universe in self.universes.values_mut() {
+            universe.mark_clean();
+        }
+    }
 }
 
 impl Default for Multiverse {
@@ -40,32 +95,62 @@ impl Default for Multiverse {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Universe {
     index: usize,
-    values: [Dmx; 512],
+    values: [Dmx; UNIVERSE_SIZE],
+    // Whether any value has changed since the universe was last marked clean.
+    // New universes start dirty, as they have never been sent.
+    dirty: bool,
 }
 
 impl Universe {
     fn new(index: usize) -> Self {
         Self {
             index,
-            values: [Dmx::new(0); 512],
+            values: [Dmx::new(0); UNIVERSE_SIZE],
+            dirty: true,
         }
     }
 
-    fn map_string(&mut self, address: &Address, dmx_string: &DmxString) {
+    // Builds a universe from received dmx, any slots not provided are left at
+    // zero.
+    pub fn from_bytes(universe_number: usize, bytes: &[u8]) -> Self {
+        let mut universe = Self::new(universe_number.saturating_sub(1));
+
+        for (value, byte) in universe.values.iter_mut().zip(bytes) {
+            *value = Dmx::new(*byte);
+        }
+
+        universe
+    }
+
+    fn map_string(&mut self, address: &Address, dmx_string: &DmxString) -> Result<(), MapError> {
         let start = address.address_index();
 
-        // TODO: If the dmx string doesn't fit we should really propogate some
-        //       error.
         if (start + dmx_string.len()) > self.values.len() {
-            return;
+            return Err(MapError::Overflow {
+                address: *address,
+                length: dmx_string.len(),
+            });
         }
 
         for (i, value) in dmx_string.iter().enumerate() {
-            self.values[start + i] = *value;
+            if self.values[start + i] != *value {
+                self.values[start + i] = *value;
+                self.dirty = true;
+            }
         }
+
+        Ok(())
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
     }
 
     pub fn bytes(&self) -> [u8; 512] {
@@ -76,3 +161,73 @@ impl Universe {
         self.index + 1
     }
 }
+
+// Universes are equal if they carry the same dmx, regardless of whether they
+// have been sent.
+impl PartialEq for Universe {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.values == other.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_that_end_on_the_last_address_are_mapped() {
+        let mut multiverse = Multiverse::new();
+        let mut dmx_string = DmxString::new(4);
+        dmx_string.set(3, Dmx::new(255));
+
+        assert!(multiverse
+            .map_string(&Address::new(1, 509), &dmx_string)
+            .is_ok());
+        assert_eq!(multiverse.universe(1).unwrap().bytes()[511], 255);
+    }
+
+    #[test]
+    fn strings_that_overflow_are_not_mapped() {
+        let mut multiverse = Multiverse::new();
+        let mut dmx_string = DmxString::new(4);
+        dmx_string.set(0, Dmx::new(255));
+
+        assert!(multiverse
+            .map_string(&Address::new(2, 510), &dmx_string)
+            .is_err());
+        assert!(
+            multiverse.universe(2).is_none(),
+            "a universe isn't made for a string that doesn't fit"
+        );
+
+        multiverse
+            .map_string(&Address::new(1, 1), &dmx_string)
+            .unwrap();
+        assert_eq!(
+            multiverse.map_string(&Address::new(1, 510), &dmx_string),
+            Err(MapError::Overflow {
+                address: Address::new(1, 510),
+                length: 4
+            })
+        );
+        assert_eq!(
+            multiverse.universe(1).unwrap().bytes()[509],
+            0,
+            "nothing is written"
+        );
+    }
+
+    #[test]
+    fn strings_at_invalid_addresses_are_not_mapped() {
+        let mut multiverse = Multiverse::new();
+        let dmx_string = DmxString::new(1);
+
+        for address in [Address::new(0, 1), Address::new(1, 0), Address::new(1, 513)] {
+            assert_eq!(
+                multiverse.map_string(&address, &dmx_string),
+                Err(MapError::InvalidAddress(address))
+            );
+        }
+        assert_eq!(multiverse.universes().count(), 0);
+    }
+}
@@ -7,21 +7,24 @@ mod literal;
 pub use literal::Literal;
 mod percentage;
 pub use percentage::Percentage;
+mod slot;
+pub use slot::Slot;
 
 use crate::{dmx::Dmx, parameter::Parameter};
 
-use self::convertable::{Convertable, Converter};
+use self::convertable::{Convertable, Converter, LiteralConverter};
 
 pub trait Value: Debug {
     fn value(&self) -> f64;
     fn set(&mut self, value: f64);
-    fn to_dmx(&self, parameter: &Parameter) -> Dmx;
+    fn to_dmx(&self, parameter: &Parameter) -> Vec<Dmx>;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Values {
     Literal(Literal),
     Percentage(Percentage),
+    Slot(Slot),
 }
 
 impl Values {
@@ -33,12 +36,28 @@ impl Values {
         Values::Percentage(Percentage::new(percentage))
     }
 
-    pub fn to_dmx(&self, parameter: &Parameter) -> Dmx {
+    pub fn make_slot(name: &str) -> Values {
+        Values::Slot(Slot::new(name))
+    }
+
+    pub fn to_dmx(&self, parameter: &Parameter) -> Vec<Dmx> {
         match self {
             Values::Literal(literal) => literal.to_dmx(parameter),
             Values::Percentage(percentage) => percentage.to_dmx(parameter),
+            Values::Slot(_) => self
+                .convert(&LiteralConverter::new(parameter))
+                .to_dmx(parameter),
         }
     }
+
+    // The position of the value within the range of the parameter, from 0 at
+    // its min to 1 at its max.
+    pub fn to_factor(&self, parameter: &Parameter) -> f64 {
+        let literal = self.convert(&LiteralConverter::new(parameter));
+        let factor = (literal.value() - parameter.min()) / (parameter.max() - parameter.min());
+
+        factor.clamp(0.0, 1.0)
+    }
 }
 
 impl<T> Convertable<T> for Values {
@@ -46,6 +65,7 @@ impl<T> Convertable<T> for Values {
         match self {
             Values::Literal(literal) => converter.convert_literal(literal),
             Values::Percentage(percentage) => converter.convert_percentage(percentage),
+            Values::Slot(slot) => converter.convert_slot(slot),
         }
     }
 }
@@ -55,6 +75,7 @@ impl Display for Values {
         let value_str = match self {
             Values::Literal(literal) => format!("{:.02}", literal.value()),
             Values::Percentage(percentage) => format!("{:.02}%", percentage.value()),
+            Values::Slot(slot) => format!("{:?}", slot),
         };
 
         write!(f, "{}", value_str)
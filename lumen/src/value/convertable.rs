@@ -6,6 +6,7 @@ pub trait Converter {
     type Result;
     fn convert_literal(&self, literal: &Literal) -> Self::Result;
     fn convert_percentage(&self, percentage: &Percentage) -> Self::Result;
+    fn convert_slot(&self, slot: &Slot) -> Self::Result;
 }
 
 pub trait Convertable<T> {
@@ -32,10 +33,15 @@ impl<'a> Converter for LiteralConverter<'a> {
     fn convert_percentage(&self, percentage: &Percentage) -> Self::Result {
         percentage.to_literal(self.parameter)
     }
+
+    // A slot the parameter doesn't have leaves it at its default
+    fn convert_slot(&self, slot: &Slot) -> Self::Result {
+        slot.to_literal(self.parameter)
+            .unwrap_or_else(|| Literal::new(self.parameter.default()))
+    }
 }
 
 pub struct PercentageConverter<'a> {
-    #[allow(dead_code)]
     parameter: &'a Parameter,
 }
 
@@ -60,4 +66,11 @@ impl<'a> Converter for PercentageConverter<'a> {
     fn convert_percentage(&self, percentage: &Percentage) -> Self::Result {
         *percentage
     }
+
+    fn convert_slot(&self, slot: &Slot) -> Self::Result {
+        let literal = LiteralConverter::new(self.parameter).convert_slot(slot);
+        let difference = self.parameter.max() - self.parameter.min();
+
+        Percentage::new((literal.value() - self.parameter.min()) / difference * 100.0)
+    }
 }
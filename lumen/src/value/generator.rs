@@ -4,11 +4,17 @@ use std::fmt::{Debug, Display};
 use std::time::Duration;
 
 use crate::parameter::Parameter;
+use crate::spread::Position;
 use crate::value::Value;
 
 use super::convertable::{LiteralConverter, PercentageConverter};
 use super::Values;
 
+mod fan;
+mod oscillator;
+pub use fan::{Fan, FanMode};
+pub use oscillator::{Oscillator, Waveform};
+
 pub type BoxedGenerator = Box<dyn Generator + Send + Sync>;
 
 // TODO: This file needs splitting out to multiple other files
@@ -21,6 +27,12 @@ pub trait Generator: Debug + GeneratorClone + Display {
         Time::at(0, 0, 0, 0)
     }
     fn resolve(&mut self, _value: &Values, _time: &Time) {}
+    // Shifts a generator that repeats further through its cycle, where 1.0 is
+    // a full cycle. Generators that don't repeat ignore it.
+    fn offset_phase(&mut self, _phase: f64) {}
+    // Tells a generator where its fixture is in the selection it is applied
+    // to, for generators that vary across a selection.
+    fn set_position(&mut self, _position: &Position) {}
 }
 
 pub trait GeneratorClone {
@@ -59,12 +71,12 @@ impl Generator for Static {
             Values::Literal(literal) => Some(Values::make_literal(
                 literal.value().clamp(parameter.min(), parameter.max()),
             )),
-            Values::Percentage(_) => Some(self.value),
+            Values::Percentage(_) | Values::Slot(_) => Some(self.value.clone()),
         }
     }
 
     fn value(&self) -> Values {
-        self.value
+        self.value.clone()
     }
 }
 
@@ -74,11 +86,97 @@ impl Display for Static {
     }
 }
 
+// The shape of a fade, from its start to its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Curve {
+    #[default]
+    Linear,
+    // Starts slowly and speeds up towards the end
+    EaseIn,
+    // Starts quickly and slows down towards the end
+    EaseOut,
+    // Starts and ends slowly
+    SCurve,
+    // Jumps to the end as soon as the fade starts
+    SnapStart,
+    // Holds the start until the fade is over, then jumps to the end
+    SnapEnd,
+    // Fades the square root of the level linearly, so the level follows a
+    // square law and the brightness of a dimmer looks even across the fade
+    SquareLaw,
+}
+
+impl Curve {
+    pub fn from_name(name: &str) -> Option<Curve> {
+        match name {
+            "linear" => Some(Curve::Linear),
+            "ease_in" => Some(Curve::EaseIn),
+            "ease_out" => Some(Curve::EaseOut),
+            "s_curve" => Some(Curve::SCurve),
+            "snap_start" => Some(Curve::SnapStart),
+            "snap_end" => Some(Curve::SnapEnd),
+            "square" => Some(Curve::SquareLaw),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Curve::Linear => "linear",
+            Curve::EaseIn => "ease_in",
+            Curve::EaseOut => "ease_out",
+            Curve::SCurve => "s_curve",
+            Curve::SnapStart => "snap_start",
+            Curve::SnapEnd => "snap_end",
+            Curve::SquareLaw => "square",
+        }
+    }
+
+    // How far from the start to the end the fade is, at a point in the fade,
+    // where both are from 0 to 1. A square law fade progresses linearly, as
+    // it curves the level instead.
+    pub fn apply(&self, factor: f64) -> f64 {
+        match self {
+            Curve::Linear => factor,
+            Curve::EaseIn => factor.powi(3),
+            Curve::EaseOut => 1.0 - (1.0 - factor).powi(3),
+            Curve::SCurve => factor * factor * (3.0 - 2.0 * factor),
+            Curve::SnapStart => 1.0,
+            Curve::SnapEnd => {
+                if factor < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Curve::SquareLaw => factor,
+        }
+    }
+
+    // The value at a point in a fade from start to end, where the point is
+    // from 0 to 1, and the values are within the given min and max.
+    pub fn between(&self, start: f64, end: f64, factor: f64, (min, max): (f64, f64)) -> f64 {
+        match self {
+            Curve::SquareLaw if min < max => {
+                let level = |value: f64| ((value - min) / (max - min)).clamp(0.0, 1.0).sqrt();
+                let (start, end) = (level(start), level(end));
+                let level = start + (end - start) * factor;
+
+                min + (max - min) * level * level
+            }
+            _ => start + (end - start) * self.apply(factor),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Fade {
     start: BoxedGenerator,
     end: BoxedGenerator,
     duration: Duration,
+    // When set, the duration of the fade when it is fading down
+    down_duration: Option<Duration>,
+    curve: Curve,
     start_time: Option<Time>,
 }
 
@@ -88,21 +186,50 @@ impl Fade {
             start,
             end,
             duration,
+            down_duration: None,
+            curve: Curve::Linear,
             start_time: None,
         }
     }
 
-    fn fade_between<V: Value>(&mut self, start: V, end: V, elapsed: Duration) -> f64 {
+    pub fn down_time(mut self, duration: Duration) -> Self {
+        self.down_duration = Some(duration);
+        self
+    }
+
+    pub fn curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    // Fades between the values, which are within the range given.
+    fn fade_between<V: Value>(
+        &mut self,
+        start: V,
+        end: V,
+        range: (f64, f64),
+        elapsed: Duration,
+    ) -> f64 {
         let fade_elapsed_time = self.fade_relative_elapsed_time(elapsed);
+        let duration = self.duration_between(&start, &end);
 
-        if fade_elapsed_time > self.duration {
+        if fade_elapsed_time > duration {
             return end.value();
         }
 
-        let difference = end.value() - start.value();
-        let factor = fade_elapsed_time.as_secs_f64() / self.duration.as_secs_f64();
+        let factor = fade_elapsed_time.as_secs_f64() / duration.as_secs_f64();
 
-        start.value() + (difference * factor)
+        self.curve
+            .between(start.value(), end.value(), factor, range)
+    }
+
+    // A fade that ends below where it starts is fading down, and takes the
+    // down time if it has one.
+    fn duration_between<V: Value>(&self, start: &V, end: &V) -> Duration {
+        match self.down_duration {
+            Some(down_duration) if end.value() < start.value() => down_duration,
+            _ => self.duration,
+        }
     }
 
     fn fade_relative_elapsed_time(&self, elapsed: Duration) -> Duration {
@@ -125,17 +252,37 @@ impl Generator for Fade {
             .expect("a fade can not have a delayed value");
 
         let elapsed: Duration = (*time).into();
+        let literal_range = (parameter.min(), parameter.max());
 
         match (start, end) {
             (Values::Literal(start), non_literal_end) => {
                 let end = non_literal_end.convert(&LiteralConverter::new(parameter));
-                Some(Values::make_literal(self.fade_between(start, end, elapsed)))
+                Some(Values::make_literal(self.fade_between(
+                    start,
+                    end,
+                    literal_range,
+                    elapsed,
+                )))
             }
             (Values::Percentage(start), non_percentage_end) => {
                 let end = non_percentage_end.convert(&PercentageConverter::new(parameter));
-                Some(Values::make_percentage(
-                    self.fade_between(start, end, elapsed),
-                ))
+                Some(Values::make_percentage(self.fade_between(
+                    start,
+                    end,
+                    (0.0, 100.0),
+                    elapsed,
+                )))
+            }
+            // Fading from a slot scrolls through the range of the parameter
+            (slot @ Values::Slot(_), non_literal_end) => {
+                let start = slot.convert(&LiteralConverter::new(parameter));
+                let end = non_literal_end.convert(&LiteralConverter::new(parameter));
+                Some(Values::make_literal(self.fade_between(
+                    start,
+                    end,
+                    literal_range,
+                    elapsed,
+                )))
             }
         }
     }
@@ -157,17 +304,37 @@ impl Generator for Fade {
         self.start.resolve(value, time);
         self.end.resolve(value, time);
     }
+
+    fn offset_phase(&mut self, phase: f64) {
+        self.start.offset_phase(phase);
+        self.end.offset_phase(phase);
+    }
+
+    fn set_position(&mut self, position: &Position) {
+        self.start.set_position(position);
+        self.end.set_position(position);
+    }
 }
 
 impl Display for Fade {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "FADE({} -> {}, {:.1}s)",
+            "FADE({} -> {}, {:.1}s",
             self.start,
             self.end,
             self.duration.as_secs_f64()
-        )
+        )?;
+
+        if let Some(down_duration) = self.down_duration {
+            write!(f, "/{:.1}s", down_duration.as_secs_f64())?;
+        }
+
+        if self.curve != Curve::Linear {
+            write!(f, ", {}", self.curve.name().to_uppercase())?;
+        }
+
+        write!(f, ")")
     }
 }
 
@@ -233,6 +400,14 @@ impl Generator for Delay {
             self.generator.resolve(value, time);
         }
     }
+
+    fn offset_phase(&mut self, phase: f64) {
+        self.generator.offset_phase(phase);
+    }
+
+    fn set_position(&mut self, position: &Position) {
+        self.generator.set_position(position);
+    }
 }
 
 impl Display for Delay {
@@ -257,7 +432,7 @@ impl Generator for CurrentValue {
     fn generate(&mut self, time: &Time, parameter: &Parameter) -> Option<Values> {
         match self.generator {
             Some(ref mut generator) => generator.generate(time, parameter),
-            None => Some(Values::make_literal(parameter.min())),
+            None => Some(Values::make_literal(parameter.default())),
         }
     }
 
@@ -270,7 +445,7 @@ impl Generator for CurrentValue {
 
     fn resolve(&mut self, value: &Values, _time: &Time) {
         if self.generator.is_none() {
-            self.generator = Some(Box::new(Static::new(*value)))
+            self.generator = Some(Box::new(Static::new(value.clone())))
         }
     }
 }
@@ -281,6 +456,34 @@ impl Display for CurrentValue {
     }
 }
 
+// The home position of a parameter, which is the default given by the profile.
+#[derive(Debug, Clone)]
+pub struct Home;
+
+#[allow(clippy::new_without_default)]
+impl Home {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Generator for Home {
+    fn generate(&mut self, _time: &Time, parameter: &Parameter) -> Option<Values> {
+        Some(Values::make_literal(parameter.default()))
+    }
+
+    // Without a parameter we don't know where home is
+    fn value(&self) -> Values {
+        Values::make_literal(0.0)
+    }
+}
+
+impl Display for Home {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HOME")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,4 +552,161 @@ mod tests {
             Some(Values::make_literal(75.0))
         );
     }
+
+    fn fade_with_curve(curve: Curve) -> Fade {
+        let start = Box::new(Static::new(Values::make_literal(0.0)));
+        let end = Box::new(Static::new(Values::make_literal(100.0)));
+        let mut fade = Fade::new(start, end, Duration::new(4, 0)).curve(curve);
+        fade.set_start_time(Time::at(0, 0, 0, 0));
+        fade
+    }
+
+    fn levels(mut fade: Fade) -> Vec<f64> {
+        let parameter = Parameter::new(0, 0.0, 100.0);
+
+        (0..=4)
+            .map(
+                |second| match fade.generate(&Time::at(0, 0, second, 0), &parameter) {
+                    Some(Values::Literal(literal)) => (literal.value() * 1000.0).round() / 1000.0,
+                    other => panic!("expected a literal, got {:?}", other),
+                },
+            )
+            .collect()
+    }
+
+    #[test]
+    fn fades_are_linear_by_default() {
+        assert_eq!(
+            levels(fade_with_curve(Curve::Linear)),
+            vec![0.0, 25.0, 50.0, 75.0, 100.0]
+        );
+    }
+
+    #[test]
+    fn ease_in_starts_slowly() {
+        assert_eq!(
+            levels(fade_with_curve(Curve::EaseIn)),
+            vec![0.0, 1.563, 12.5, 42.188, 100.0]
+        );
+    }
+
+    #[test]
+    fn ease_out_ends_slowly() {
+        assert_eq!(
+            levels(fade_with_curve(Curve::EaseOut)),
+            vec![0.0, 57.813, 87.5, 98.438, 100.0]
+        );
+    }
+
+    #[test]
+    fn s_curve_starts_and_ends_slowly() {
+        assert_eq!(
+            levels(fade_with_curve(Curve::SCurve)),
+            vec![0.0, 15.625, 50.0, 84.375, 100.0]
+        );
+    }
+
+    #[test]
+    fn snaps_jump_at_the_start_or_end() {
+        assert_eq!(
+            levels(fade_with_curve(Curve::SnapStart)),
+            vec![100.0, 100.0, 100.0, 100.0, 100.0]
+        );
+        assert_eq!(
+            levels(fade_with_curve(Curve::SnapEnd)),
+            vec![0.0, 0.0, 0.0, 0.0, 100.0]
+        );
+    }
+
+    #[test]
+    fn square_law_is_the_square_of_a_linear_level() {
+        assert_eq!(
+            levels(fade_with_curve(Curve::SquareLaw)),
+            vec![0.0, 6.25, 25.0, 56.25, 100.0]
+        );
+    }
+
+    #[test]
+    fn square_law_fades_down_on_the_same_curve_as_up() {
+        let mut down = Fade::new(
+            Box::new(Static::new(Values::make_literal(100.0))),
+            Box::new(Static::new(Values::make_literal(0.0))),
+            Duration::new(4, 0),
+        )
+        .curve(Curve::SquareLaw);
+        down.set_start_time(Time::at(0, 0, 0, 0));
+
+        assert_eq!(levels(down), vec![100.0, 56.25, 25.0, 6.25, 0.0]);
+    }
+
+    #[test]
+    fn square_law_is_relative_to_the_range_of_the_parameter() {
+        // A quarter of the way along a fade from 25% to full, the square
+        // root of the level is 0.5 + 0.5 / 4.
+        assert!((Curve::SquareLaw.between(25.0, 100.0, 0.25, (0.0, 100.0)) - 39.0625).abs() < 1e-9);
+        assert_eq!(
+            Curve::SquareLaw.between(-50.0, 50.0, 0.5, (-50.0, 50.0)),
+            -25.0
+        );
+        assert_eq!(
+            Curve::SquareLaw.between(100.0, 25.0, 1.0, (0.0, 100.0)),
+            25.0
+        );
+    }
+
+    #[test]
+    fn curves_are_shown_when_not_linear() {
+        assert_eq!(
+            fade_with_curve(Curve::Linear).to_string(),
+            "FADE(STATIC(0.00) -> STATIC(100.00), 4.0s)"
+        );
+        assert_eq!(
+            fade_with_curve(Curve::EaseIn).to_string(),
+            "FADE(STATIC(0.00) -> STATIC(100.00), 4.0s, EASE_IN)"
+        );
+    }
+
+    #[test]
+    fn fades_down_take_the_down_time() {
+        let parameter = Parameter::new(0, 0.0, 100.0);
+        let fade = |start: f64, end: f64| {
+            let start = Box::new(Static::new(Values::make_literal(start)));
+            let end = Box::new(Static::new(Values::make_literal(end)));
+            let mut fade =
+                Fade::new(start, end, Duration::new(2, 0)).down_time(Duration::new(4, 0));
+            fade.set_start_time(Time::at(0, 0, 0, 0));
+            fade
+        };
+
+        assert_eq!(
+            fade(0.0, 100.0).generate(&time!(0 0 1 0 Thirty), &parameter),
+            Some(Values::make_literal(50.0))
+        );
+        assert_eq!(
+            fade(100.0, 0.0).generate(&time!(0 0 1 0 Thirty), &parameter),
+            Some(Values::make_literal(75.0))
+        );
+        assert_eq!(
+            fade(100.0, 0.0).to_string(),
+            "FADE(STATIC(100.00) -> STATIC(0.00), 2.0s/4.0s)"
+        );
+    }
+
+    #[test]
+    fn fades_from_a_current_value_choose_their_time_once_resolved() {
+        let parameter = Parameter::new(0, 0.0, 100.0);
+        let mut fade = Fade::new(
+            Box::new(CurrentValue::new()),
+            Box::new(Static::new(Values::make_literal(50.0))),
+            Duration::new(2, 0),
+        )
+        .down_time(Duration::new(5, 0));
+        fade.resolve(&Values::make_literal(100.0), &Time::at(0, 0, 0, 0));
+        fade.set_start_time(Time::at(0, 0, 0, 0));
+
+        assert_eq!(
+            fade.generate(&time!(0 0 1 0 Thirty), &parameter),
+            Some(Values::make_literal(90.0))
+        );
+    }
 }
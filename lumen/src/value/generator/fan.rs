@@ -0,0 +1,209 @@
+//! Fans
+//!
+//! A fan spreads values out across a selection, so that a row of fixtures
+//! can be panned out or given a ramp of intensities with a single apply. Each
+//! fixture is given a value between the two ends of the fan by its position
+//! in the selection.
+//!
+//! A linear fan runs from one end to the other, a center out fan runs from
+//! the first value in the middle to the second value at both edges, and a
+//! mirrored fan runs the other way, from the first value at both edges to the
+//! second value in the middle.
+//!
+//! # Examples
+//!
+//! ```
+//! use lumen::parameter::Parameter;
+//! use lumen::spread::Position;
+//! use lumen::timecode::time::Time;
+//! use lumen::value::generator::{Fan, Generator};
+//! use lumen::value::Values;
+//!
+//! let mut fan = Fan::new(Values::make_literal(-45.0), Values::make_literal(45.0));
+//! fan.set_position(&Position::new(1, 3));
+//!
+//! assert_eq!(
+//!     fan.generate(&Time::at(0, 0, 0, 0), &Parameter::new(0, -90.0, 90.0)),
+//!     Some(Values::make_literal(0.0))
+//! );
+//! ```
+
+use std::fmt::{Debug, Display};
+
+use crate::{
+    parameter::Parameter,
+    spread::Position,
+    timecode::time::Time,
+    value::{
+        convertable::{Convertable, LiteralConverter, PercentageConverter},
+        Value, Values,
+    },
+};
+
+use super::Generator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FanMode {
+    #[default]
+    Linear,
+    CenterOut,
+    Mirrored,
+}
+
+impl FanMode {
+    pub fn from_name(name: &str) -> Option<FanMode> {
+        match name {
+            "linear" => Some(FanMode::Linear),
+            "center" => Some(FanMode::CenterOut),
+            "mirror" => Some(FanMode::Mirrored),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FanMode::Linear => "linear",
+            FanMode::CenterOut => "center",
+            FanMode::Mirrored => "mirror",
+        }
+    }
+
+    // How far from the first value to the second a position along the fan
+    // is, where both are from 0 to 1.
+    fn factor(&self, position: f64) -> f64 {
+        match self {
+            FanMode::Linear => position,
+            FanMode::CenterOut => (2.0 * position - 1.0).abs(),
+            FanMode::Mirrored => 1.0 - (2.0 * position - 1.0).abs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fan {
+    from: Values,
+    to: Values,
+    mode: FanMode,
+    // How far along the fan the fixture is, from 0 to 1
+    position: f64,
+}
+
+impl Fan {
+    pub fn new(from: Values, to: Values) -> Self {
+        Self {
+            from,
+            to,
+            mode: FanMode::Linear,
+            position: 0.0,
+        }
+    }
+
+    pub fn mode(mut self, mode: FanMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn between<V: Value>(from: V, to: V, factor: f64) -> f64 {
+        from.value() + (to.value() - from.value()) * factor
+    }
+}
+
+impl Generator for Fan {
+    fn generate(&mut self, _time: &Time, parameter: &Parameter) -> Option<Values> {
+        let factor = self.mode.factor(self.position);
+
+        // Like a fade, the first value decides what the fan generates.
+        match self.from {
+            Values::Percentage(from) => {
+                let to = self.to.convert(&PercentageConverter::new(parameter));
+                Some(Values::make_percentage(Self::between(from, to, factor)))
+            }
+            _ => {
+                let from = self.from.convert(&LiteralConverter::new(parameter));
+                let to = self.to.convert(&LiteralConverter::new(parameter));
+                Some(Values::make_literal(Self::between(from, to, factor)))
+            }
+        }
+    }
+
+    // For value inspection of a fan we return the value at the far end
+    fn value(&self) -> Values {
+        self.to.clone()
+    }
+
+    fn set_position(&mut self, position: &Position) {
+        self.position = position.linear();
+    }
+}
+
+impl Display for Fan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FAN({}, {}", self.from, self.to)?;
+
+        if self.mode != FanMode::Linear {
+            write!(f, ", {}", self.mode.name().to_uppercase())?;
+        }
+
+        write!(f, ", {:.02}%)", self.position * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(fan: Fan, count: usize) -> Vec<f64> {
+        let parameter = Parameter::new(0, -90.0, 90.0);
+
+        (0..count)
+            .map(|index| {
+                let mut fan = fan.clone();
+                fan.set_position(&Position::new(index, count));
+
+                match fan.generate(&Time::at(0, 0, 0, 0), &parameter) {
+                    Some(Values::Literal(literal)) => literal.value(),
+                    other => panic!("expected a literal, got {:?}", other),
+                }
+            })
+            .collect()
+    }
+
+    fn fan() -> Fan {
+        Fan::new(Values::make_literal(-40.0), Values::make_literal(40.0))
+    }
+
+    #[test]
+    fn linear_fans_run_from_end_to_end() {
+        assert_eq!(values(fan(), 5), vec![-40.0, -20.0, 0.0, 20.0, 40.0]);
+    }
+
+    #[test]
+    fn center_out_fans_start_in_the_middle() {
+        assert_eq!(
+            values(fan().mode(FanMode::CenterOut), 5),
+            vec![40.0, 0.0, -40.0, 0.0, 40.0]
+        );
+    }
+
+    #[test]
+    fn mirrored_fans_start_at_the_edges() {
+        assert_eq!(
+            values(fan().mode(FanMode::Mirrored), 5),
+            vec![-40.0, 0.0, 40.0, 0.0, -40.0]
+        );
+    }
+
+    #[test]
+    fn a_single_fixture_is_at_the_start_of_the_fan() {
+        assert_eq!(values(fan(), 1), vec![-40.0]);
+    }
+
+    #[test]
+    fn percentages_fan_as_percentages() {
+        let mut fan = Fan::new(Values::make_percentage(0.0), Values::make_percentage(100.0));
+        fan.set_position(&Position::new(1, 4));
+
+        let value = fan.generate(&Time::at(0, 0, 0, 0), &Parameter::new(0, 0.0, 100.0));
+        assert!(matches!(value, Some(Values::Percentage(_))));
+    }
+}
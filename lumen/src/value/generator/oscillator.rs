@@ -0,0 +1,334 @@
+//! Oscillators
+//!
+//! An oscillator is a generator that moves a parameter back and forth between
+//! a low and a high value, repeating every period, to make effects such as
+//! waves and chases. The shape of the movement is its waveform, and its phase
+//! shifts where in the cycle it starts, so that fixtures running the same
+//! oscillator at different phases move one after another.
+//!
+//! Oscillators are driven only by the time they are generated at, so a show
+//! can be scrubbed to any time and an oscillator will be in the same place.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//! use lumen::parameter::Parameter;
+//! use lumen::timecode::time::Time;
+//! use lumen::value::generator::{Generator, Oscillator, Waveform};
+//! use lumen::value::Values;
+//!
+//! let mut saw = Oscillator::new(
+//!     Waveform::Saw,
+//!     Values::make_literal(0.0),
+//!     Values::make_literal(100.0),
+//!     Duration::from_secs(2),
+//! );
+//! let parameter = Parameter::new(0, 0.0, 100.0);
+//!
+//! assert_eq!(
+//!     saw.generate(&Time::at(0, 0, 1, 0), &parameter),
+//!     Some(Values::make_literal(50.0))
+//! );
+//! ```
+
+use std::{
+    f64::consts::PI,
+    fmt::{Debug, Display},
+    time::Duration,
+};
+
+use crate::{
+    parameter::Parameter,
+    timecode::time::Time,
+    value::{
+        convertable::{Convertable, LiteralConverter, PercentageConverter},
+        Value, Values,
+    },
+};
+
+use super::Generator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    // Eases from low to high and back again
+    Sine,
+    // Low for the first half of the period, then high
+    Square,
+    // Rises from low to high, then drops back to low
+    Saw,
+    // Jumps to a new value between low and high every period
+    Random,
+}
+
+impl Waveform {
+    pub fn from_name(name: &str) -> Option<Waveform> {
+        match name {
+            "sine" => Some(Waveform::Sine),
+            "square" => Some(Waveform::Square),
+            "saw" => Some(Waveform::Saw),
+            "random" => Some(Waveform::Random),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "sine",
+            Waveform::Square => "square",
+            Waveform::Saw => "saw",
+            Waveform::Random => "random",
+        }
+    }
+
+    // The position between low and high, from 0 to 1, at a point in the
+    // cycle. The cycle is counted from the start, with the position in the
+    // current cycle from 0 to 1.
+    fn factor(&self, cycle: u64, position: f64) -> f64 {
+        match self {
+            Waveform::Sine => 0.5 - (position * 2.0 * PI).cos() / 2.0,
+            Waveform::Square => {
+                if position < 0.5 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Waveform::Saw => position,
+            Waveform::Random => random(cycle),
+        }
+    }
+}
+
+// A stable pseudo random number from 0 to 1 for each cycle, so that a random
+// oscillator is the same every time it is played. This is splitmix64.
+fn random(cycle: u64) -> f64 {
+    let mut z = cycle.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[derive(Debug, Clone)]
+pub struct Oscillator {
+    waveform: Waveform,
+    low: Values,
+    high: Values,
+    period: Duration,
+    // How far through the cycle the oscillator starts, from 0 to 1
+    phase: f64,
+    start_time: Option<Time>,
+}
+
+impl Oscillator {
+    pub fn new(waveform: Waveform, low: Values, high: Values, period: Duration) -> Self {
+        Self {
+            waveform,
+            low,
+            high,
+            period,
+            phase: 0.0,
+            start_time: None,
+        }
+    }
+
+    pub fn phase(mut self, phase: f64) -> Self {
+        self.phase = phase.rem_euclid(1.0);
+        self
+    }
+
+    pub fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    // The number of whole cycles, and how far through the current one the
+    // oscillator is at the given time.
+    fn cycle_at(&self, time: &Time) -> (u64, f64) {
+        if self.period.is_zero() {
+            return (0, self.phase);
+        }
+
+        let start = self.start_time.unwrap_or_else(|| Time::at(0, 0, 0, 0));
+        let elapsed: Duration = (*time).into();
+        let elapsed = elapsed.checked_sub(start.into()).unwrap_or_default();
+
+        let cycles = elapsed.as_secs_f64() / self.period.as_secs_f64() + self.phase;
+
+        (cycles.floor() as u64, cycles.fract())
+    }
+
+    fn between<V: Value>(low: V, high: V, factor: f64) -> f64 {
+        low.value() + (high.value() - low.value()) * factor
+    }
+}
+
+impl Generator for Oscillator {
+    fn generate(&mut self, time: &Time, parameter: &Parameter) -> Option<Values> {
+        let (cycle, position) = self.cycle_at(time);
+        let factor = self.waveform.factor(cycle, position);
+
+        // Like a fade, the low value decides what the oscillator generates.
+        match self.low {
+            Values::Percentage(low) => {
+                let high = self.high.convert(&PercentageConverter::new(parameter));
+                Some(Values::make_percentage(Self::between(low, high, factor)))
+            }
+            _ => {
+                let low = self.low.convert(&LiteralConverter::new(parameter));
+                let high = self.high.convert(&LiteralConverter::new(parameter));
+                Some(Values::make_literal(Self::between(low, high, factor)))
+            }
+        }
+    }
+
+    // For value inspection of an oscillator we return the high value
+    fn value(&self) -> Values {
+        self.high.clone()
+    }
+
+    fn set_start_time(&mut self, time: Time) {
+        self.start_time = Some(time);
+    }
+
+    fn start_time(&self) -> Time {
+        self.start_time.unwrap_or_else(|| Time::at(0, 0, 0, 0))
+    }
+
+    fn offset_phase(&mut self, phase: f64) {
+        self.phase = (self.phase + phase).rem_euclid(1.0);
+    }
+}
+
+impl Display for Oscillator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}({}, {}, {:.1}s",
+            self.waveform.name().to_uppercase(),
+            self.low,
+            self.high,
+            self.period.as_secs_f64()
+        )?;
+
+        if self.phase != 0.0 {
+            write!(f, ", {:.02}%", self.phase * 100.0)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(oscillator: &mut Oscillator, milliseconds: u128) -> f64 {
+        let parameter = Parameter::new(0, 0.0, 100.0);
+
+        match oscillator.generate(&Time::at(0, 0, 0, milliseconds), &parameter) {
+            Some(Values::Literal(literal)) => (literal.value() * 1000.0).round() / 1000.0,
+            other => panic!("expected a literal, got {:?}", other),
+        }
+    }
+
+    fn oscillator(waveform: Waveform) -> Oscillator {
+        let mut oscillator = Oscillator::new(
+            waveform,
+            Values::make_literal(20.0),
+            Values::make_literal(80.0),
+            Duration::from_secs(1),
+        );
+        oscillator.set_start_time(Time::at(0, 0, 0, 0));
+        oscillator
+    }
+
+    #[test]
+    fn sine_eases_from_low_to_high_and_back() {
+        let mut sine = oscillator(Waveform::Sine);
+
+        assert_eq!(generate(&mut sine, 0), 20.0);
+        assert_eq!(generate(&mut sine, 250), 50.0);
+        assert_eq!(generate(&mut sine, 500), 80.0);
+        assert_eq!(generate(&mut sine, 750), 50.0);
+        assert_eq!(generate(&mut sine, 1000), 20.0);
+    }
+
+    #[test]
+    fn square_switches_halfway_through_the_period() {
+        let mut square = oscillator(Waveform::Square);
+
+        assert_eq!(generate(&mut square, 0), 20.0);
+        assert_eq!(generate(&mut square, 499), 20.0);
+        assert_eq!(generate(&mut square, 500), 80.0);
+        assert_eq!(generate(&mut square, 1000), 20.0);
+    }
+
+    #[test]
+    fn saw_rises_and_drops_back() {
+        let mut saw = oscillator(Waveform::Saw);
+
+        assert_eq!(generate(&mut saw, 0), 20.0);
+        assert_eq!(generate(&mut saw, 500), 50.0);
+        assert_eq!(generate(&mut saw, 1000), 20.0);
+    }
+
+    #[test]
+    fn random_holds_a_repeatable_value_each_period() {
+        let mut random = oscillator(Waveform::Random);
+
+        let first = generate(&mut random, 0);
+        assert_eq!(generate(&mut random, 999), first);
+        assert!((20.0..=80.0).contains(&first));
+
+        let values: Vec<f64> = (0..10).map(|n| generate(&mut random, n * 1000)).collect();
+        assert!(values.windows(2).any(|pair| pair[0] != pair[1]));
+        assert_eq!(
+            values,
+            (0..10)
+                .map(|n| generate(&mut oscillator(Waveform::Random), n * 1000))
+                .collect::<Vec<f64>>()
+        );
+    }
+
+    #[test]
+    fn phase_shifts_the_start_of_the_cycle() {
+        let mut saw = oscillator(Waveform::Saw).phase(0.25);
+
+        assert_eq!(generate(&mut saw, 0), 35.0);
+        assert_eq!(generate(&mut saw, 750), 20.0);
+    }
+
+    #[test]
+    fn offsetting_the_phase_wraps_around_the_cycle() {
+        let mut saw = oscillator(Waveform::Saw).phase(0.5);
+        saw.offset_phase(0.75);
+
+        assert_eq!(generate(&mut saw, 0), 35.0);
+    }
+
+    #[test]
+    fn oscillators_run_from_their_start_time() {
+        let mut saw = oscillator(Waveform::Saw);
+        saw.set_start_time(Time::at(0, 0, 0, 500));
+
+        assert_eq!(generate(&mut saw, 0), 20.0);
+        assert_eq!(generate(&mut saw, 750), 35.0);
+    }
+
+    #[test]
+    fn percentages_oscillate_as_percentages() {
+        let mut saw = Oscillator::new(
+            Waveform::Saw,
+            Values::make_percentage(0.0),
+            Values::make_percentage(100.0),
+            Duration::from_secs(2),
+        );
+
+        assert_eq!(
+            saw.generate(&Time::at(0, 0, 1, 0), &Parameter::new(0, 0.0, 100.0)),
+            Some(Values::make_percentage(50.0))
+        );
+    }
+}
@@ -25,12 +25,12 @@ impl Value for Literal {
         self.value = value
     }
 
-    fn to_dmx(&self, parameter: &Parameter) -> Dmx {
+    fn to_dmx(&self, parameter: &Parameter) -> Vec<Dmx> {
         let difference = parameter.max() - parameter.min();
         let distance_to_min = (parameter.min() - self.value).abs();
         let factor = distance_to_min / difference;
 
-        Dmx::from_factor(factor)
+        Dmx::from_factor_with_resolution(factor, parameter.resolution())
     }
 }
 
@@ -43,23 +43,68 @@ impl Debug for Literal {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parameter::ParameterBuilder;
 
     #[test]
     fn to_dmx_simple() {
         let parameter = Parameter::new(0, 0.0, 100.0);
 
-        assert_eq!(Literal::new(0.0).to_dmx(&parameter), Dmx::new(0));
-        assert_eq!(Literal::new(50.0).to_dmx(&parameter), Dmx::new(128));
-        assert_eq!(Literal::new(100.0).to_dmx(&parameter), Dmx::new(255));
+        assert_eq!(Literal::new(0.0).to_dmx(&parameter), vec![Dmx::new(0)]);
+        assert_eq!(Literal::new(50.0).to_dmx(&parameter), vec![Dmx::new(128)]);
+        assert_eq!(Literal::new(100.0).to_dmx(&parameter), vec![Dmx::new(255)]);
     }
 
     #[test]
     fn to_dmx_complex() {
         let parameter = Parameter::new(0, -100.0, 100.0);
 
-        assert_eq!(Literal::new(-100.0).to_dmx(&parameter), Dmx::new(0));
-        assert_eq!(Literal::new(-50.0).to_dmx(&parameter), Dmx::new(64));
-        assert_eq!(Literal::new(0.0).to_dmx(&parameter), Dmx::new(128));
-        assert_eq!(Literal::new(100.0).to_dmx(&parameter), Dmx::new(255));
+        assert_eq!(Literal::new(-100.0).to_dmx(&parameter), vec![Dmx::new(0)]);
+        assert_eq!(Literal::new(-50.0).to_dmx(&parameter), vec![Dmx::new(64)]);
+        assert_eq!(Literal::new(0.0).to_dmx(&parameter), vec![Dmx::new(128)]);
+        assert_eq!(Literal::new(100.0).to_dmx(&parameter), vec![Dmx::new(255)]);
+    }
+
+    #[test]
+    fn to_dmx_16_bit() {
+        let parameter = ParameterBuilder::new(0)
+            .fine(1)
+            .min(-270.0)
+            .max(270.0)
+            .build();
+
+        assert_eq!(
+            Literal::new(-270.0).to_dmx(&parameter),
+            vec![Dmx::new(0), Dmx::new(0)]
+        );
+        assert_eq!(
+            Literal::new(0.0).to_dmx(&parameter),
+            vec![Dmx::new(128), Dmx::new(0)]
+        );
+        assert_eq!(
+            Literal::new(1.0).to_dmx(&parameter),
+            vec![Dmx::new(128), Dmx::new(121)]
+        );
+        assert_eq!(
+            Literal::new(270.0).to_dmx(&parameter),
+            vec![Dmx::new(255), Dmx::new(255)]
+        );
+    }
+
+    #[test]
+    fn to_dmx_24_bit() {
+        let parameter = ParameterBuilder::new(0).fine(1).ultra(2).build();
+
+        assert_eq!(
+            Literal::new(0.0).to_dmx(&parameter),
+            vec![Dmx::new(0), Dmx::new(0), Dmx::new(0)]
+        );
+        assert_eq!(
+            Literal::new(50.0).to_dmx(&parameter),
+            vec![Dmx::new(128), Dmx::new(0), Dmx::new(0)]
+        );
+        assert_eq!(
+            Literal::new(100.0).to_dmx(&parameter),
+            vec![Dmx::new(255), Dmx::new(255), Dmx::new(255)]
+        );
     }
 }
@@ -88,8 +88,8 @@ impl Value for Percentage {
         self.percentage = value
     }
 
-    fn to_dmx(&self, _parameter: &Parameter) -> crate::dmx::Dmx {
-        Dmx::from_factor(self.factor())
+    fn to_dmx(&self, parameter: &Parameter) -> Vec<Dmx> {
+        Dmx::from_factor_with_resolution(self.factor(), parameter.resolution())
     }
 }
 
@@ -102,7 +102,11 @@ impl Debug for Percentage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{dmx::Dmx, parameter::Parameter, value::Literal};
+    use crate::{
+        dmx::Dmx,
+        parameter::{Parameter, ParameterBuilder},
+        value::Literal,
+    };
 
     #[test]
     fn to_literal() {
@@ -128,17 +132,47 @@ mod tests {
     fn to_dmx_simple() {
         let parameter = Parameter::new(0, 0.0, 100.0);
 
-        assert_eq!(Percentage::new(0.0).to_dmx(&parameter), Dmx::new(0));
-        assert_eq!(Percentage::new(50.0).to_dmx(&parameter), Dmx::new(128));
-        assert_eq!(Percentage::new(100.0).to_dmx(&parameter), Dmx::new(255));
+        assert_eq!(Percentage::new(0.0).to_dmx(&parameter), vec![Dmx::new(0)]);
+        assert_eq!(
+            Percentage::new(50.0).to_dmx(&parameter),
+            vec![Dmx::new(128)]
+        );
+        assert_eq!(
+            Percentage::new(100.0).to_dmx(&parameter),
+            vec![Dmx::new(255)]
+        );
     }
 
     #[test]
     fn to_dmx_complex() {
         let parameter = Parameter::new(0, -100.0, 100.0);
 
-        assert_eq!(Percentage::new(0.0).to_dmx(&parameter), Dmx::new(0));
-        assert_eq!(Percentage::new(50.0).to_dmx(&parameter), Dmx::new(128));
-        assert_eq!(Percentage::new(100.0).to_dmx(&parameter), Dmx::new(255));
+        assert_eq!(Percentage::new(0.0).to_dmx(&parameter), vec![Dmx::new(0)]);
+        assert_eq!(
+            Percentage::new(50.0).to_dmx(&parameter),
+            vec![Dmx::new(128)]
+        );
+        assert_eq!(
+            Percentage::new(100.0).to_dmx(&parameter),
+            vec![Dmx::new(255)]
+        );
+    }
+
+    #[test]
+    fn to_dmx_16_bit() {
+        let parameter = ParameterBuilder::new(0).fine(1).build();
+
+        assert_eq!(
+            Percentage::new(0.0).to_dmx(&parameter),
+            vec![Dmx::new(0), Dmx::new(0)]
+        );
+        assert_eq!(
+            Percentage::new(25.0).to_dmx(&parameter),
+            vec![Dmx::new(64), Dmx::new(0)]
+        );
+        assert_eq!(
+            Percentage::new(100.0).to_dmx(&parameter),
+            vec![Dmx::new(255), Dmx::new(255)]
+        );
     }
 }
@@ -0,0 +1,144 @@
+//! A Slot Value
+//!
+//! A slot value selects one of the named ranges of a parameter, such as a gobo
+//! on a wheel or the open position of a shutter. The name only has meaning in
+//! context with a parameter, which provides the literal value of the range.
+//!
+//! # Examples
+//!
+//! ```
+//! use lumen::value::{Literal, Slot};
+//! use lumen::parameter::ParameterBuilder;
+//! let slot = Slot::new("stars");
+//! let parameter = ParameterBuilder::new(0)
+//!     .range("open", 0.0, 10.0)
+//!     .range("stars", 10.0, 20.0)
+//!     .build();
+//!
+//! assert_eq!(slot.to_literal(&parameter), Some(Literal::new(10.0)));
+//! ```
+
+use std::{fmt::Debug, sync::Arc};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::parameter::Parameter;
+
+use super::Literal;
+
+/// A `Slot` type represents a named range of a parameter.
+// The name is shared so that cloning the values of generators stays cheap.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Slot(Arc<str>);
+
+impl Slot {
+    /// Creates a new `Slot` selecting the range with the supplied name.
+    pub fn new(name: &str) -> Self {
+        Self(Arc::from(name))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// Converts a `Slot` to a `Literal` at the start of its range, if the
+    /// parameter has a range with that name.
+    pub fn to_literal(&self, parameter: &Parameter) -> Option<Literal> {
+        parameter
+            .range(&self.0)
+            .map(|range| Literal::new(range.from()))
+    }
+}
+
+impl Debug for Slot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\"", self.0)
+    }
+}
+
+impl Serialize for Slot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Slot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Slot::new(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        dmx::Dmx,
+        parameter::ParameterBuilder,
+        timecode::time::Time,
+        value::{
+            generator::{Fade, Generator, Static},
+            Values,
+        },
+    };
+
+    use super::*;
+
+    fn gobo() -> Parameter {
+        ParameterBuilder::new(0)
+            .max(255.0)
+            .range("open", 0.0, 9.0)
+            .range("stars", 10.0, 19.0)
+            .range("dots", 20.0, 29.0)
+            .build()
+    }
+
+    #[test]
+    fn slots_with_the_same_name_are_equal() {
+        assert_eq!(Slot::new("open"), Slot::new("open"));
+        assert_ne!(Slot::new("open"), Slot::new("closed"));
+    }
+
+    #[test]
+    fn slots_are_found_by_name_ignoring_case() {
+        let parameter = ParameterBuilder::new(0)
+            .range("Open", 0.0, 10.0)
+            .range("Strobe", 10.0, 100.0)
+            .build();
+
+        assert_eq!(
+            Slot::new("strobe").to_literal(&parameter),
+            Some(Literal::new(10.0))
+        );
+        assert_eq!(Slot::new("closed").to_literal(&parameter), None);
+    }
+
+    #[test]
+    fn slots_are_output_at_the_start_of_their_range() {
+        assert_eq!(
+            Values::make_slot("dots").to_dmx(&gobo()),
+            vec![Dmx::new(20)]
+        );
+        assert_eq!(
+            Values::make_slot("missing").to_dmx(&gobo()),
+            vec![Dmx::new(0)],
+            "an unknown slot is output at the default"
+        );
+    }
+
+    #[test]
+    fn fades_scroll_between_slots() {
+        let mut fade = Fade::new(
+            Box::new(Static::new(Values::make_slot("open"))),
+            Box::new(Static::new(Values::make_slot("dots"))),
+            Duration::from_secs(2),
+        );
+        fade.set_start_time(Time::at(0, 0, 0, 0));
+
+        assert_eq!(
+            fade.generate(&Time::at(0, 0, 1, 0), &gobo()),
+            Some(Values::make_literal(10.0))
+        );
+    }
+}
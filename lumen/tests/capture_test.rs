@@ -0,0 +1,219 @@
+use std::{io::Cursor, net::UdpSocket, time::Duration};
+
+use lumen::{
+    capture::{CaptureError, CaptureFrame, CapturePlayer, CaptureReader, CaptureWriter},
+    output::artnet::{ArtNet, ARTDMX_PACKET_LENGTH},
+    timecode::time::Time,
+    universe::{Multiverse, Universe},
+};
+
+fn multiverse(values: &[(usize, u8)]) -> Multiverse {
+    let mut multiverse = Multiverse::new();
+
+    for (universe_number, value) in values {
+        let mut bytes = [0; 512];
+        bytes[0] = *value;
+        bytes[10] = *value;
+        multiverse.insert(Universe::from_bytes(*universe_number, &bytes));
+    }
+
+    multiverse
+}
+
+fn capture(frames: &[(Time, Multiverse)]) -> Vec<u8> {
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+
+    for (time, multiverse) in frames {
+        writer.record(*time, multiverse).unwrap();
+    }
+
+    writer.finish().unwrap()
+}
+
+fn value(multiverse: &Multiverse, universe_number: usize) -> u8 {
+    multiverse.universe(universe_number).unwrap().bytes()[10]
+}
+
+#[test]
+fn frames_round_trip_through_a_capture() {
+    let bytes = capture(&[
+        (Time::at(0, 0, 0, 0), multiverse(&[(1, 10), (2, 20)])),
+        (Time::at(0, 0, 1, 500), multiverse(&[(1, 30)])),
+    ]);
+
+    let frames: Vec<_> = CaptureReader::new(Cursor::new(bytes))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].time, Time::at(0, 0, 0, 0));
+    assert_eq!(value(&frames[0].multiverse, 1), 10);
+    assert_eq!(value(&frames[0].multiverse, 2), 20);
+    assert_eq!(frames[1].time, Time::at(0, 0, 1, 500));
+    assert_eq!(frames[1].multiverse.universes().count(), 1);
+    assert_eq!(value(&frames[1].multiverse, 1), 30);
+}
+
+#[test]
+fn trailing_zeros_are_not_written() {
+    let dark = capture(&[(Time::at(0, 0, 0, 0), multiverse(&[(1, 0)]))]);
+    let lit = capture(&[(Time::at(0, 0, 0, 0), multiverse(&[(1, 255)]))]);
+
+    // Header, time, universe count, then the universe number and slot count
+    assert_eq!(dark.len(), 7 + 8 + 2 + 4);
+    assert_eq!(lit.len(), dark.len() + 11);
+}
+
+#[test]
+fn invalid_captures_are_rejected() {
+    assert!(matches!(
+        CaptureReader::new(Cursor::new(b"NOTCAP\x01".to_vec())),
+        Err(CaptureError::InvalidHeader)
+    ));
+    assert!(matches!(
+        CaptureReader::new(Cursor::new(b"LUMCAP\x09".to_vec())),
+        Err(CaptureError::UnsupportedVersion(9))
+    ));
+
+    let mut bytes = capture(&[(Time::at(0, 0, 0, 0), multiverse(&[(1, 255)]))]);
+    bytes.pop();
+
+    let mut reader = CaptureReader::new(Cursor::new(bytes)).unwrap();
+    assert!(matches!(reader.next(), Some(Err(CaptureError::Truncated))));
+}
+
+#[test]
+fn frames_must_be_recorded_in_order() {
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer
+        .record(Time::at(0, 0, 1, 0), &multiverse(&[(1, 10)]))
+        .unwrap();
+    writer
+        .record(Time::at(0, 0, 1, 0), &multiverse(&[(1, 20)]))
+        .unwrap();
+
+    assert_eq!(
+        writer.record(Time::at(0, 0, 0, 500), &multiverse(&[(1, 30)])),
+        Err(CaptureError::OutOfOrder(500))
+    );
+}
+
+#[test]
+fn captures_with_frames_out_of_order_are_rejected() {
+    let first = capture(&[(Time::at(0, 0, 1, 0), multiverse(&[(1, 10)]))]);
+    let second = capture(&[(Time::at(0, 0, 0, 0), multiverse(&[(1, 20)]))]);
+
+    // Join the frames of two captures, skipping the header of the second.
+    let mut bytes = first;
+    bytes.extend_from_slice(&second[7..]);
+
+    let reader = CaptureReader::new(Cursor::new(bytes)).unwrap();
+    assert!(matches!(
+        CapturePlayer::from_reader(reader),
+        Err(CaptureError::OutOfOrder(0))
+    ));
+
+    let frames = vec![
+        CaptureFrame {
+            time: Time::at(0, 0, 1, 0),
+            multiverse: multiverse(&[(1, 10)]),
+        },
+        CaptureFrame {
+            time: Time::at(0, 0, 0, 0),
+            multiverse: multiverse(&[(1, 20)]),
+        },
+    ];
+    assert!(matches!(
+        CapturePlayer::new(frames),
+        Err(CaptureError::OutOfOrder(0))
+    ));
+}
+
+#[test]
+fn captures_can_be_saved_and_loaded() {
+    let path = std::env::temp_dir().join(format!("lumen-capture-{}.cap", std::process::id()));
+
+    let mut writer = CaptureWriter::create(&path).unwrap();
+    writer
+        .record(Time::at(0, 0, 0, 0), &multiverse(&[(1, 10)]))
+        .unwrap();
+    writer.finish().unwrap();
+
+    let player = CapturePlayer::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(player.frames().len(), 1);
+}
+
+#[test]
+fn the_player_finds_the_frame_for_a_time() {
+    let player = CapturePlayer::from_reader(
+        CaptureReader::new(Cursor::new(capture(&[
+            (Time::at(0, 0, 1, 0), multiverse(&[(1, 10)])),
+            (Time::at(0, 0, 2, 0), multiverse(&[(1, 20)])),
+        ])))
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert!(player.frame_at(Time::at(0, 0, 0, 500)).is_none());
+    assert_eq!(
+        value(
+            &player.frame_at(Time::at(0, 0, 1, 0)).unwrap().multiverse,
+            1
+        ),
+        10
+    );
+    assert_eq!(
+        value(
+            &player.frame_at(Time::at(0, 0, 1, 999)).unwrap().multiverse,
+            1
+        ),
+        10
+    );
+    assert_eq!(
+        value(
+            &player.frame_at(Time::at(1, 0, 0, 0)).unwrap().multiverse,
+            1
+        ),
+        20
+    );
+}
+
+#[test]
+fn the_player_replays_frames_through_an_output() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+    let mut player = CapturePlayer::from_reader(
+        CaptureReader::new(Cursor::new(capture(&[
+            (Time::at(0, 0, 0, 0), multiverse(&[(1, 10)])),
+            (Time::at(0, 0, 1, 0), multiverse(&[(1, 20)])),
+        ])))
+        .unwrap(),
+    )
+    .unwrap();
+    let mut artnet = ArtNet::new();
+
+    assert!(player
+        .play(Time::at(0, 0, 0, 0), &mut artnet, &sender)
+        .unwrap());
+    // The same frame is not sent twice
+    assert!(!player
+        .play(Time::at(0, 0, 0, 500), &mut artnet, &sender)
+        .unwrap());
+    assert!(player
+        .play(Time::at(0, 0, 1, 0), &mut artnet, &sender)
+        .unwrap());
+
+    let mut buf = [0; ARTDMX_PACKET_LENGTH];
+    receiver.recv(&mut buf).unwrap();
+    assert_eq!(buf[18 + 10], 10);
+    receiver.recv(&mut buf).unwrap();
+    assert_eq!(buf[18 + 10], 20);
+}
@@ -21,7 +21,7 @@ fn simple() {
     let mut patch = Patch::new();
     let mut dimmer = FixtureProfile::new();
     dimmer.set_parameter(Param::Intensity, Parameter::new(0, 0.0, 100.0));
-    patch.patch(1, Address::new(1, 1), &dimmer);
+    patch.patch(1, Address::new(1, 1), dimmer).unwrap();
 
     let mut track = Track::new();
 
@@ -55,7 +55,7 @@ fn multi_fade() {
     let mut patch = Patch::new();
     let mut dimmer = FixtureProfile::new();
     dimmer.set_parameter(Param::Intensity, Parameter::new(0, 0.0, 100.0));
-    patch.patch(1, Address::new(1, 1), &dimmer);
+    patch.patch(1, Address::new(1, 1), dimmer).unwrap();
 
     let mut track = Track::new();
 
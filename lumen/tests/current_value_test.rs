@@ -3,7 +3,7 @@ use std::time::Duration;
 use lumen::{
     action::Apply,
     fixture::Fixture,
-    parameter::{Param, Parameter},
+    parameter::{Param, Parameter, ParameterBuilder},
     patch::FixtureProfile,
     timecode::time::Time,
     value::{
@@ -137,3 +137,17 @@ fn current_value_from_running_fade() {
 
 // TODO: a running fade with a current value referenced fade
 // TODO: Current Value from the previous Action
+
+#[test]
+fn unresolved_current_values_start_from_the_default() {
+    let parameter = ParameterBuilder::new(0)
+        .min(-270.0)
+        .max(270.0)
+        .default(90.0)
+        .build();
+
+    assert_eq!(
+        CurrentValue::new().generate(&Time::at(0, 0, 0, 0), &parameter),
+        Some(Values::make_literal(90.0))
+    );
+}
@@ -0,0 +1,92 @@
+use lumen::{
+    dmx::Dmx,
+    fixture::ResolvedFixture,
+    parameter::{Param, Parameter, ParameterBuilder},
+    patch::FixtureProfile,
+    value::Values,
+};
+
+fn moving_head() -> FixtureProfile {
+    let mut profile = FixtureProfile::new();
+    profile.set_parameter(Param::Intensity, Parameter::simple(0));
+    profile.set_parameter(
+        Param::Pan,
+        ParameterBuilder::new(1)
+            .fine(2)
+            .min(-270.0)
+            .max(270.0)
+            .build(),
+    );
+    profile.set_parameter(
+        Param::Tilt,
+        ParameterBuilder::new(3)
+            .fine(4)
+            .ultra(5)
+            .min(-135.0)
+            .max(135.0)
+            .build(),
+    );
+    profile
+}
+
+fn bytes(profile: &FixtureProfile, resolved_fixture: &ResolvedFixture) -> Vec<u8> {
+    profile
+        .to_dmx(resolved_fixture)
+        .iter()
+        .map(|dmx| dmx.byte())
+        .collect()
+}
+
+#[test]
+fn footprint_includes_fine_channels() {
+    assert_eq!(moving_head().footprint(), 6);
+}
+
+#[test]
+fn fine_channels_are_written_after_coarse_channels() {
+    let profile = moving_head();
+    let mut resolved_fixture = ResolvedFixture::new(1);
+    resolved_fixture.set(Param::Intensity, Values::make_percentage(100.0));
+    resolved_fixture.set(Param::Pan, Values::make_literal(1.0));
+    resolved_fixture.set(Param::Tilt, Values::make_percentage(100.0));
+
+    assert_eq!(
+        bytes(&profile, &resolved_fixture),
+        vec![255, 128, 121, 255, 255, 255]
+    );
+}
+
+#[test]
+fn defaults_are_written_to_fine_channels() {
+    let profile = moving_head();
+    let resolved_fixture = ResolvedFixture::new(1);
+
+    assert_eq!(
+        bytes(&profile, &resolved_fixture),
+        vec![0, 128, 0, 128, 0, 0]
+    );
+}
+
+#[test]
+fn ultra_offsets_need_a_fine_offset() {
+    let parameter = ParameterBuilder::new(0).ultra(1).build();
+
+    assert_eq!(parameter.resolution(), 1);
+    assert_eq!(parameter.offsets().collect::<Vec<usize>>(), vec![0]);
+}
+
+#[test]
+fn resolution_splits_factors_over_bytes() {
+    assert_eq!(
+        Dmx::from_factor_with_resolution(0.5, 1),
+        vec![Dmx::new(128)]
+    );
+    assert_eq!(
+        Dmx::from_factor_with_resolution(0.5, 2),
+        vec![Dmx::new(128), Dmx::new(0)]
+    );
+    assert_eq!(
+        Dmx::from_factor_with_resolution(1.0, 3),
+        vec![Dmx::new(255), Dmx::new(255), Dmx::new(255)]
+    );
+}
@@ -0,0 +1,447 @@
+use lumen::{
+    color::Colorspace,
+    parameter::Param,
+    patch::{
+        gdtf::{GdtfError, GdtfFixture},
+        library::{ProfileError, ProfileLibrary},
+    },
+};
+
+const GDTF_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/gdtf");
+
+fn open(file: &str) -> GdtfFixture {
+    GdtfFixture::open(format!("{}/{}", GDTF_DIRECTORY, file)).unwrap()
+}
+
+#[test]
+fn imports_fixture_type_details() {
+    let fixture = open("led_par.gdtf");
+
+    assert_eq!(fixture.name, "LED Par");
+    assert_eq!(fixture.manufacturer, "Lux Sample");
+}
+
+#[test]
+fn imports_a_profile_per_mode() {
+    let fixture = open("led_par.gdtf");
+    let modes: Vec<&str> = fixture
+        .modes
+        .iter()
+        .map(|mode| mode.name.as_str())
+        .collect();
+
+    assert_eq!(modes, vec!["6 Channel", "3 Channel"]);
+}
+
+#[test]
+fn maps_attributes_to_parameters_at_their_offsets() {
+    let fixture = open("led_par.gdtf");
+    let profile = &fixture.modes[0].profile;
+
+    assert_eq!(
+        profile.get_parameter(&Param::Intensity).unwrap().offset(),
+        0
+    );
+    assert_eq!(profile.get_parameter(&Param::Red).unwrap().offset(), 1);
+    assert_eq!(profile.get_parameter(&Param::Green).unwrap().offset(), 2);
+    assert_eq!(profile.get_parameter(&Param::Blue).unwrap().offset(), 3);
+    assert_eq!(profile.get_parameter(&Param::Amber).unwrap().offset(), 4);
+
+    let profile = &fixture.modes[1].profile;
+
+    assert!(profile.get_parameter(&Param::Intensity).is_none());
+    assert_eq!(profile.get_parameter(&Param::Red).unwrap().offset(), 0);
+    assert_eq!(profile.get_parameter(&Param::Blue).unwrap().offset(), 2);
+}
+
+#[test]
+fn detects_colorspace_from_mapped_parameters() {
+    let fixture = open("led_par.gdtf");
+
+    assert_eq!(
+        *fixture.modes[0].profile.colorspace(),
+        Some(Colorspace::RGBA)
+    );
+    assert_eq!(
+        *fixture.modes[1].profile.colorspace(),
+        Some(Colorspace::RGB)
+    );
+}
+
+#[test]
+fn maps_beam_attributes() {
+    let fixture = open("spot_500.gdtf");
+    let profile = &fixture.modes[0].profile;
+
+    let zoom = profile.get_parameter(&Param::Zoom).unwrap();
+    assert_eq!(zoom.offset(), 5);
+    assert_eq!(zoom.min(), 10.0);
+    assert_eq!(zoom.max(), 45.0);
+
+    let shutter = open("led_par.gdtf");
+    assert!(shutter.modes[0]
+        .profile
+        .get_parameter(&Param::Shutter)
+        .is_some());
+}
+
+#[test]
+fn reports_unmapped_attributes() {
+    let fixture = GdtfFixture::from_description(
+        r#"<GDTF DataVersion="1.1">
+            <FixtureType Name="Hazer" Manufacturer="Lux Sample">
+              <DMXModes>
+                <DMXMode Name="Default">
+                  <DMXChannels>
+                    <DMXChannel DMXBreak="1" Offset="1">
+                      <LogicalChannel Attribute="Haze" />
+                    </DMXChannel>
+                    <DMXChannel DMXBreak="1" Offset="2">
+                      <LogicalChannel Attribute="Fan" />
+                    </DMXChannel>
+                  </DMXChannels>
+                </DMXMode>
+              </DMXModes>
+            </FixtureType>
+        </GDTF>"#,
+    )
+    .unwrap();
+
+    assert_eq!(fixture.modes[0].unmapped, vec!["Haze", "Fan"]);
+    assert!(open("led_par.gdtf").modes[0].unmapped.is_empty());
+}
+
+#[test]
+fn maps_channel_sets_to_named_ranges() {
+    let fixture = GdtfFixture::from_description(
+        r#"<GDTF DataVersion="1.1">
+            <FixtureType Name="Spot" Manufacturer="Lux Sample">
+              <DMXModes>
+                <DMXMode Name="Default">
+                  <DMXChannels>
+                    <DMXChannel DMXBreak="1" Offset="1">
+                      <LogicalChannel Attribute="Gobo1">
+                        <ChannelFunction Name="Gobo" Attribute="Gobo1" DMXFrom="0/1">
+                          <ChannelSet Name="Open" DMXFrom="0/1" />
+                          <ChannelSet Name="Stars" DMXFrom="51/1" />
+                          <ChannelSet Name="" DMXFrom="102/1" />
+                        </ChannelFunction>
+                        <ChannelFunction Name="Spin" Attribute="Gobo1WheelSpin" DMXFrom="128/1" />
+                      </LogicalChannel>
+                    </DMXChannel>
+                  </DMXChannels>
+                </DMXMode>
+              </DMXModes>
+            </FixtureType>
+        </GDTF>"#,
+    )
+    .unwrap();
+
+    let gobo = fixture.modes[0]
+        .profile
+        .get_parameter(&Param::Gobo)
+        .unwrap();
+    let names: Vec<&str> = gobo.ranges().iter().map(|range| range.name()).collect();
+    assert_eq!(
+        names,
+        vec!["Gobo", "Open", "Stars", "Spin"],
+        "unnamed sets are skipped"
+    );
+
+    // Ranges are in the units of the parameter, 0 to 100 here, and run until
+    // the dmx value before the next set starts.
+    let stars = gobo.range("stars").unwrap();
+    assert_eq!(stars.from(), 20.0);
+    assert!((stars.to() - 101.0 / 255.0 * 100.0).abs() < 0.001);
+
+    let open = gobo.range("open").unwrap();
+    assert!((open.to() - 50.0 / 255.0 * 100.0).abs() < 0.001);
+}
+
+#[test]
+fn maps_channel_functions_to_named_ranges() {
+    let fixture = GdtfFixture::from_description(
+        r#"<GDTF DataVersion="1.1">
+            <FixtureType Name="Wash" Manufacturer="Lux Sample">
+              <DMXModes>
+                <DMXMode Name="Default">
+                  <DMXChannels>
+                    <DMXChannel DMXBreak="1" Offset="1">
+                      <LogicalChannel Attribute="Shutter1">
+                        <ChannelFunction Name="Closed" Attribute="Shutter1" DMXFrom="0/1" />
+                        <ChannelFunction Name="Open" Attribute="Shutter1" DMXFrom="32/1" />
+                        <ChannelFunction Name="Strobe" Attribute="Shutter1Strobe" DMXFrom="64/1">
+                          <ChannelSet Name="Slow" DMXFrom="64/1" />
+                          <ChannelSet Name="Fast" DMXFrom="160/1" />
+                        </ChannelFunction>
+                        <ChannelFunction Name="Open" Attribute="Shutter1" DMXFrom="255/1" />
+                      </LogicalChannel>
+                    </DMXChannel>
+                  </DMXChannels>
+                </DMXMode>
+              </DMXModes>
+            </FixtureType>
+        </GDTF>"#,
+    )
+    .unwrap();
+
+    let shutter = fixture.modes[0]
+        .profile
+        .get_parameter(&Param::Shutter)
+        .unwrap();
+    let names: Vec<&str> = shutter.ranges().iter().map(|range| range.name()).collect();
+    assert_eq!(
+        names,
+        vec!["Closed", "Open", "Strobe", "Slow", "Fast"],
+        "repeated names are only mapped once"
+    );
+
+    // Functions run until the next function starts, as do the channel sets
+    // at the end of a function.
+    let open = shutter.range("open").unwrap();
+    assert!((open.from() - 32.0 / 255.0 * 100.0).abs() < 0.001);
+    assert!((open.to() - 63.0 / 255.0 * 100.0).abs() < 0.001);
+
+    let fast = shutter.range("fast").unwrap();
+    assert!((fast.from() - 160.0 / 255.0 * 100.0).abs() < 0.001);
+    assert!((fast.to() - 254.0 / 255.0 * 100.0).abs() < 0.001);
+}
+
+#[test]
+fn maps_physical_ranges_and_defaults() {
+    let fixture = open("spot_500.gdtf");
+    let profile = &fixture.modes[0].profile;
+
+    let pan = profile.get_parameter(&Param::Pan).unwrap();
+    assert_eq!(pan.offset(), 0);
+    assert_eq!(pan.fine(), Some(1));
+    assert_eq!(pan.min(), -270.0);
+    assert_eq!(pan.max(), 270.0);
+    assert!(pan.default().abs() < 0.01);
+
+    let tilt = profile.get_parameter(&Param::Tilt).unwrap();
+    assert_eq!(tilt.offset(), 2);
+    assert_eq!(tilt.fine(), Some(3));
+    assert_eq!(tilt.min(), -135.0);
+    assert_eq!(tilt.max(), 135.0);
+
+    let intensity = profile.get_parameter(&Param::Intensity).unwrap();
+    assert_eq!(intensity.min(), 0.0);
+    assert_eq!(intensity.max(), 100.0);
+    assert_eq!(intensity.default(), 0.0);
+}
+
+#[test]
+fn footprint_includes_fine_channels() {
+    let fixture = open("spot_500.gdtf");
+
+    assert_eq!(fixture.modes[0].profile.footprint(), 9);
+}
+
+#[test]
+fn skips_virtual_channels() {
+    let fixture = open("spot_500.gdtf");
+    let profile = &fixture.modes[0].profile;
+
+    assert_eq!(
+        profile.get_parameter(&Param::Intensity).unwrap().offset(),
+        4
+    );
+}
+
+#[test]
+fn loads_modes_into_a_profile_library() {
+    let mut library = ProfileLibrary::new();
+    let names = library
+        .load_gdtf(format!("{}/led_par.gdtf", GDTF_DIRECTORY))
+        .unwrap();
+
+    assert_eq!(
+        names,
+        vec![
+            "Lux Sample LED Par (6 Channel)",
+            "Lux Sample LED Par (3 Channel)"
+        ]
+    );
+    assert!(library.get("Lux Sample LED Par (3 Channel)").is_some());
+}
+
+#[test]
+fn loads_a_directory_of_gdtf_files() {
+    let library = ProfileLibrary::from_directory(GDTF_DIRECTORY).unwrap();
+
+    assert_eq!(library.len(), 3);
+    assert!(library.get("Lux Sample Spot 500 (Standard)").is_some());
+}
+
+fn description(modes: &[(&str, &[(usize, &str)])]) -> String {
+    let modes: String = modes
+        .iter()
+        .map(|(name, channels)| {
+            let channels: String = channels
+                .iter()
+                .map(|(offset, attribute)| {
+                    format!(
+                        r#"<DMXChannel DMXBreak="1" Offset="{}">
+                             <LogicalChannel Attribute="{}" />
+                           </DMXChannel>"#,
+                        offset, attribute
+                    )
+                })
+                .collect();
+
+            format!(
+                r#"<DMXMode Name="{}"><DMXChannels>{}</DMXChannels></DMXMode>"#,
+                name, channels
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<GDTF DataVersion="1.1">
+             <FixtureType Name="Dimmer" Manufacturer="Lux Sample">
+               <DMXModes>{}</DMXModes>
+             </FixtureType>
+           </GDTF>"#,
+        modes
+    )
+}
+
+#[test]
+fn maps_repeated_attributes_to_their_first_channel() {
+    let fixture = GdtfFixture::from_description(&description(&[(
+        "Default",
+        &[(1, "Dimmer"), (2, "Dimmer")],
+    )]))
+    .unwrap();
+    let mode = &fixture.modes[0];
+
+    assert_eq!(
+        mode.profile
+            .get_parameter(&Param::Intensity)
+            .unwrap()
+            .offset(),
+        0
+    );
+    assert_eq!(mode.unmapped, vec!["Dimmer"]);
+}
+
+#[test]
+fn imports_multi_cell_fixtures() {
+    let cell = |cell: usize, offset: usize| -> String {
+        ["ColorAdd_R", "ColorAdd_G", "ColorAdd_B"]
+            .iter()
+            .enumerate()
+            .map(|(i, attribute)| {
+                format!(
+                    r#"<DMXChannel DMXBreak="1" Offset="{}" Geometry="Cell {}">
+                         <LogicalChannel Attribute="{}" />
+                       </DMXChannel>"#,
+                    offset + i,
+                    cell,
+                    attribute
+                )
+            })
+            .collect()
+    };
+    let cells: String = (0..4).map(|i| cell(i + 1, 2 + i * 3)).collect();
+
+    let fixture = GdtfFixture::from_description(&format!(
+        r#"<GDTF DataVersion="1.1">
+             <FixtureType Name="Batten" Manufacturer="Lux Sample">
+               <DMXModes>
+                 <DMXMode Name="Single Cell" Geometry="Body">
+                   <DMXChannels>
+                     <DMXChannel DMXBreak="1" Offset="1" Geometry="Body">
+                       <LogicalChannel Attribute="Dimmer" />
+                     </DMXChannel>
+                     {}
+                   </DMXChannels>
+                 </DMXMode>
+                 <DMXMode Name="4 Cell" Geometry="Body">
+                   <DMXChannels>
+                     <DMXChannel DMXBreak="1" Offset="1" Geometry="Body">
+                       <LogicalChannel Attribute="Dimmer" />
+                     </DMXChannel>
+                     {}
+                   </DMXChannels>
+                 </DMXMode>
+               </DMXModes>
+             </FixtureType>
+           </GDTF>"#,
+        cell(1, 2),
+        cells
+    ))
+    .unwrap();
+
+    let modes: Vec<&str> = fixture
+        .modes
+        .iter()
+        .map(|mode| mode.name.as_str())
+        .collect();
+    assert_eq!(modes, vec!["Single Cell", "4 Cell"]);
+    assert!(fixture.skipped.is_empty());
+
+    let mode = &fixture.modes[1];
+    assert_eq!(mode.profile.footprint(), 4);
+    assert_eq!(mode.profile.get_parameter(&Param::Red).unwrap().offset(), 1);
+    assert_eq!(
+        mode.profile.get_parameter(&Param::Blue).unwrap().offset(),
+        3
+    );
+    assert_eq!(mode.unmapped.len(), 9);
+}
+
+#[test]
+fn skips_modes_that_cannot_be_imported() {
+    let fixture = GdtfFixture::from_description(&description(&[
+        ("Default", &[(1, "Dimmer")]),
+        ("Broken", &[(1, "Dimmer"), (1, "Pan")]),
+    ]))
+    .unwrap();
+
+    assert_eq!(fixture.modes.len(), 1);
+    assert_eq!(fixture.modes[0].name, "Default");
+    assert_eq!(fixture.skipped.len(), 1);
+    assert_eq!(fixture.skipped[0].0, "Broken");
+    assert!(matches!(fixture.skipped[0].1, GdtfError::Invalid(_)));
+}
+
+#[test]
+fn rejects_fixtures_where_no_mode_can_be_imported() {
+    let result =
+        GdtfFixture::from_description(&description(&[("Default", &[(1, "Dimmer"), (1, "Pan")])]));
+
+    assert!(matches!(result, Err(GdtfError::Invalid(_))));
+}
+
+#[test]
+fn modes_are_only_loaded_if_they_all_can_be() {
+    let fixture = GdtfFixture::from_description(&description(&[
+        ("Default", &[(1, "Dimmer")]),
+        ("Extended", &[(1, "Dimmer"), (2, "Pan")]),
+        ("Default", &[(1, "Pan")]),
+    ]))
+    .unwrap();
+
+    let mut library = ProfileLibrary::new();
+    assert!(matches!(
+        library.insert_gdtf(fixture),
+        Err(ProfileError::DuplicateProfile(_))
+    ));
+    assert_eq!(library.len(), 0);
+}
+
+#[test]
+fn rejects_descriptions_without_a_fixture_type() {
+    let result = GdtfFixture::from_description(r#"<GDTF DataVersion="1.1"></GDTF>"#);
+
+    assert!(matches!(result, Err(GdtfError::Invalid(_))));
+}
+
+#[test]
+fn rejects_files_that_are_not_archives() {
+    let result = GdtfFixture::from_reader(std::io::Cursor::new(b"not a zip file".to_vec()));
+
+    assert!(matches!(result, Err(GdtfError::Archive(_))));
+}
@@ -0,0 +1,163 @@
+use lumen::{
+    action::{Action, Apply, ApplyGroup},
+    address::Address,
+    dmx::Dmx,
+    parameter::{Param, ParameterBuilder},
+    patch::FixtureProfile,
+    timecode::time::Time,
+    track::Track,
+    value::{
+        generator::{Home, Static},
+        Values,
+    },
+    Environment, Patch, QueryBuilder,
+};
+
+fn moving_head() -> FixtureProfile {
+    let mut profile = FixtureProfile::new();
+    profile.set_parameter(
+        Param::Intensity,
+        ParameterBuilder::new(0).default(0.0).build(),
+    );
+    profile.set_parameter(
+        Param::Pan,
+        ParameterBuilder::new(1)
+            .min(-270.0)
+            .max(270.0)
+            .default(0.0)
+            .build(),
+    );
+    profile.set_parameter(
+        Param::Tilt,
+        ParameterBuilder::new(2)
+            .min(-135.0)
+            .max(135.0)
+            .default(45.0)
+            .build(),
+    );
+    profile
+}
+
+fn apply(param: Param, value: f64) -> Apply {
+    Apply::new(param, Box::new(Static::new(Values::make_literal(value))))
+}
+
+fn environment(actions: Vec<(Time, Action)>) -> (Environment, Patch) {
+    let mut patch = Patch::new();
+    patch.patch(1, Address::new(1, 1), moving_head()).unwrap();
+
+    let mut environment = Environment::new();
+    environment.fixtures.create_with_id(1);
+
+    let mut track = Track::new();
+    for (time, action) in actions {
+        track.add_action(time, action);
+    }
+    environment.add_track(track);
+
+    (environment, patch)
+}
+
+fn value(environment: &mut Environment, patch: &Patch, time: Time, param: Param) -> Values {
+    environment.run_to_time(time, patch);
+    environment.fixtures.resolve(time, patch)[&1]
+        .get_value(&param)
+        .unwrap()
+        .clone()
+}
+
+fn programmed() -> Action {
+    let mut group = ApplyGroup::new(QueryBuilder::new().all().build());
+    group.add_apply(apply(Param::Intensity, 100.0));
+    group.add_apply(apply(Param::Pan, 90.0));
+    group.add_apply(apply(Param::Tilt, -90.0));
+
+    let mut action = Action::new();
+    action.add_group(group);
+    action
+}
+
+#[test]
+fn unprogrammed_parameters_are_output_at_home() {
+    let (mut environment, patch) = environment(vec![]);
+    environment.run_to_time(Time::at(0, 0, 0, 0), &patch);
+    let resolved = environment.fixtures.resolve(Time::at(0, 0, 0, 0), &patch);
+
+    let dmx = patch.to_dmx(&1, &resolved[&1]).unwrap();
+    assert_eq!(
+        dmx.iter().collect::<Vec<_>>(),
+        vec![&Dmx::new(0), &Dmx::new(128), &Dmx::new(170)]
+    );
+}
+
+#[test]
+fn home_returns_programmed_parameters_to_their_defaults() {
+    let mut group = ApplyGroup::new(QueryBuilder::new().all().build());
+    group.add_home(Box::new(Home::new()));
+    let mut home = Action::new();
+    home.add_group(group);
+
+    let (mut environment, patch) = environment(vec![
+        (Time::at(0, 0, 0, 0), programmed()),
+        (Time::at(0, 0, 1, 0), home),
+    ]);
+
+    assert_eq!(
+        value(&mut environment, &patch, Time::at(0, 0, 0, 0), Param::Tilt),
+        Values::make_literal(-90.0)
+    );
+    assert_eq!(
+        value(&mut environment, &patch, Time::at(0, 0, 1, 0), Param::Tilt),
+        Values::make_literal(45.0)
+    );
+    assert_eq!(
+        value(&mut environment, &patch, Time::at(0, 0, 1, 0), Param::Pan),
+        Values::make_literal(0.0)
+    );
+}
+
+#[test]
+fn applies_alongside_home_are_applied_after_it() {
+    let mut group = ApplyGroup::new(QueryBuilder::new().all().build());
+    group.add_home(Box::new(Home::new()));
+    group.add_apply(apply(Param::Intensity, 50.0));
+    let mut home = Action::new();
+    home.add_group(group);
+
+    let (mut environment, patch) = environment(vec![
+        (Time::at(0, 0, 0, 0), programmed()),
+        (Time::at(0, 0, 1, 0), home),
+    ]);
+
+    let time = Time::at(0, 0, 1, 0);
+    assert_eq!(
+        value(&mut environment, &patch, time, Param::Intensity),
+        Values::make_literal(50.0)
+    );
+    assert_eq!(
+        value(&mut environment, &patch, time, Param::Pan),
+        Values::make_literal(0.0)
+    );
+}
+
+#[test]
+fn home_only_affects_what_was_applied_before_it() {
+    let mut group = ApplyGroup::new(QueryBuilder::new().all().build());
+    group.add_apply(apply(Param::Intensity, 50.0));
+    group.add_home(Box::new(Home::new()));
+    group.add_apply(apply(Param::Pan, 90.0));
+    let mut action = Action::new();
+    action.add_group(group);
+
+    let (mut environment, patch) = environment(vec![(Time::at(0, 0, 0, 0), action)]);
+
+    let time = Time::at(0, 0, 0, 0);
+    assert_eq!(
+        value(&mut environment, &patch, time, Param::Intensity),
+        Values::make_literal(0.0)
+    );
+    assert_eq!(
+        value(&mut environment, &patch, time, Param::Pan),
+        Values::make_literal(90.0)
+    );
+}
@@ -0,0 +1,159 @@
+use std::{net::UdpSocket, sync::Arc, time::Duration};
+
+use lumen::{
+    address::Address,
+    input::sacn::SacnReceiver,
+    merge::{Merge, SourceId, DEFAULT_PRIORITY},
+    output::{Output, SACN},
+    parameter::{Param, Parameter, ParameterBuilder},
+    patch::FixtureProfile,
+    universe::Universe,
+    Patch,
+};
+
+// A fixture with 16 bit intensity at offsets 0 and 1, and pan at offset 2.
+fn patch() -> Patch {
+    let mut profile = FixtureProfile::new();
+    profile.set_parameter(Param::Intensity, ParameterBuilder::new(0).fine(1).build());
+    profile.set_parameter(Param::Pan, Parameter::simple(2));
+
+    let mut patch = Patch::new();
+    patch
+        .patch(1, Address::new(1, 1), Arc::new(profile))
+        .unwrap();
+    patch
+}
+
+fn universe(values: &[u8]) -> Universe {
+    Universe::from_bytes(1, values)
+}
+
+fn merged(merge: &Merge) -> [u8; 512] {
+    merge.merge().universe(1).unwrap().bytes()
+}
+
+fn wing() -> SourceId {
+    SourceId::Named("wing".to_string())
+}
+
+#[test]
+fn intensity_channels_come_from_the_patch() {
+    let merge = Merge::with_patch(&patch());
+
+    assert!(merge.is_intensity(1, 0));
+    assert!(merge.is_intensity(1, 1));
+    assert!(!merge.is_intensity(1, 2));
+    assert!(!merge.is_intensity(2, 0));
+}
+
+#[test]
+fn intensity_is_merged_highest_takes_precedence() {
+    let mut merge = Merge::with_patch(&patch());
+
+    merge.update(SourceId::Engine, DEFAULT_PRIORITY, &universe(&[200, 0]));
+    merge.update(wing(), DEFAULT_PRIORITY, &universe(&[100, 50]));
+
+    assert_eq!(&merged(&merge)[0..2], &[200, 50]);
+}
+
+#[test]
+fn other_channels_are_merged_latest_takes_precedence() {
+    let mut merge = Merge::with_patch(&patch());
+
+    merge.update(SourceId::Engine, DEFAULT_PRIORITY, &universe(&[0, 0, 10]));
+    merge.update(wing(), DEFAULT_PRIORITY, &universe(&[0, 0, 20]));
+    assert_eq!(merged(&merge)[2], 20);
+
+    merge.update(SourceId::Engine, DEFAULT_PRIORITY, &universe(&[0, 0, 30]));
+    assert_eq!(merged(&merge)[2], 30);
+
+    // Sending the same value again is not a change
+    merge.update(wing(), DEFAULT_PRIORITY, &universe(&[255, 0, 20]));
+    assert_eq!(merged(&merge)[2], 30);
+}
+
+#[test]
+fn only_the_highest_priority_sources_are_merged() {
+    let mut merge = Merge::with_patch(&patch());
+
+    merge.update(SourceId::Engine, DEFAULT_PRIORITY, &universe(&[255, 0, 10]));
+    merge.update(wing(), 150, &universe(&[50, 0, 20]));
+
+    assert_eq!(&merged(&merge)[0..3], &[50, 0, 20]);
+
+    merge.remove_source(&wing());
+
+    assert_eq!(&merged(&merge)[0..3], &[255, 0, 10]);
+}
+
+#[test]
+fn priority_is_per_universe() {
+    let mut merge = Merge::new();
+
+    merge.update(SourceId::Engine, DEFAULT_PRIORITY, &universe(&[10]));
+    merge.update(
+        SourceId::Engine,
+        DEFAULT_PRIORITY,
+        &Universe::from_bytes(2, &[10]),
+    );
+    merge.update(wing(), 150, &universe(&[20]));
+
+    let multiverse = merge.merge();
+
+    assert_eq!(multiverse.universe(1).unwrap().bytes()[0], 20);
+    assert_eq!(multiverse.universe(2).unwrap().bytes()[0], 10);
+}
+
+// A console sending sACN to a receiver over the loopback.
+fn console() -> (SACN, UdpSocket, SacnReceiver) {
+    let receiver = SacnReceiver::bind("127.0.0.1:0").unwrap();
+    receiver
+        .socket()
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .connect(receiver.socket().local_addr().unwrap())
+        .unwrap();
+
+    let sacn = SACN::new("console".to_string(), uuid::Uuid::new_v4());
+
+    (sacn, socket, receiver)
+}
+
+#[test]
+fn sacn_sources_that_are_lost_are_removed() {
+    let (mut sacn, socket, mut receiver) = console();
+    let mut merge = Merge::new();
+    merge.update(SourceId::Engine, DEFAULT_PRIORITY, &universe(&[10]));
+
+    sacn.send_universe(&universe(&[20]), &socket).unwrap();
+    receiver.receive().unwrap();
+    merge.update_sacn(&receiver);
+    assert_eq!(merged(&merge)[0], 20);
+
+    sacn.terminate_universe(&universe(&[20]), &socket).unwrap();
+    receiver.receive().unwrap();
+    merge.update_sacn(&receiver);
+    assert_eq!(merged(&merge)[0], 10);
+}
+
+#[test]
+fn sacn_priority_changes_without_data_changes() {
+    let (mut sacn, socket, mut receiver) = console();
+    let mut merge = Merge::new();
+    merge.update(SourceId::Engine, DEFAULT_PRIORITY, &universe(&[10]));
+
+    sacn.set_priority(Some(50));
+    sacn.send_universe(&universe(&[20]), &socket).unwrap();
+    receiver.receive().unwrap();
+    merge.update_sacn(&receiver);
+    assert_eq!(merged(&merge)[0], 10);
+
+    sacn.set_priority(Some(150));
+    sacn.send_universe(&universe(&[20]), &socket).unwrap();
+    receiver.receive().unwrap();
+    merge.update_sacn(&receiver);
+    assert_eq!(merged(&merge)[0], 20);
+}
@@ -0,0 +1,489 @@
+use std::sync::Arc;
+
+use lumen::{
+    address::Address,
+    color::Colorspace,
+    dmx::Dmx,
+    fixture::ResolvedFixture,
+    parameter::{Param, Parameter, ParameterBuilder},
+    patch::{modifiers::FixtureModifiers, FixtureProfile, PatchConflict, PatchError},
+    timecode::time::Time,
+    value::Values,
+    Environment, Patch,
+};
+
+fn profile(footprint: usize) -> Arc<FixtureProfile> {
+    let mut profile = FixtureProfile::new();
+    profile.set_parameter(Param::Intensity, Parameter::simple(0));
+    if footprint > 1 {
+        profile.set_parameter(Param::Control, Parameter::simple(footprint - 1));
+    }
+    Arc::new(profile)
+}
+
+#[test]
+fn patching_fixtures() {
+    let dimmer = profile(1);
+    let mut patch = Patch::new();
+
+    assert!(patch.patch(1, Address::new(1, 1), dimmer.clone()).is_ok());
+    assert!(patch.patch(2, Address::new(1, 2), dimmer.clone()).is_ok());
+
+    assert_eq!(patch.get_address(&2), Ok(&Address::new(1, 2)));
+    assert!(patch.get_profile(&1).is_ok());
+    assert!(patch.is_patched(&1));
+}
+
+#[test]
+fn lookups_on_unpatched_fixtures_are_errors() {
+    let patch = Patch::new();
+
+    assert_eq!(patch.get_address(&1), Err(PatchError::NotPatched(1)));
+    assert!(matches!(
+        patch.get_profile(&1),
+        Err(PatchError::NotPatched(1))
+    ));
+}
+
+#[test]
+fn fixtures_can_only_be_patched_once() {
+    let dimmer = profile(1);
+    let mut patch = Patch::new();
+
+    patch.patch(1, Address::new(1, 1), dimmer.clone()).unwrap();
+
+    assert_eq!(
+        patch.patch(1, Address::new(2, 1), dimmer.clone()),
+        Err(PatchError::AlreadyPatched(1))
+    );
+    assert_eq!(patch.get_address(&1), Ok(&Address::new(1, 1)));
+}
+
+#[test]
+fn invalid_addresses_are_rejected() {
+    let dimmer = profile(1);
+    let mut patch = Patch::new();
+
+    for address in [Address::new(0, 1), Address::new(1, 0), Address::new(1, 513)] {
+        assert_eq!(
+            patch.patch(1, address, dimmer.clone()),
+            Err(PatchError::InvalidAddress(1, address))
+        );
+    }
+}
+
+#[test]
+fn footprints_must_fit_in_their_universe() {
+    let mut patch = Patch::new();
+
+    assert!(patch.patch(1, Address::new(1, 509), profile(4)).is_ok());
+    assert!(patch.patch(2, Address::new(2, 512), profile(1)).is_ok());
+    assert_eq!(
+        patch.patch(3, Address::new(3, 510), profile(4)),
+        Err(PatchError::FootprintOverflow(3, Address::new(3, 510), 4))
+    );
+    assert_eq!(
+        patch.force_patch(3, Address::new(3, 512), profile(2)),
+        Err(PatchError::FootprintOverflow(3, Address::new(3, 512), 2))
+    );
+    assert!(!patch.is_patched(&3));
+}
+
+#[test]
+fn overlapping_footprints_are_rejected() {
+    let quad = profile(4);
+    let mut patch = Patch::new();
+
+    patch.patch(1, Address::new(1, 1), quad.clone()).unwrap();
+
+    assert_eq!(
+        patch.patch(2, Address::new(1, 3), quad.clone()),
+        Err(PatchError::AddressConflict(PatchConflict {
+            fixture: 2,
+            other: 1,
+            universe: 1,
+            start: 3,
+            end: 4,
+        }))
+    );
+
+    assert!(patch.patch(2, Address::new(1, 5), quad.clone()).is_ok());
+    assert!(patch.patch(3, Address::new(2, 1), quad.clone()).is_ok());
+}
+
+#[test]
+fn unpatching_fixtures() {
+    let dimmer = profile(1);
+    let mut patch = Patch::new();
+
+    patch.patch(1, Address::new(1, 1), dimmer.clone()).unwrap();
+
+    assert_eq!(patch.unpatch(&1), Ok(()));
+    assert_eq!(patch.unpatch(&1), Err(PatchError::NotPatched(1)));
+    assert!(patch.patch(2, Address::new(1, 1), dimmer.clone()).is_ok());
+}
+
+#[test]
+fn conflicts_are_empty_for_a_valid_patch() {
+    let quad = profile(4);
+    let mut patch = Patch::new();
+
+    for n in 0..4 {
+        patch
+            .patch(n + 1, Address::new(1, (n * 4 + 1) as u16), quad.clone())
+            .unwrap();
+    }
+
+    assert!(patch.conflicts().is_empty());
+}
+
+#[test]
+fn conflicts_report_every_overlap() {
+    let quad = profile(4);
+    let mut patch = Patch::new();
+
+    patch.patch(1, Address::new(1, 1), quad.clone()).unwrap();
+    patch
+        .force_patch(2, Address::new(1, 1), quad.clone())
+        .unwrap();
+    patch
+        .force_patch(3, Address::new(1, 4), quad.clone())
+        .unwrap();
+    patch
+        .force_patch(4, Address::new(2, 1), quad.clone())
+        .unwrap();
+
+    assert_eq!(
+        patch.conflicts(),
+        vec![
+            PatchConflict {
+                fixture: 1,
+                other: 2,
+                universe: 1,
+                start: 1,
+                end: 4,
+            },
+            PatchConflict {
+                fixture: 1,
+                other: 3,
+                universe: 1,
+                start: 4,
+                end: 4,
+            },
+            PatchConflict {
+                fixture: 2,
+                other: 3,
+                universe: 1,
+                start: 4,
+                end: 4,
+            },
+        ]
+    );
+}
+
+#[test]
+fn conflicts_display_their_addresses() {
+    let conflict = PatchConflict {
+        fixture: 1,
+        other: 2,
+        universe: 1,
+        start: 3,
+        end: 4,
+    };
+
+    assert_eq!(
+        conflict.to_string(),
+        "fixtures 1 and 2 overlap at 1.003 to 1.004"
+    );
+}
+
+#[test]
+fn resolving_skips_unpatched_fixtures() {
+    let dimmer = profile(1);
+    let mut environment = Environment::new();
+    let mut patch = Patch::new();
+
+    environment.fixtures.create_with_id(1);
+    environment.fixtures.create_with_id(2);
+    patch.patch(1, Address::new(1, 1), dimmer.clone()).unwrap();
+
+    let resolved = environment.fixtures.resolve(Time::at(0, 0, 0, 0), &patch);
+
+    assert!(resolved.contains_key(&1));
+    assert!(!resolved.contains_key(&2));
+}
+
+#[test]
+fn patches_own_their_profiles() {
+    let mut patch = Patch::new();
+
+    {
+        let mut dimmer = FixtureProfile::new();
+        dimmer.set_parameter(Param::Intensity, Parameter::simple(0));
+        patch.patch(1, Address::new(1, 1), dimmer).unwrap();
+    }
+
+    let shared = patch.get_shared_profile(&1).unwrap();
+    patch.patch(2, Address::new(1, 2), shared).unwrap();
+
+    assert!(patch
+        .get_profile(&2)
+        .unwrap()
+        .get_parameter(&Param::Intensity)
+        .is_some());
+}
+
+#[test]
+fn patches_round_trip_through_json() {
+    let dimmer = profile(1);
+    let mut quad = FixtureProfile::new();
+    quad.set_colorspace(Colorspace::RGBA);
+    quad.set_parameter(Param::Red, Parameter::simple(0));
+    quad.set_parameter(Param::Green, Parameter::simple(1));
+    quad.set_parameter(Param::Blue, Parameter::simple(2));
+    quad.set_parameter(Param::Amber, ParameterBuilder::new(3).fine(4).build());
+    let quad = Arc::new(quad);
+
+    let mut patch = Patch::new();
+    patch.patch(1, Address::new(1, 1), dimmer.clone()).unwrap();
+    patch.patch(2, Address::new(1, 3), dimmer).unwrap();
+    patch.patch(101, Address::new(2, 1), quad.clone()).unwrap();
+
+    let loaded = Patch::from_json(&patch.to_json()).unwrap();
+
+    assert_eq!(loaded.len(), 3);
+    assert_eq!(loaded.get_address(&2), Ok(&Address::new(1, 3)));
+    assert_eq!(loaded.get_profile(&101).unwrap(), quad.as_ref());
+    assert_eq!(loaded.get_profile(&101).unwrap().footprint(), 5);
+
+    // Fixtures that shared a profile still share it once loaded
+    assert!(Arc::ptr_eq(
+        &loaded.get_shared_profile(&1).unwrap(),
+        &loaded.get_shared_profile(&2).unwrap()
+    ));
+}
+
+#[test]
+fn shared_profiles_are_written_once() {
+    let dimmer = profile(1);
+    let mut patch = Patch::new();
+
+    for n in 1..=10 {
+        patch
+            .patch(n, Address::new(1, n as u16), dimmer.clone())
+            .unwrap();
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&patch.to_json()).unwrap();
+
+    assert_eq!(json["profiles"].as_array().unwrap().len(), 1);
+    assert_eq!(json["fixtures"]["10"]["profile"], 0);
+    assert_eq!(json["fixtures"]["10"]["address"]["address"], 10);
+}
+
+fn moving_head() -> Arc<FixtureProfile> {
+    let mut profile = FixtureProfile::new();
+    profile.set_parameter(Param::Pan, Parameter::new(0, -270.0, 270.0));
+    profile.set_parameter(Param::Tilt, Parameter::new(1, -135.0, 135.0));
+    Arc::new(profile)
+}
+
+fn pan_tilt(pan: f64, tilt: f64) -> ResolvedFixture {
+    let mut resolved = ResolvedFixture::new(1);
+    resolved.set(Param::Pan, Values::make_literal(pan));
+    resolved.set(Param::Tilt, Values::make_literal(tilt));
+    resolved
+}
+
+#[test]
+fn modifiers_are_applied_when_converting_to_dmx() {
+    let mut patch = Patch::new();
+    patch.patch(1, Address::new(1, 1), moving_head()).unwrap();
+    patch.patch(2, Address::new(1, 3), moving_head()).unwrap();
+
+    let mut hung = FixtureModifiers::new();
+    hung.set_swap_pan_tilt(true);
+    hung.set_invert(Param::Pan, true);
+    patch.set_modifiers(&2, hung).unwrap();
+
+    let resolved = pan_tilt(0.0, 135.0);
+
+    assert_eq!(
+        patch
+            .to_dmx(&1, &resolved)
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![&Dmx::new(128), &Dmx::new(255)]
+    );
+    assert_eq!(
+        patch
+            .to_dmx(&2, &resolved)
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![&Dmx::new(64), &Dmx::new(128)],
+        "tilt is swapped to pan and inverted, pan is swapped to tilt"
+    );
+    assert_eq!(
+        patch.set_modifiers(&3, FixtureModifiers::new()),
+        Err(PatchError::NotPatched(3))
+    );
+}
+
+#[test]
+fn modifiers_round_trip_through_json() {
+    let mut patch = Patch::new();
+    patch.patch(1, Address::new(1, 1), moving_head()).unwrap();
+    patch.patch(2, Address::new(1, 3), moving_head()).unwrap();
+
+    let mut modifiers = FixtureModifiers::new();
+    modifiers.set_invert(Param::Tilt, true);
+    modifiers.set_offset(Param::Pan, 45.0);
+    patch.set_modifiers(&2, modifiers.clone()).unwrap();
+
+    let json = patch.to_json();
+    let loaded = Patch::from_json(&json).unwrap();
+
+    assert!(loaded.get_modifiers(&1).unwrap().is_empty());
+    assert_eq!(loaded.get_modifiers(&2), Ok(&modifiers));
+
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert!(
+        json["fixtures"]["1"].get("modifiers").is_none(),
+        "fixtures without modifiers don't write them"
+    );
+}
+
+fn rig() -> Patch {
+    let dimmer = profile(1);
+    let head = moving_head();
+    let mut patch = Patch::new();
+
+    for n in 1..=20 {
+        let profile = if n % 3 == 0 {
+            head.clone()
+        } else {
+            dimmer.clone()
+        };
+        patch
+            .patch(n, Address::new(1, n as u16 * 2), profile)
+            .unwrap();
+    }
+
+    let mut modifiers = FixtureModifiers::new();
+    modifiers.set_invert(Param::Pan, true);
+    modifiers.set_invert(Param::Tilt, true);
+    modifiers.set_offset(Param::Intensity, 10.0);
+    patch.set_modifiers(&3, modifiers).unwrap();
+
+    patch
+}
+
+#[test]
+fn the_same_patch_is_always_saved_the_same_way() {
+    let json = rig().to_json();
+
+    assert_eq!(rig().to_json(), json);
+    assert_eq!(Patch::from_json(&json).unwrap().to_json(), json);
+}
+
+#[test]
+fn patch_files_must_refer_to_their_profiles() {
+    let result = Patch::from_json(
+        r#"{
+            "profiles": [],
+            "fixtures": { "1": { "address": { "universe": 1, "address": 1 }, "profile": 0 } }
+        }"#,
+    );
+
+    assert!(matches!(result, Err(PatchError::UnknownProfile(1, 0))));
+}
+
+#[test]
+fn validate_reports_deliberate_overlaps() {
+    let quad = profile(4);
+    let mut patch = Patch::new();
+
+    patch.patch(1, Address::new(1, 1), quad.clone()).unwrap();
+    patch.patch(2, Address::new(1, 509), quad.clone()).unwrap();
+    assert!(patch.validate().is_empty());
+
+    patch
+        .force_patch(3, Address::new(1, 3), quad.clone())
+        .unwrap();
+    assert_eq!(
+        patch.validate(),
+        vec![PatchError::AddressConflict(PatchConflict {
+            fixture: 1,
+            other: 3,
+            universe: 1,
+            start: 3,
+            end: 4,
+        })]
+    );
+}
+
+#[test]
+fn patch_files_with_overflowing_footprints_are_rejected() {
+    let result = Patch::from_json(
+        r#"{
+            "profiles": [{ "parameters": { "intensity": { "min": 0, "max": 100, "offset": 1, "default": 0 } } }],
+            "fixtures": { "1": { "address": { "universe": 1, "address": 512 }, "profile": 0 } }
+        }"#,
+    );
+
+    assert!(matches!(
+        result,
+        Err(PatchError::FootprintOverflow(1, _, 2))
+    ));
+}
+
+#[test]
+fn patch_files_with_invalid_addresses_are_rejected() {
+    let result = Patch::from_json(
+        r#"{
+            "profiles": [{ "parameters": { "intensity": { "min": 0, "max": 100, "offset": 0, "default": 0 } } }],
+            "fixtures": { "1": { "address": { "universe": 0, "address": 1 }, "profile": 0 } }
+        }"#,
+    );
+
+    assert!(matches!(result, Err(PatchError::InvalidAddress(1, _))));
+}
+
+#[test]
+fn malformed_patch_files_are_rejected() {
+    assert!(matches!(
+        Patch::from_json(r#"{ "profiles": "#),
+        Err(PatchError::Parse(_))
+    ));
+}
+
+#[test]
+fn patch_files_with_invalid_profiles_are_rejected() {
+    let result = Patch::from_json(
+        r#"{
+            "profiles": [
+                { "parameters": { "intensity": { "min": 0, "max": 100, "offset": 0, "default": 0 } } },
+                { "parameters": { "intensity": { "min": 50, "max": 50, "offset": 0, "default": 50 } } }
+            ],
+            "fixtures": { "1": { "address": { "universe": 1, "address": 1 }, "profile": 0 } }
+        }"#,
+    );
+
+    assert!(matches!(result, Err(PatchError::InvalidProfile(1, _))));
+
+    let result = Patch::from_json(
+        r#"{
+            "profiles": [
+                { "parameters": {
+                    "intensity": { "min": 0, "max": 100, "offset": 0, "default": 0 },
+                    "pan": { "min": 0, "max": 100, "offset": 0, "default": 0 }
+                } }
+            ],
+            "fixtures": {}
+        }"#,
+    );
+
+    assert!(matches!(result, Err(PatchError::InvalidProfile(0, _))));
+}
@@ -0,0 +1,255 @@
+use lumen::{
+    address::Address,
+    color::Colorspace,
+    parameter::Param,
+    patch::library::{ProfileError, ProfileLibrary},
+    Patch,
+};
+
+const PROFILES_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/profiles");
+
+#[test]
+fn loads_a_directory_of_profiles() {
+    let library = ProfileLibrary::from_directory(PROFILES_DIRECTORY).unwrap();
+
+    assert_eq!(library.len(), 3);
+    assert!(library.get("Generic Dimmer").is_some());
+    assert!(library.get("Generic RGBA").is_some());
+    assert!(library.get("Generic Moving Head").is_some());
+}
+
+#[test]
+fn loaded_parameters_have_offsets_ranges_and_defaults() {
+    let library = ProfileLibrary::from_directory(PROFILES_DIRECTORY).unwrap();
+    let moving_head = library.get("Generic Moving Head").unwrap();
+
+    let pan = moving_head.get_parameter(&Param::Pan).unwrap();
+    assert_eq!(pan.offset(), 1);
+    assert_eq!(pan.min(), -270.0);
+    assert_eq!(pan.max(), 270.0);
+    assert_eq!(pan.default(), 0.0);
+
+    let tilt = moving_head.get_parameter(&Param::Tilt).unwrap();
+    assert_eq!(tilt.offset(), 2);
+    assert_eq!(tilt.default(), 45.0);
+
+    let intensity = moving_head.get_parameter(&Param::Intensity).unwrap();
+    assert_eq!(intensity.min(), 0.0);
+    assert_eq!(intensity.max(), 100.0);
+}
+
+#[test]
+fn loaded_profiles_have_a_colorspace() {
+    let library = ProfileLibrary::from_directory(PROFILES_DIRECTORY).unwrap();
+
+    assert_eq!(
+        *library.get("Generic RGBA").unwrap().colorspace(),
+        Some(Colorspace::RGBA)
+    );
+    assert_eq!(*library.get("Generic Dimmer").unwrap().colorspace(), None);
+}
+
+#[test]
+fn loaded_parameters_can_be_16_and_24_bit() {
+    let mut library = ProfileLibrary::new();
+    library
+        .load_str(
+            r#"{
+                "name": "Fine",
+                "parameters": {
+                    "pan": { "offset": 0, "fine": 1 },
+                    "tilt": { "offset": 2, "fine": 3, "ultra": 4 }
+                }
+            }"#,
+        )
+        .unwrap();
+
+    let profile = library.get("Fine").unwrap();
+    let pan = profile.get_parameter(&Param::Pan).unwrap();
+    let tilt = profile.get_parameter(&Param::Tilt).unwrap();
+
+    assert_eq!(pan.fine(), Some(1));
+    assert_eq!(pan.resolution(), 2);
+    assert_eq!(tilt.ultra(), Some(4));
+    assert_eq!(tilt.resolution(), 3);
+    assert_eq!(profile.footprint(), 5);
+}
+
+#[test]
+fn unknown_parameters_are_rejected() {
+    let result = ProfileLibrary::new()
+        .load_str(r#"{ "name": "Bad", "parameters": { "sparkle": { "offset": 0 } } }"#);
+
+    assert!(matches!(result, Err(ProfileError::UnknownParameter { .. })));
+}
+
+#[test]
+fn invalid_ranges_are_rejected() {
+    let result = ProfileLibrary::new().load_str(
+        r#"{ "name": "Bad", "parameters": { "pan": { "offset": 0, "min": 10, "max": -10 } } }"#,
+    );
+
+    assert!(matches!(result, Err(ProfileError::InvalidRange { .. })));
+}
+
+#[test]
+fn defaults_outside_the_range_are_rejected() {
+    let result = ProfileLibrary::new().load_str(
+        r#"{ "name": "Bad", "parameters": { "intensity": { "offset": 0, "default": 110 } } }"#,
+    );
+
+    assert!(matches!(
+        result,
+        Err(ProfileError::DefaultOutOfRange { .. })
+    ));
+}
+
+#[test]
+fn loaded_parameters_have_named_ranges() {
+    let mut library = ProfileLibrary::new();
+    library
+        .load_str(
+            r#"{ "name": "Spot", "parameters": { "gobo": { "offset": 0, "max": 255, "ranges": [
+                { "name": "open", "from": 0, "to": 9 },
+                { "name": "stars", "from": 10, "to": 19 }
+            ] } } }"#,
+        )
+        .unwrap();
+
+    let gobo = library.get("Spot").unwrap();
+    let gobo = gobo.get_parameter(&Param::Gobo).unwrap();
+
+    assert_eq!(gobo.ranges().len(), 2);
+    assert_eq!(gobo.range("stars").unwrap().from(), 10.0);
+    assert_eq!(gobo.range("stars").unwrap().to(), 19.0);
+}
+
+#[test]
+fn named_ranges_outside_the_range_are_rejected() {
+    let result = ProfileLibrary::new().load_str(
+        r#"{ "name": "Bad", "parameters": { "gobo": { "offset": 0, "ranges": [
+            { "name": "stars", "from": 90, "to": 110 }
+        ] } } }"#,
+    );
+
+    assert!(matches!(
+        result,
+        Err(ProfileError::NamedRangeOutOfBounds { ref name, .. }) if name == "stars"
+    ));
+}
+
+#[test]
+fn shared_offsets_are_rejected() {
+    let result = ProfileLibrary::new().load_str(
+        r#"{ "name": "Bad", "parameters": { "pan": { "offset": 0 }, "tilt": { "offset": 0 } } }"#,
+    );
+
+    assert!(matches!(
+        result,
+        Err(ProfileError::DuplicateOffset { offset: 0, .. })
+    ));
+}
+
+#[test]
+fn shared_fine_offsets_are_rejected() {
+    let result = ProfileLibrary::new().load_str(
+        r#"{ "name": "Bad", "parameters": { "pan": { "offset": 0, "fine": 1 }, "tilt": { "offset": 1 } } }"#,
+    );
+
+    assert!(matches!(
+        result,
+        Err(ProfileError::DuplicateOffset { offset: 1, .. })
+    ));
+}
+
+#[test]
+fn ultra_offsets_require_a_fine_offset() {
+    let result = ProfileLibrary::new()
+        .load_str(r#"{ "name": "Bad", "parameters": { "pan": { "offset": 0, "ultra": 1 } } }"#);
+
+    assert!(matches!(result, Err(ProfileError::UltraWithoutFine { .. })));
+}
+
+#[test]
+fn colorspaces_require_their_parameters() {
+    let result = ProfileLibrary::new().load_str(
+        r#"{ "name": "Bad", "colorspace": "RGB", "parameters": { "red": { "offset": 0 } } }"#,
+    );
+
+    assert!(matches!(
+        result,
+        Err(ProfileError::MissingColorParameter { .. })
+    ));
+}
+
+#[test]
+fn duplicate_profile_names_are_rejected() {
+    let mut library = ProfileLibrary::new();
+    let source = r#"{ "name": "Dimmer", "parameters": { "intensity": { "offset": 0 } } }"#;
+
+    assert!(library.load_str(source).is_ok());
+    assert!(matches!(
+        library.load_str(source),
+        Err(ProfileError::DuplicateProfile(_))
+    ));
+}
+
+#[test]
+fn directories_are_only_loaded_if_every_file_can_be() {
+    let directory = std::env::temp_dir().join(format!("lumen-profiles-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::copy(
+        format!("{}/dimmer.json", PROFILES_DIRECTORY),
+        directory.join("dimmer.json"),
+    )
+    .unwrap();
+    std::fs::write(directory.join("zz_broken.json"), r#"{ "name": "Bad", "#).unwrap();
+
+    let mut library = ProfileLibrary::new();
+    library
+        .load_str(r#"{ "name": "Existing", "parameters": { "intensity": { "offset": 0 } } }"#)
+        .unwrap();
+
+    let result = library.load_directory(&directory);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(matches!(result, Err(ProfileError::Parse(_))));
+    assert_eq!(library.len(), 1);
+    assert!(library.get("Generic Dimmer").is_none());
+}
+
+#[test]
+fn directories_with_profiles_already_in_the_library_are_rejected() {
+    let mut library = ProfileLibrary::new();
+    library
+        .load_str(r#"{ "name": "Generic RGBA", "parameters": { "intensity": { "offset": 0 } } }"#)
+        .unwrap();
+
+    assert!(matches!(
+        library.load_directory(PROFILES_DIRECTORY),
+        Err(ProfileError::DuplicateProfile(name)) if name == "Generic RGBA"
+    ));
+    assert_eq!(library.len(), 1);
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    let result = ProfileLibrary::new().load_str(r#"{ "name": "Bad", "parameters": "#);
+
+    assert!(matches!(result, Err(ProfileError::Parse(_))));
+}
+
+#[test]
+fn shared_profiles_can_be_patched() {
+    let library = ProfileLibrary::from_directory(PROFILES_DIRECTORY).unwrap();
+    let dimmer = library.get_shared("Generic Dimmer").unwrap();
+    let mut patch = Patch::new();
+
+    patch.patch(1, Address::new(1, 1), dimmer.clone()).unwrap();
+    patch.patch(2, Address::new(1, 2), dimmer).unwrap();
+
+    assert_eq!(
+        patch.get_profile(&1).unwrap(),
+        library.get("Generic Dimmer").unwrap()
+    );
+}
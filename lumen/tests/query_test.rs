@@ -45,3 +45,25 @@ fn build_example_fixture_set(amount: usize) -> FixtureSet {
 
     f
 }
+
+#[test]
+fn ordered_results_follow_the_selection() {
+    let fixture_set = build_example_fixture_set(EXAMPLE_SIZE);
+    let query = QueryBuilder::new().id(5).range(3, 1).id(2).id(9).build();
+
+    assert_eq!(
+        query.evaluate_ordered(&fixture_set.ids()),
+        vec![5, 3, 2, 1, 9]
+    );
+}
+
+#[test]
+fn ordered_results_of_commands_are_in_id_order() {
+    let fixture_set = build_example_fixture_set(EXAMPLE_SIZE);
+    let query = QueryBuilder::new().odd().build();
+
+    assert_eq!(
+        query.evaluate_ordered(&fixture_set.ids()),
+        vec![1, 3, 5, 7, 9]
+    );
+}
@@ -0,0 +1,192 @@
+use std::{net::UdpSocket, sync::Arc, thread, time::Duration};
+
+use lumen::{
+    address::Address,
+    fixture::ResolvedFixture,
+    fixture_set::ResolvedFixtureMap,
+    output::{
+        artnet::{ArtNet, ARTDMX_PACKET_LENGTH},
+        NetworkOutput,
+    },
+    parameter::{Param, Parameter},
+    patch::{modifiers::FixtureModifiers, FixtureProfile},
+    render::Renderer,
+    universe::Multiverse,
+    value::Values,
+    Patch,
+};
+
+fn dimmer() -> Arc<FixtureProfile> {
+    let mut dimmer = FixtureProfile::new();
+    dimmer.set_parameter(Param::Intensity, Parameter::simple(0));
+    Arc::new(dimmer)
+}
+
+// Dimmer 1 in universe 1 and dimmer 2 in universe 2, both at 1.001.
+fn patch() -> Patch {
+    let mut patch = Patch::new();
+    patch.patch(1, Address::new(1, 1), dimmer()).unwrap();
+    patch.patch(2, Address::new(2, 1), dimmer()).unwrap();
+    patch
+}
+
+fn resolved(levels: &[(usize, f64)]) -> ResolvedFixtureMap {
+    let mut resolved_map = ResolvedFixtureMap::new();
+
+    for (id, level) in levels {
+        let mut fixture = ResolvedFixture::new(*id);
+        fixture.set(Param::Intensity, Values::make_literal(*level));
+        resolved_map.insert(*id, fixture);
+    }
+
+    resolved_map
+}
+
+fn dirty(multiverse: &Multiverse) -> Vec<usize> {
+    let mut dirty: Vec<usize> = multiverse
+        .dirty_universes()
+        .map(|universe| universe.universe_number())
+        .collect();
+    dirty.sort();
+    dirty
+}
+
+fn first_byte(multiverse: &Multiverse, universe_number: usize) -> u8 {
+    multiverse.universe(universe_number).unwrap().bytes()[0]
+}
+
+#[test]
+fn the_first_render_is_dirty() {
+    let mut renderer = Renderer::new();
+
+    let multiverse = renderer.render(&resolved(&[(1, 100.0), (2, 0.0)]), &patch());
+
+    assert_eq!(dirty(multiverse), vec![1, 2]);
+    assert_eq!(first_byte(multiverse, 1), 255);
+}
+
+#[test]
+fn only_changed_universes_are_dirty() {
+    let patch = patch();
+    let mut renderer = Renderer::new();
+
+    renderer.render(&resolved(&[(1, 100.0), (2, 0.0)]), &patch);
+    let multiverse = renderer.render(&resolved(&[(1, 100.0), (2, 50.0)]), &patch);
+    assert_eq!(dirty(multiverse), vec![2]);
+
+    let multiverse = renderer.render(&resolved(&[(1, 100.0), (2, 50.0)]), &patch);
+    assert!(dirty(multiverse).is_empty());
+}
+
+#[test]
+fn removed_fixtures_are_cleared() {
+    let patch = patch();
+    let mut renderer = Renderer::new();
+
+    renderer.render(&resolved(&[(1, 100.0), (2, 100.0)]), &patch);
+    let multiverse = renderer.render(&resolved(&[(2, 100.0)]), &patch);
+
+    assert_eq!(dirty(multiverse), vec![1]);
+    assert_eq!(first_byte(multiverse, 1), 0);
+}
+
+#[test]
+fn repatched_fixtures_move() {
+    let mut patch = patch();
+    let mut renderer = Renderer::new();
+    let levels = resolved(&[(1, 100.0)]);
+
+    renderer.render(&levels, &patch);
+
+    patch.unpatch(&1).unwrap();
+    patch.patch(1, Address::new(1, 2), dimmer()).unwrap();
+    let multiverse = renderer.render(&levels, &patch);
+
+    assert_eq!(
+        &multiverse.universe(1).unwrap().bytes()[0..2],
+        &[0, 255],
+        "the old address is cleared"
+    );
+}
+
+#[test]
+fn repatching_with_a_new_profile_renders_again() {
+    let mut patch = patch();
+    let mut renderer = Renderer::new();
+    let levels = resolved(&[(1, 100.0)]);
+
+    renderer.render(&levels, &patch);
+
+    // The same footprint, but with twice the range
+    let mut dimmer = FixtureProfile::new();
+    dimmer.set_parameter(Param::Intensity, Parameter::new(0, 0.0, 200.0));
+    patch.unpatch(&1).unwrap();
+    patch.patch(1, Address::new(1, 1), dimmer).unwrap();
+    let multiverse = renderer.render(&levels, &patch);
+
+    assert_eq!(dirty(multiverse), vec![1]);
+    assert_eq!(first_byte(multiverse, 1), 128);
+}
+
+#[test]
+fn clearing_a_fixture_renders_any_it_overlapped() {
+    let mut patch = patch();
+    patch.force_patch(3, Address::new(1, 1), dimmer()).unwrap();
+    let mut renderer = Renderer::new();
+
+    renderer.render(&resolved(&[(1, 100.0), (3, 100.0)]), &patch);
+    let multiverse = renderer.render(&resolved(&[(1, 100.0)]), &patch);
+
+    assert_eq!(first_byte(multiverse, 1), 255);
+}
+
+#[test]
+fn changing_modifiers_renders_again() {
+    let mut patch = patch();
+    let mut renderer = Renderer::new();
+    let levels = resolved(&[(1, 100.0)]);
+
+    renderer.render(&levels, &patch);
+
+    let mut modifiers = FixtureModifiers::new();
+    modifiers.set_invert(Param::Intensity, true);
+    patch.set_modifiers(&1, modifiers).unwrap();
+    let multiverse = renderer.render(&levels, &patch);
+
+    assert_eq!(dirty(multiverse), vec![1]);
+    assert_eq!(first_byte(multiverse, 1), 0);
+}
+
+#[test]
+fn outputs_skip_unchanged_universes_until_the_keepalive() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+
+    let mut output = NetworkOutput::with_protocol(Box::new(ArtNet::new()));
+    output.bind("127.0.0.1:0").unwrap();
+    output.connect(receiver.local_addr().unwrap()).unwrap();
+    output.set_keepalive(Duration::from_millis(50));
+
+    let patch = patch();
+    let mut renderer = Renderer::new();
+
+    let multiverse = renderer.render(&resolved(&[(1, 100.0), (2, 0.0)]), &patch);
+    assert_eq!(output.send_changes(multiverse).unwrap(), 2);
+
+    let multiverse = renderer.render(&resolved(&[(1, 50.0), (2, 0.0)]), &patch);
+    assert_eq!(output.send_changes(multiverse).unwrap(), 1);
+
+    let multiverse = renderer.render(&resolved(&[(1, 50.0), (2, 0.0)]), &patch);
+    assert_eq!(output.send_changes(multiverse).unwrap(), 0);
+
+    thread::sleep(Duration::from_millis(60));
+    assert_eq!(output.send_changes(renderer.multiverse()).unwrap(), 2);
+
+    let mut buf = [0; ARTDMX_PACKET_LENGTH];
+    for _ in 0..5 {
+        receiver.recv(&mut buf).unwrap();
+    }
+    assert!(receiver.recv(&mut buf).is_err());
+}
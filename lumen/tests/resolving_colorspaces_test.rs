@@ -1,11 +1,16 @@
+use std::time::Duration;
+
 use lumen::{
     action::Apply,
     color::Colorspace,
-    fixture::Fixture,
+    fixture::{Fixture, ResolvedFixture},
     parameter::{Param, Parameter},
     patch::FixtureProfile,
     timecode::time::Time,
-    value::{generator::Static, Values},
+    value::{
+        generator::{Fade, Generator, Static},
+        Value, Values,
+    },
 };
 
 #[test]
@@ -29,6 +34,315 @@ fn resolve_rgb() {
     );
 }
 
+#[test]
+fn resolve_rgb_on_rgba() {
+    let profile = profile(Colorspace::RGBA);
+    let mut fixture = programmed(&[
+        (Param::Red, 100.0),
+        (Param::Green, 75.0),
+        (Param::Blue, 0.0),
+    ]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Red, 0.0),
+            (Param::Green, 0.0),
+            (Param::Blue, 0.0),
+            (Param::Amber, 100.0),
+        ],
+    );
+}
+
+#[test]
+fn resolve_rgb_on_rgbw() {
+    let profile = profile(Colorspace::RGBW);
+    let mut fixture = programmed(&[
+        (Param::Red, 100.0),
+        (Param::Green, 50.0),
+        (Param::Blue, 50.0),
+    ]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Red, 50.0),
+            (Param::Green, 0.0),
+            (Param::Blue, 0.0),
+            (Param::White, 50.0),
+        ],
+    );
+}
+
+#[test]
+fn resolve_rgb_on_rgbaw() {
+    let profile = profile(Colorspace::RGBAW);
+    let mut fixture = programmed(&[
+        (Param::Red, 100.0),
+        (Param::Green, 100.0),
+        (Param::Blue, 40.0),
+    ]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Red, 0.0),
+            (Param::Green, 15.0),
+            (Param::Blue, 0.0),
+            (Param::Amber, 60.0),
+            (Param::White, 40.0),
+        ],
+    );
+}
+
+#[test]
+fn resolve_rgb_on_cmy() {
+    let profile = profile(Colorspace::CMY);
+    let mut fixture = programmed(&[
+        (Param::Red, 100.0),
+        (Param::Green, 0.0),
+        (Param::Blue, 50.0),
+    ]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Cyan, 0.0),
+            (Param::Magenta, 100.0),
+            (Param::Yellow, 50.0),
+        ],
+    );
+    assert!(resolved.get_value(&Param::Red).is_none());
+}
+
+#[test]
+fn resolve_cmy_on_rgb() {
+    let profile = profile(Colorspace::RGB);
+    let mut fixture = programmed(&[(Param::Cyan, 100.0), (Param::Yellow, 20.0)]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Red, 0.0),
+            (Param::Green, 100.0),
+            (Param::Blue, 80.0),
+        ],
+    );
+}
+
+#[test]
+fn resolve_hsi_on_rgb() {
+    let profile = profile(Colorspace::RGB);
+    let mut fixture = programmed(&[
+        (Param::Hue, 120.0),
+        (Param::Saturation, 50.0),
+        (Param::Brightness, 80.0),
+    ]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Red, 40.0),
+            (Param::Green, 80.0),
+            (Param::Blue, 40.0),
+        ],
+    );
+}
+
+#[test]
+fn resolve_rgb_on_hsi() {
+    let mut profile = FixtureProfile::new();
+    profile.set_colorspace(Colorspace::HSI);
+    profile.set_parameter(Param::Hue, Parameter::new(0, 0.0, 360.0));
+    profile.set_parameter(Param::Saturation, Parameter::simple(1));
+    profile.set_parameter(Param::Brightness, Parameter::simple(2));
+
+    let mut fixture = programmed(&[(Param::Red, 0.0), (Param::Green, 0.0), (Param::Blue, 50.0)]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Hue, 240.0),
+            (Param::Saturation, 100.0),
+            (Param::Brightness, 50.0),
+        ],
+    );
+}
+
+#[test]
+fn resolve_kelvin_on_rgb() {
+    let profile = profile(Colorspace::RGB);
+    let mut fixture = programmed(&[(Param::ColorTemperature, 6600.0)]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    // Daylight is close to a full white
+    for param in [Param::Red, Param::Green, Param::Blue] {
+        match resolved.get_value(&param) {
+            Some(Values::Literal(literal)) => assert!(literal.value() > 95.0),
+            other => panic!("expected a literal for {}, got {:?}", param, other),
+        }
+    }
+}
+
+#[test]
+fn resolve_kelvin_on_rgbw() {
+    let profile = profile(Colorspace::RGBW);
+    let mut fixture = programmed(&[(Param::ColorTemperature, 3200.0)]);
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+    let value = |param| match resolved.get_value(&param) {
+        Some(Values::Literal(literal)) => literal.value(),
+        _ => panic!("{} was not resolved", param),
+    };
+
+    // A warm white is mostly white, with red added and no blue
+    assert!(value(Param::White) > 40.0);
+    assert!(value(Param::Red) > value(Param::Green));
+    assert_eq!(value(Param::Blue), 0.0);
+}
+
+#[test]
+fn conversion_uses_the_range_of_each_parameter() {
+    let mut profile = FixtureProfile::new();
+    profile.set_colorspace(Colorspace::CMY);
+    profile.set_parameter(Param::Cyan, Parameter::new(0, 0.0, 255.0));
+    profile.set_parameter(Param::Magenta, Parameter::new(1, 0.0, 255.0));
+    profile.set_parameter(Param::Yellow, Parameter::new(2, 0.0, 255.0));
+
+    let mut fixture = Fixture::new(1);
+    apply(&mut fixture, Param::Red, Values::make_percentage(60.0));
+    apply(&mut fixture, Param::Green, Values::make_literal(100.0));
+    apply(&mut fixture, Param::Blue, Values::make_literal(0.0));
+
+    let resolved = fixture.resolve(&Time::at(0, 0, 0, 0), &profile);
+
+    assert_resolved(
+        &resolved,
+        &[
+            (Param::Cyan, 102.0),
+            (Param::Magenta, 0.0),
+            (Param::Yellow, 255.0),
+        ],
+    );
+}
+
+#[test]
+fn detect_nothing_without_color_params() {
+    let fixture = programmed(&[(Param::Intensity, 100.0), (Param::Uv, 100.0)]);
+
+    assert_eq!(fixture.colorspace(), None);
+}
+
+#[test]
+fn detect_additive_colorspaces() {
+    let rgb = programmed(&[(Param::Red, 100.0)]);
+    let rgba = programmed(&[(Param::Red, 100.0), (Param::Amber, 100.0)]);
+    let rgbw = programmed(&[(Param::Blue, 100.0), (Param::White, 100.0)]);
+    let rgbaw = programmed(&[(Param::Amber, 100.0), (Param::White, 100.0)]);
+
+    assert_eq!(rgb.colorspace(), Some(Colorspace::RGB));
+    assert_eq!(rgba.colorspace(), Some(Colorspace::RGBA));
+    assert_eq!(rgbw.colorspace(), Some(Colorspace::RGBW));
+    assert_eq!(rgbaw.colorspace(), Some(Colorspace::RGBAW));
+}
+
+#[test]
+fn detect_cmy_and_hsi() {
+    let cmy = programmed(&[(Param::Magenta, 100.0)]);
+    let hsi = programmed(&[(Param::Hue, 100.0), (Param::Saturation, 100.0)]);
+
+    assert_eq!(cmy.colorspace(), Some(Colorspace::CMY));
+    assert_eq!(hsi.colorspace(), Some(Colorspace::HSI));
+}
+
+#[test]
+fn detect_kelvin() {
+    let kelvin = programmed(&[(Param::ColorTemperature, 3200.0)]);
+
+    assert_eq!(kelvin.colorspace(), Some(Colorspace::Kelvin));
+}
+
+#[test]
+fn detect_the_last_applied_colorspace_at_the_same_time() {
+    let rgb_then_cmy = programmed(&[(Param::Red, 100.0), (Param::Cyan, 100.0)]);
+    let cmy_then_rgb = programmed(&[(Param::Cyan, 100.0), (Param::Red, 100.0)]);
+
+    assert_eq!(rgb_then_cmy.colorspace(), Some(Colorspace::CMY));
+    assert_eq!(cmy_then_rgb.colorspace(), Some(Colorspace::RGB));
+}
+
+#[test]
+fn detect_the_most_recently_programmed_colorspace() {
+    let mut fixture = programmed(&[(Param::Red, 100.0)]);
+
+    let mut fade = Fade::new(
+        Box::new(Static::new(Values::make_literal(0.0))),
+        Box::new(Static::new(Values::make_literal(100.0))),
+        Duration::from_secs(1),
+    );
+    fade.set_start_time(Time::at(0, 0, 5, 0));
+    fixture.apply(&Apply::new(Param::Cyan, Box::new(fade)));
+
+    assert_eq!(fixture.colorspace(), Some(Colorspace::CMY));
+}
+
+fn profile(colorspace: Colorspace) -> FixtureProfile {
+    let mut profile = FixtureProfile::new();
+    profile.set_colorspace(colorspace);
+
+    for (offset, param) in colorspace.params().iter().enumerate() {
+        profile.set_parameter(*param, Parameter::simple(offset));
+    }
+
+    profile
+}
+
+fn programmed(values: &[(Param, f64)]) -> Fixture {
+    let mut fixture = Fixture::new(1);
+
+    for (param, value) in values {
+        apply(&mut fixture, *param, Values::make_literal(*value));
+    }
+
+    fixture
+}
+
+fn apply(fixture: &mut Fixture, param: Param, value: Values) {
+    fixture.apply(&Apply::new(param, Box::new(Static::new(value))));
+}
+
+fn assert_resolved(resolved: &ResolvedFixture, expected: &[(Param, f64)]) {
+    for (param, value) in expected {
+        let resolved_value = match resolved.get_value(param) {
+            Some(Values::Literal(literal)) => literal.value(),
+            other => panic!("expected a literal for {}, got {:?}", param, other),
+        };
+
+        assert!(
+            (resolved_value - value).abs() < 0.001,
+            "{} resolved to {}, expected {}",
+            param,
+            resolved_value,
+            value
+        );
+    }
+}
+
 fn rgb_profile() -> FixtureProfile {
     let mut profile = FixtureProfile::new();
     profile.set_colorspace(Colorspace::RGB);
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use lumen::address::Address;
 use lumen::fixture::FixtureID;
 use lumen::parameter::Parameter;
@@ -522,10 +524,13 @@ mod time_moving_both_directions {
 fn build_environment(n_fixtures: usize, profile: &FixtureProfile) -> (Environment, Patch) {
     let mut environment = Environment::new();
     let mut patch = Patch::new();
+    let profile = Arc::new(profile.clone());
 
     for n in 1..=n_fixtures {
         environment.fixtures.create_with_id(n);
-        patch.patch(n, Address::new(0, n as u16), profile)
+        patch
+            .patch(n, Address::new(1, n as u16), Arc::clone(&profile))
+            .unwrap();
     }
 
     (environment, patch)
@@ -0,0 +1,207 @@
+use std::{net::UdpSocket, time::Duration};
+
+use lumen::{
+    address::Address,
+    dmx::{Dmx, DmxString},
+    input::sacn::SacnReceiver,
+    output::{
+        sacn::{MAX_PACKET_LENGTH, OPT_PREVIEW_DATA, OPT_STREAM_TERMINATED, SYNC_PACKET_LENGTH},
+        Output, SACN,
+    },
+    universe::Multiverse,
+};
+
+fn sockets() -> (UdpSocket, UdpSocket) {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+    (sender, receiver)
+}
+
+fn sacn() -> SACN {
+    SACN::new("test".to_string(), uuid::Uuid::nil())
+}
+
+fn multiverse(universes: &[u16]) -> Multiverse {
+    let mut dmx_string = DmxString::new(1);
+    dmx_string.set(0, Dmx::new(255));
+
+    let mut multiverse = Multiverse::new();
+    for universe in universes {
+        multiverse
+            .map_string(&Address::new(*universe, 1), &dmx_string)
+            .unwrap();
+    }
+    multiverse
+}
+
+fn receive(receiver: &UdpSocket) -> Vec<u8> {
+    let mut buf = [0; 1024];
+    let length = receiver.recv(&mut buf).unwrap();
+    buf[..length].to_vec()
+}
+
+fn universe_of(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[113], packet[114]])
+}
+
+#[test]
+fn universes_are_sent_with_their_priority() {
+    let (sender, receiver) = sockets();
+    let mut sacn = sacn();
+    sacn.set_priority(Some(50));
+    sacn.set_universe_priority(2, 150);
+
+    sacn.send_multiverse(&multiverse(&[1, 2]), &sender).unwrap();
+
+    for _ in 0..2 {
+        let packet = receive(&receiver);
+
+        match universe_of(&packet) {
+            1 => assert_eq!(packet[108], 50),
+            2 => assert_eq!(packet[108], 150),
+            universe => panic!("unexpected universe {}", universe),
+        }
+    }
+}
+
+#[test]
+fn preview_data_is_flagged() {
+    let (sender, receiver) = sockets();
+    let mut sacn = sacn();
+    sacn.set_preview(true);
+
+    sacn.send_multiverse(&multiverse(&[1]), &sender).unwrap();
+
+    assert_eq!(receive(&receiver)[112], OPT_PREVIEW_DATA);
+}
+
+#[test]
+fn synchronized_multiverses_end_with_a_sync_packet() {
+    let (sender, receiver) = sockets();
+    let mut sacn = sacn();
+    sacn.set_sync_address(Some(7));
+
+    sacn.send_multiverse(&multiverse(&[1, 2]), &sender).unwrap();
+
+    for _ in 0..2 {
+        let packet = receive(&receiver);
+        assert_eq!(packet.len(), MAX_PACKET_LENGTH);
+        assert_eq!(&packet[109..111], &[0, 7]);
+    }
+
+    let sync = receive(&receiver);
+    assert_eq!(sync.len(), SYNC_PACKET_LENGTH);
+    assert_eq!(&sync[45..47], &[0, 7]);
+}
+
+#[test]
+fn unsynchronized_multiverses_send_no_sync_packet() {
+    let (sender, receiver) = sockets();
+    let mut sacn = sacn();
+
+    sacn.send_multiverse(&multiverse(&[1]), &sender).unwrap();
+    sacn.send_multiverse(&multiverse(&[1]), &sender).unwrap();
+
+    assert_eq!(receive(&receiver).len(), MAX_PACKET_LENGTH);
+    assert_eq!(receive(&receiver).len(), MAX_PACKET_LENGTH);
+}
+
+#[test]
+fn terminating_a_universe_sends_three_packets() {
+    let (sender, receiver) = sockets();
+    let mut sacn = sacn();
+    let multiverse = multiverse(&[3]);
+
+    sacn.terminate_universe(multiverse.universes().next().unwrap(), &sender)
+        .unwrap();
+
+    let sequence: Vec<u8> = (0..3)
+        .map(|_| {
+            let packet = receive(&receiver);
+            assert_eq!(universe_of(&packet), 3);
+            assert_eq!(packet[112] & OPT_STREAM_TERMINATED, OPT_STREAM_TERMINATED);
+            packet[111]
+        })
+        .collect();
+
+    assert_eq!(sequence, vec![0, 1, 2]);
+}
+
+#[test]
+fn discovery_is_off_by_default() {
+    let (sender, receiver) = sockets();
+    let mut sacn = sacn();
+
+    sacn.send_multiverse(&multiverse(&[1]), &sender).unwrap();
+
+    assert_eq!(receive(&receiver).len(), MAX_PACKET_LENGTH);
+    receiver
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(receiver.recv(&mut [0; 1024]).is_err());
+}
+
+#[test]
+fn discovery_is_sent_with_the_first_multiverse() {
+    let (sender, receiver) = sockets();
+    let mut sacn = sacn();
+    sacn.set_discovery(true);
+
+    sacn.send_multiverse(&multiverse(&[2, 1]), &sender).unwrap();
+    sacn.send_multiverse(&multiverse(&[2, 1]), &sender).unwrap();
+
+    assert_eq!(receive(&receiver).len(), MAX_PACKET_LENGTH);
+    assert_eq!(receive(&receiver).len(), MAX_PACKET_LENGTH);
+
+    let discovery = receive(&receiver);
+    assert_eq!(discovery.len(), 124);
+    assert_eq!(&discovery[120..124], &[0, 1, 0, 2]);
+
+    // Discovery waits for the interval before being sent again
+    assert_eq!(receive(&receiver).len(), MAX_PACKET_LENGTH);
+    assert_eq!(receive(&receiver).len(), MAX_PACKET_LENGTH);
+    receiver
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(receiver.recv(&mut [0; 1024]).is_err());
+}
+
+#[test]
+fn each_universe_has_its_own_sequence() {
+    let mut receiver = SacnReceiver::bind("127.0.0.1:0").unwrap();
+    receiver
+        .socket()
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender
+        .connect(receiver.socket().local_addr().unwrap())
+        .unwrap();
+    let mut sacn = sacn();
+
+    // A quiet universe is only sent now and then, while a busy one is sent
+    // every frame in between.
+    let quiet = multiverse(&[1]);
+    let busy = multiverse(&[2]);
+
+    sacn.send_multiverse(&quiet, &sender).unwrap();
+    assert_eq!(receiver.receive().unwrap(), Some(1));
+
+    for _ in 0..240 {
+        sacn.send_multiverse(&busy, &sender).unwrap();
+        assert_eq!(receiver.receive().unwrap(), Some(2));
+    }
+
+    sacn.send_multiverse(&quiet, &sender).unwrap();
+    assert_eq!(
+        receiver.receive().unwrap(),
+        Some(1),
+        "the quiet universe was dropped as out of order"
+    );
+}
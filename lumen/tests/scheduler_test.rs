@@ -0,0 +1,187 @@
+use std::{
+    io,
+    net::UdpSocket,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use lumen::{
+    action::{Action, Apply, ApplyGroup},
+    address::Address,
+    output::{
+        artnet::{ArtNet, ARTDMX_PACKET_LENGTH},
+        BoxedOutput, NetworkOutput, NetworkState, Output,
+    },
+    parameter::{Param, Parameter},
+    patch::FixtureProfile,
+    scheduler::OutputScheduler,
+    timecode::{time::Time, FrameRate, Source},
+    track::Track,
+    universe::Universe,
+    value::{generator::Static, Values},
+    Environment, Patch, QueryBuilder,
+};
+
+fn intensity(value: f64) -> Action {
+    let mut action = Action::new();
+    let apply = Apply::new(
+        Param::Intensity,
+        Box::new(Static::new(Values::make_literal(value))),
+    );
+    let mut apply_group = ApplyGroup::new(QueryBuilder::new().all().build());
+    apply_group.add_apply(apply);
+    action.add_group(apply_group);
+
+    action
+}
+
+// A dimmer at 1.005 that goes to full at the start of the show, and an output
+// sending Art-Net to the returned socket.
+fn scheduler() -> (OutputScheduler, UdpSocket) {
+    let (scheduler, receiver, _) = scheduler_with_protocol(Box::new(ArtNet::new()));
+    (scheduler, receiver)
+}
+
+fn scheduler_with_protocol(
+    protocol: BoxedOutput,
+) -> (OutputScheduler, UdpSocket, Arc<Mutex<NetworkOutput>>) {
+    let mut dimmer = FixtureProfile::new();
+    dimmer.set_parameter(Param::Intensity, Parameter::simple(0));
+
+    let mut patch = Patch::new();
+    patch.patch(1, Address::new(1, 5), dimmer).unwrap();
+
+    let mut environment = Environment::new();
+    environment.fixtures.create_with_id(1);
+    let mut track = Track::new();
+    track.add_action(Time::at(0, 0, 0, 0), intensity(100.0));
+    environment.add_track(track);
+
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+
+    let mut output = NetworkOutput::with_protocol(protocol);
+    output.bind("127.0.0.1:0").unwrap();
+    output.connect(receiver.local_addr().unwrap()).unwrap();
+    let output = Arc::new(Mutex::new(output));
+
+    let scheduler = OutputScheduler::new(
+        Arc::new(Mutex::new(environment)),
+        Arc::new(Mutex::new(patch)),
+        Arc::new(Mutex::new(Source::new(FrameRate::Thirty))),
+        Arc::clone(&output),
+    );
+
+    (scheduler, receiver, output)
+}
+
+// Art-Net that fails to send the first universe it is given.
+struct Unplugged {
+    artnet: ArtNet,
+    failed: bool,
+}
+
+impl Output for Unplugged {
+    fn send_universe(&mut self, universe: &Universe, socket: &UdpSocket) -> io::Result<usize> {
+        if !self.failed {
+            self.failed = true;
+            return Err(io::Error::other("unplugged"));
+        }
+
+        self.artnet.send_universe(universe, socket)
+    }
+}
+
+#[test]
+fn refresh_rate_sets_the_interval() {
+    let (scheduler, _) = scheduler();
+
+    assert_eq!(
+        scheduler.interval(),
+        Duration::from_secs(1) / 44,
+        "defaults to 44Hz"
+    );
+    assert_eq!(
+        scheduler.refresh_rate(25).interval(),
+        Duration::from_millis(40)
+    );
+}
+
+#[test]
+fn a_tick_resolves_and_sends_the_environment() {
+    let (scheduler, receiver) = scheduler();
+
+    scheduler.tick();
+
+    let mut buf = [0; ARTDMX_PACKET_LENGTH];
+    receiver.recv(&mut buf).unwrap();
+    assert_eq!(buf[18 + 4], 255);
+
+    let status = scheduler.status();
+    assert_eq!(status.frames, 1);
+    assert_eq!(status.network_state, NetworkState::Connected);
+    assert_eq!(
+        status.resolved[&1].get_value(&Param::Intensity),
+        Some(&Values::make_literal(100.0))
+    );
+    assert!(status.last_error.is_none());
+}
+
+#[test]
+fn output_runs_on_its_own_thread_until_stopped() {
+    let (scheduler, receiver) = scheduler();
+    let mut handle = scheduler.refresh_rate(100).start();
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(handle.is_running());
+
+    handle.stop().unwrap();
+    assert!(!handle.is_running());
+
+    let frames = handle.status().frames;
+    assert!(frames > 1, "only sent {} frames", frames);
+
+    let mut buf = [0; ARTDMX_PACKET_LENGTH];
+    receiver.recv(&mut buf).unwrap();
+    assert_eq!(buf[18 + 4], 255);
+
+    // Nothing more is sent once stopped
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(handle.status().frames, frames);
+}
+
+#[test]
+fn errors_are_cleared_once_a_frame_sends() {
+    let (scheduler, receiver, output) = scheduler_with_protocol(Box::new(Unplugged {
+        artnet: ArtNet::new(),
+        failed: false,
+    }));
+
+    scheduler.tick();
+
+    let status = scheduler.status();
+    assert_eq!(status.network_state, NetworkState::Bound);
+    assert_eq!(status.last_error.as_deref(), Some("unplugged"));
+
+    // Nothing is sent until reconnected, so the error still stands.
+    scheduler.tick();
+    assert!(scheduler.status().last_error.is_some());
+
+    output
+        .lock()
+        .unwrap()
+        .connect(receiver.local_addr().unwrap())
+        .unwrap();
+    scheduler.tick();
+
+    let mut buf = [0; ARTDMX_PACKET_LENGTH];
+    receiver.recv(&mut buf).unwrap();
+    assert_eq!(buf[18 + 4], 255);
+
+    let status = scheduler.status();
+    assert_eq!(status.network_state, NetworkState::Connected);
+    assert!(status.last_error.is_none());
+}
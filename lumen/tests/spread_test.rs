@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use lumen::{
+    action::{Action, Apply, ApplyGroup},
+    address::Address,
+    parameter::{Param, Parameter},
+    patch::FixtureProfile,
+    spread::{Spread, SpreadOrder},
+    timecode::time::Time,
+    track::Track,
+    value::{
+        generator::{Fan, FanMode, Oscillator, Static, Waveform},
+        Value, Values,
+    },
+    Environment, Patch, Query, QueryBuilder,
+};
+
+fn dimmer() -> FixtureProfile {
+    let mut profile = FixtureProfile::new();
+    profile.set_parameter(Param::Intensity, Parameter::new(0, 0.0, 100.0));
+    profile
+}
+
+fn environment(action: Action) -> (Environment, Patch) {
+    let mut patch = Patch::new();
+    let mut environment = Environment::new();
+
+    for id in 1..=4 {
+        patch
+            .patch(id, Address::new(1, id as u16), dimmer())
+            .unwrap();
+        environment.fixtures.create_with_id(id);
+    }
+
+    let mut track = Track::new();
+    track.add_action(Time::at(0, 0, 0, 0), action);
+    environment.add_track(track);
+
+    (environment, patch)
+}
+
+fn saw(spread: Spread) -> Apply {
+    let mut apply = Apply::new(
+        Param::Intensity,
+        Box::new(Oscillator::new(
+            Waveform::Saw,
+            Values::make_literal(0.0),
+            Values::make_literal(100.0),
+            Duration::from_secs(1),
+        )),
+    );
+    apply.set_spread(spread);
+    apply
+}
+
+fn action(query: Query, applies: Vec<Apply>) -> Action {
+    let mut group = ApplyGroup::new(query);
+    for apply in applies {
+        group.add_apply(apply);
+    }
+
+    let mut action = Action::new();
+    action.add_group(group);
+    action
+}
+
+fn levels(action: Action, time: Time) -> Vec<f64> {
+    let (mut environment, patch) = environment(action);
+    environment.run_to_time(time, &patch);
+    let resolved = environment.fixtures.resolve(time, &patch);
+
+    (1..=4)
+        .map(|id| match resolved[&id].get_value(&Param::Intensity) {
+            Some(Values::Literal(literal)) => (literal.value() * 1000.0).round() / 1000.0,
+            other => panic!("expected a literal, got {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn phase_ripples_across_the_selection() {
+    let action = action(
+        QueryBuilder::new().all().build(),
+        vec![saw(Spread::phase(1.0))],
+    );
+
+    assert_eq!(
+        levels(action, Time::at(0, 0, 0, 0)),
+        vec![0.0, 25.0, 50.0, 75.0]
+    );
+}
+
+#[test]
+fn phase_can_follow_the_selection_order() {
+    let query = QueryBuilder::new().range(4, 1).build();
+
+    let by_id = action(query.clone(), vec![saw(Spread::phase(1.0))]);
+    assert_eq!(
+        levels(by_id, Time::at(0, 0, 0, 0)),
+        vec![0.0, 25.0, 50.0, 75.0]
+    );
+
+    let by_selection = action(
+        query,
+        vec![saw(Spread::phase(1.0).order(SpreadOrder::Selection))],
+    );
+    assert_eq!(
+        levels(by_selection, Time::at(0, 0, 0, 0)),
+        vec![75.0, 50.0, 25.0, 0.0]
+    );
+}
+
+#[test]
+fn wings_mirror_the_phase() {
+    let action = action(
+        QueryBuilder::new().all().build(),
+        vec![saw(Spread::phase(0.5).wings(2))],
+    );
+
+    assert_eq!(
+        levels(action, Time::at(0, 0, 0, 0)),
+        vec![0.0, 25.0, 25.0, 0.0]
+    );
+}
+
+#[test]
+fn delays_are_spread_from_first_to_last() {
+    let mut on = Apply::new(
+        Param::Intensity,
+        Box::new(Static::new(Values::make_literal(100.0))),
+    );
+    on.set_spread(Spread::delay(Duration::from_secs(3)));
+
+    let off = Apply::new(
+        Param::Intensity,
+        Box::new(Static::new(Values::make_literal(0.0))),
+    );
+
+    let action = action(QueryBuilder::new().all().build(), vec![off, on]);
+
+    assert_eq!(
+        levels(action.clone(), Time::at(0, 0, 0, 0)),
+        vec![100.0, 0.0, 0.0, 0.0]
+    );
+    assert_eq!(
+        levels(action.clone(), Time::at(0, 0, 2, 0)),
+        vec![100.0, 100.0, 100.0, 0.0]
+    );
+    assert_eq!(
+        levels(action, Time::at(0, 0, 3, 0)),
+        vec![100.0, 100.0, 100.0, 100.0]
+    );
+}
+
+fn fan(mode: FanMode) -> Apply {
+    Apply::new(
+        Param::Intensity,
+        Box::new(Fan::new(Values::make_literal(0.0), Values::make_literal(90.0)).mode(mode)),
+    )
+}
+
+#[test]
+fn fans_follow_the_same_order_as_spreads() {
+    let forwards = action(
+        QueryBuilder::new().all().build(),
+        vec![fan(FanMode::Linear)],
+    );
+    assert_eq!(
+        levels(forwards, Time::at(0, 0, 0, 0)),
+        vec![0.0, 30.0, 60.0, 90.0]
+    );
+
+    let by_id = action(
+        QueryBuilder::new().range(4, 1).build(),
+        vec![fan(FanMode::Linear)],
+    );
+    assert_eq!(
+        levels(by_id, Time::at(0, 0, 0, 0)),
+        vec![0.0, 30.0, 60.0, 90.0]
+    );
+
+    let mut apply = fan(FanMode::Linear);
+    apply.set_spread(Spread::default().order(SpreadOrder::Selection));
+    let by_selection = action(QueryBuilder::new().range(4, 1).build(), vec![apply]);
+    assert_eq!(
+        levels(by_selection, Time::at(0, 0, 0, 0)),
+        vec![90.0, 60.0, 30.0, 0.0]
+    );
+}
+
+#[test]
+fn fans_use_the_position_of_a_spread() {
+    let mut apply = fan(FanMode::Linear);
+    apply.set_spread(Spread::default().blocks(2));
+
+    let action = action(QueryBuilder::new().all().build(), vec![apply]);
+
+    assert_eq!(
+        levels(action, Time::at(0, 0, 0, 0)),
+        vec![0.0, 0.0, 90.0, 90.0]
+    );
+}
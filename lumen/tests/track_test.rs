@@ -102,7 +102,7 @@ mod unrun_actions_at_time {
 
         let actions = track.unrun_actions_at_time(Time::at(0, 0, 2, 0));
 
-        assert!(actions.get(&Time::at(0, 0, 1, 0)).is_none());
+        assert!(!actions.contains_key(&Time::at(0, 0, 1, 0)));
         assert_eq!(actions.get(&Time::at(0, 0, 2, 0)).unwrap().len(), 2);
     }
 }
@@ -1,10 +1,11 @@
 #[derive(Debug, Clone)]
 pub enum AstNode {
-    Apply(Box<AstNode>, Box<AstNode>),
+    Apply(Box<AstNode>, Box<AstNode>, Option<Box<AstNode>>),
     Parameter(String),
     Ident(String),
     Literal(f64),
     Percentage(f64),
+    Slot(String),
     Query(Vec<AstNode>),
     QRange(Box<AstNode>, Box<AstNode>),
     QCommand(Box<AstNode>),
@@ -12,10 +13,28 @@ pub enum AstNode {
     FixtureID(usize),
     GeneratorGroup(Option<Box<AstNode>>, Vec<AstNode>),
     Static(Box<AstNode>),
-    Fade(Box<AstNode>, Box<AstNode>, Box<AstNode>),
+    Fade(
+        Box<AstNode>,
+        Box<AstNode>,
+        Option<Box<AstNode>>,
+        Option<String>,
+    ),
+    Oscillator(
+        String,
+        Box<AstNode>,
+        Box<AstNode>,
+        Box<AstNode>,
+        Option<Box<AstNode>>,
+    ),
+    Fan(Box<AstNode>, Box<AstNode>, Option<String>),
     Time(f64),
+    Spread(Option<Box<AstNode>>, Vec<AstNode>),
+    SpreadOption(String, Option<String>),
     DelayBlock(Box<AstNode>, Vec<AstNode>),
     PresetBlock(Box<AstNode>, Vec<AstNode>),
     Preset(Box<AstNode>),
     CurrentValue,
+    Home,
+    Timing(Option<String>, Option<Box<AstNode>>, Option<Box<AstNode>>),
+    HomeValue,
 }
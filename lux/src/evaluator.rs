@@ -13,16 +13,21 @@
 // will create an Lumen Environment that can be handed off and executed.
 
 use crate::group_parameters::GROUP_PARAMETERS;
+use crate::timing::{FadeTime, Timing};
 use std::{collections::HashMap, fmt::Display, time::Duration};
 
 use crate::ast::AstNode;
 use lumen::{
     action::{Action, Apply, ApplyGroup},
-    parameter::Param,
+    parameter::{Category, Param},
+    spread::{Spread, SpreadOrder},
     timecode::time::Time,
     track::Track,
     value::{
-        generator::{BoxedGenerator, CurrentValue, Delay, Fade, Static},
+        generator::{
+            BoxedGenerator, CurrentValue, Curve, Delay, Fade, Fan, FanMode, Home, Oscillator,
+            Static, Waveform,
+        },
         Values,
     },
     Environment, Query, QueryBuilder, Step,
@@ -36,6 +41,7 @@ pub struct Evaluator<'a> {
     apply_groups: Vec<ApplyGroup>,
     parent_apply_group: Vec<usize>,
     delay_time: Option<Duration>,
+    timing: Timing,
     presets: HashMap<String, Vec<AstNode>>,
 }
 
@@ -47,6 +53,7 @@ impl<'b, 'a> Evaluator<'a> {
             apply_groups: Vec::new(),
             parent_apply_group: Vec::new(),
             delay_time: None,
+            timing: Timing::new(),
             presets: HashMap::new(),
         }
     }
@@ -79,8 +86,8 @@ impl<'b, 'a> Evaluator<'a> {
 
     fn evaluate_statement(&mut self, node: &AstNode) -> EvaluationResult {
         match node {
-            AstNode::Apply(identifier, value) => {
-                self.evaluate_apply(identifier, value)?;
+            AstNode::Apply(identifier, value, spread) => {
+                self.evaluate_apply(identifier, value, spread)?;
             }
             AstNode::Select(query, statements) => {
                 self.evaluate_select(query, statements)?;
@@ -94,6 +101,12 @@ impl<'b, 'a> Evaluator<'a> {
             AstNode::Preset(identifier) => {
                 self.evaluate_preset(identifier)?;
             }
+            AstNode::Home => {
+                self.evaluate_home();
+            }
+            AstNode::Timing(category, up, down) => {
+                self.evaluate_timing(category, up, down)?;
+            }
             _ => {
                 return self.evaluation_error(format!("Expected a statement but got: {:?}", node));
             }
@@ -101,12 +114,22 @@ impl<'b, 'a> Evaluator<'a> {
         Ok(())
     }
 
-    fn evaluate_apply(&mut self, identifier: &AstNode, generator: &AstNode) -> EvaluationResult {
+    fn evaluate_apply(
+        &mut self,
+        identifier: &AstNode,
+        generator: &AstNode,
+        spread: &Option<Box<AstNode>>,
+    ) -> EvaluationResult {
+        let spread = match spread {
+            Some(spread) => Some(self.evaluate_spread(spread)?),
+            None => None,
+        };
+
         match generator {
             AstNode::GeneratorGroup(prefix, generator_group) => {
-                self.evaluate_apply_generator_group(identifier, prefix, generator_group)
+                self.evaluate_apply_generator_group(identifier, prefix, generator_group, spread)
             }
-            _ => self.evaluate_apply_single_generator(identifier, generator),
+            _ => self.evaluate_apply_single_generator(identifier, generator, spread),
         }
     }
 
@@ -115,30 +138,32 @@ impl<'b, 'a> Evaluator<'a> {
         identifier: &AstNode,
         prefix: &Option<Box<AstNode>>,
         generator_group: &[AstNode],
+        spread: Option<Spread>,
     ) -> EvaluationResult {
         let group_parameter = self.evaluate_group_parameter(prefix, identifier)?;
 
         // check param is a group param and get list of param values to use
         let group_parameters = GROUP_PARAMETERS.get(group_parameter.as_str()).unwrap();
-        // generate list of generators from the group
-        let mut generators = Vec::new();
-        for unevaluated_generator in generator_group.iter() {
-            let evaluated_generator = self.evaluate_generator(unevaluated_generator)?;
-            generators.push(evaluated_generator);
-        }
 
         // check provided generator group is exact length
-        if generators.len() != group_parameters.len() {
+        if generator_group.len() != group_parameters.len() {
             return self.evaluation_error(format!(
                 "expected a group of len {} but got one of len {}",
                 group_parameters.len(),
-                generators.len()
+                generator_group.len()
             ));
         }
 
+        // generate list of generators from the group
+        let mut generators = Vec::new();
+        for (param, unevaluated_generator) in group_parameters.iter().zip(generator_group) {
+            let evaluated_generator = self.evaluate_generator(unevaluated_generator, param)?;
+            generators.push(evaluated_generator);
+        }
+
         // for each param, generator group pair, add to the parent apply group
-        for (param, generator) in group_parameters.iter().zip(generators.into_iter()) {
-            self.add_apply_with_any_delay(*param, generator)
+        for (param, generator) in group_parameters.iter().zip(generators) {
+            self.add_apply_with_any_delay(*param, generator, spread)
         }
 
         Ok(())
@@ -148,25 +173,121 @@ impl<'b, 'a> Evaluator<'a> {
         &mut self,
         identifier: &AstNode,
         generator: &AstNode,
+        spread: Option<Spread>,
     ) -> EvaluationResult {
         let identifier = self.evaluate_parameter(identifier)?;
-        let generator = self.evaluate_generator(generator)?;
+        let generator = self.evaluate_generator(generator, &identifier)?;
 
-        self.add_apply_with_any_delay(identifier, generator);
+        self.add_apply_with_any_delay(identifier, generator, spread);
 
         Ok(())
     }
 
-    fn add_apply_with_any_delay(&mut self, identifier: Param, mut generator: BoxedGenerator) {
+    fn add_apply_with_any_delay(
+        &mut self,
+        identifier: Param,
+        mut generator: BoxedGenerator,
+        spread: Option<Spread>,
+    ) {
         if let Some(delay_time) = self.delay_time {
             generator = Box::new(Delay::new(delay_time, generator));
         }
 
-        let apply = Apply::new(identifier, generator);
+        let mut apply = Apply::new(identifier, generator);
+        if let Some(spread) = spread {
+            apply.set_spread(spread);
+        }
 
         self.parent_apply_group().add_apply(apply);
     }
 
+    fn evaluate_home(&mut self) {
+        let mut generator: BoxedGenerator = Box::new(Home::new());
+        if let Some(delay_time) = self.delay_time {
+            generator = Box::new(Delay::new(delay_time, generator));
+        }
+
+        self.parent_apply_group().add_home(generator);
+    }
+
+    fn evaluate_spread(&mut self, spread: &AstNode) -> Result<Spread, EvaluationError> {
+        let (amount, options) = match spread {
+            AstNode::Spread(amount, options) => (amount, options),
+            _ => return self.evaluation_error(format!("expected a spread but got: {:?}", spread)),
+        };
+
+        let mut spread = match amount.as_deref() {
+            None => Spread::default(),
+            Some(AstNode::Percentage(phase)) => Spread::phase(phase / 100.0),
+            Some(amount @ AstNode::Time(_)) => Spread::delay(self.evaluate_time(amount)?),
+            _ => {
+                return self
+                    .evaluation_error(format!("expected a phase or delay but got: {:?}", amount))
+            }
+        };
+
+        for option in options {
+            spread = match option {
+                AstNode::SpreadOption(name, None) if name == "selection" => {
+                    spread.order(SpreadOrder::Selection)
+                }
+                AstNode::SpreadOption(name, Some(count)) if name == "blocks" => {
+                    spread.blocks(self.evaluate_count(count)?)
+                }
+                AstNode::SpreadOption(name, Some(count)) if name == "wings" => {
+                    spread.wings(self.evaluate_count(count)?)
+                }
+                _ => {
+                    return self
+                        .evaluation_error(format!("{:?} is not a valid spread option", option))
+                }
+            };
+        }
+
+        Ok(spread)
+    }
+
+    fn evaluate_count(&self, count: &str) -> Result<usize, EvaluationError> {
+        match count.parse::<usize>() {
+            Ok(count) => Ok(count),
+            Err(_) => self.evaluation_error(format!("{} is not a valid count", count)),
+        }
+    }
+
+    fn evaluate_timing(
+        &mut self,
+        category: &Option<String>,
+        up: &Option<Box<AstNode>>,
+        down: &Option<Box<AstNode>>,
+    ) -> EvaluationResult {
+        let up = match up {
+            Some(up) => Some(self.evaluate_time(up)?),
+            None => None,
+        };
+        let down = match down {
+            Some(down) => Some(self.evaluate_time(down)?),
+            None => None,
+        };
+
+        let category = match category.as_deref() {
+            Some("intensity") => Category::Intensity,
+            Some("position") => Category::Position,
+            Some("color") => Category::Color,
+            Some("beam") => Category::Beam,
+            Some(category) => {
+                return self.evaluation_error(format!("{} is not a timing category", category))
+            }
+            None => {
+                self.timing.set(up, down);
+                return Ok(());
+            }
+        };
+
+        self.timing.set_category(category, up, down);
+
+        Ok(())
+    }
+
     fn evaluate_parameter(&mut self, parameter: &AstNode) -> Result<Param, EvaluationError> {
         if let AstNode::Parameter(parameter_string) = parameter {
             match Param::from_string(parameter_string) {
@@ -217,11 +338,24 @@ impl<'b, 'a> Evaluator<'a> {
         }
     }
 
-    fn evaluate_generator(&self, generator: &AstNode) -> Result<BoxedGenerator, EvaluationError> {
+    // Generators are evaluated for the parameter they are applied to, as some
+    // depend on it, such as the time of a fade.
+    fn evaluate_generator(
+        &self,
+        generator: &AstNode,
+        param: &Param,
+    ) -> Result<BoxedGenerator, EvaluationError> {
         let generator = match generator {
             AstNode::Static(value) => self.evaluate_static(value)?,
-            AstNode::Fade(start, end, time) => self.evaluate_fade(start, end, time)?,
+            AstNode::Fade(start, end, time, curve) => {
+                self.evaluate_fade(start, end, time, curve, param)?
+            }
+            AstNode::Oscillator(waveform, low, high, time, phase) => {
+                self.evaluate_oscillator(waveform, low, high, time, phase)?
+            }
+            AstNode::Fan(from, to, mode) => self.evaluate_fan(from, to, mode)?,
             AstNode::CurrentValue => Box::new(CurrentValue::new()),
+            AstNode::HomeValue => Box::new(Home::new()),
             _ => {
                 return self.evaluation_error(format!(
                     "Expected a valid generator but got: {:?}",
@@ -242,6 +376,7 @@ impl<'b, 'a> Evaluator<'a> {
         let value = match value {
             AstNode::Literal(value) => Values::make_literal(*value),
             AstNode::Percentage(value) => Values::make_percentage(*value),
+            AstNode::Slot(name) => Values::make_slot(name),
             _ => {
                 return self.evaluation_error(format!(
                     "Expected a valid static value but got: {:?}",
@@ -257,13 +392,96 @@ impl<'b, 'a> Evaluator<'a> {
         &self,
         start: &AstNode,
         end: &AstNode,
+        time: &Option<Box<AstNode>>,
+        curve: &Option<String>,
+        param: &Param,
+    ) -> Result<BoxedGenerator, EvaluationError> {
+        let start = self.evaluate_generator(start, param)?;
+        let end = self.evaluate_generator(end, param)?;
+
+        // A fade without a time takes the timing of its parameter
+        let fade_time = match time {
+            Some(time) => FadeTime::new(self.evaluate_time(time)?),
+            None => self.timing.get(param),
+        };
+
+        let curve = match curve {
+            Some(curve) => match Curve::from_name(curve) {
+                Some(curve) => curve,
+                None => return self.evaluation_error(format!("{} is not a fade curve", curve)),
+            },
+            None => Curve::Linear,
+        };
+
+        let mut fade = Fade::new(start, end, fade_time.up).curve(curve);
+        if let Some(down) = fade_time.down.filter(|down| *down != fade_time.up) {
+            fade = fade.down_time(down);
+        }
+
+        Ok(Box::new(fade))
+    }
+
+    fn evaluate_oscillator(
+        &self,
+        waveform: &str,
+        low: &AstNode,
+        high: &AstNode,
         time: &AstNode,
+        phase: &Option<Box<AstNode>>,
     ) -> Result<BoxedGenerator, EvaluationError> {
-        let start = self.evaluate_generator(start)?;
-        let end = self.evaluate_generator(end)?;
+        let waveform = match Waveform::from_name(waveform) {
+            Some(waveform) => waveform,
+            None => return self.evaluation_error(format!("{} is not a waveform", waveform)),
+        };
+
+        // Oscillators move between values, so can't start from a current value
+        let low = self.evaluate_oscillator_value(low)?;
+        let high = self.evaluate_oscillator_value(high)?;
         let time = self.evaluate_time(time)?;
 
-        Ok(Box::new(Fade::new(start, end, time)))
+        let mut oscillator = Oscillator::new(waveform, low, high, time);
+
+        if let Some(phase) = phase {
+            match phase.as_ref() {
+                AstNode::Percentage(phase) => oscillator = oscillator.phase(phase / 100.0),
+                _ => {
+                    return self.evaluation_error(format!("expected a phase but got: {:?}", phase))
+                }
+            }
+        }
+
+        Ok(Box::new(oscillator))
+    }
+
+    fn evaluate_fan(
+        &self,
+        from: &AstNode,
+        to: &AstNode,
+        mode: &Option<String>,
+    ) -> Result<BoxedGenerator, EvaluationError> {
+        // Like an oscillator, a fan is between two values that don't change
+        let from = self.evaluate_oscillator_value(from)?;
+        let to = self.evaluate_oscillator_value(to)?;
+
+        let mode = match mode {
+            Some(mode) => match FanMode::from_name(mode) {
+                Some(mode) => mode,
+                None => return self.evaluation_error(format!("{} is not a fan mode", mode)),
+            },
+            None => FanMode::Linear,
+        };
+
+        Ok(Box::new(Fan::new(from, to).mode(mode)))
+    }
+
+    fn evaluate_oscillator_value(&self, value: &AstNode) -> Result<Values, EvaluationError> {
+        match value {
+            AstNode::Static(value) => self.evaluate_value(value),
+            _ => self.evaluation_error(format!(
+                "expected a static value for an oscillator but got: {:?}",
+                value
+            )),
+        }
     }
 
     fn evaluate_time(&self, time: &AstNode) -> Result<Duration, EvaluationError> {
@@ -277,10 +495,14 @@ impl<'b, 'a> Evaluator<'a> {
         let query = self.evaluate_query(query)?;
         self.open_apply_group(query);
 
+        // Timing set in a block only lasts until the end of it
+        let timing = self.timing.clone();
+
         for statement in statements {
             self.evaluate_statement(statement)?;
         }
 
+        self.timing = timing;
         self.close_apply_group();
 
         Ok(())
@@ -355,11 +577,13 @@ impl<'b, 'a> Evaluator<'a> {
         }
 
         self.delay_time = Some(self.evaluate_time(time)?);
+        let timing = self.timing.clone();
 
         for statement in statements {
             self.evaluate_statement(statement)?;
         }
 
+        self.timing = timing;
         self.delay_time = None;
         Ok(())
     }
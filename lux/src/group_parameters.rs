@@ -12,10 +12,23 @@ lazy_static! {
         hashmap.insert("position", vec![Param::Pan, Param::Tilt]);
         hashmap.insert("color", vec![Param::Red, Param::Green, Param::Blue]);
         hashmap.insert("color_rgb", vec![Param::Red, Param::Green, Param::Blue]);
+        hashmap.insert(
+            "color_rgba",
+            vec![Param::Red, Param::Green, Param::Blue, Param::Amber],
+        );
+        hashmap.insert(
+            "color_rgbw",
+            vec![Param::Red, Param::Green, Param::Blue, Param::White],
+        );
         hashmap.insert(
             "color_cmy",
             vec![Param::Cyan, Param::Magenta, Param::Yellow],
         );
+        hashmap.insert(
+            "color_hsi",
+            vec![Param::Hue, Param::Saturation, Param::Brightness],
+        );
+        hashmap.insert("color_kelvin", vec![Param::ColorTemperature]);
         hashmap
     };
 }
@@ -8,3 +8,4 @@ pub mod ast;
 pub mod evaluator;
 mod group_parameters;
 pub mod parser;
+mod timing;
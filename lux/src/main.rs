@@ -2,6 +2,7 @@ use std::fmt::Write;
 use std::fs::{self, DirEntry, File};
 use std::io::BufRead;
 use std::io::BufReader;
+use std::sync::Arc;
 
 use lumen::address::Address;
 use lumen::parameter::{Param, Parameter};
@@ -39,10 +40,13 @@ fn run_example(example: DirEntry) {
         let mut patch = Patch::new();
         let mut profile = FixtureProfile::new();
         profile.set_parameter(Param::Intensity, Parameter::new(0, 0.0, 100.0));
+        let profile = Arc::new(profile);
 
         for n in 1..=10 {
             environment.fixtures.create_with_id(n);
-            patch.patch(n, Address::new(1, n as u16), &profile)
+            patch
+                .patch(n, Address::new(1, n as u16), Arc::clone(&profile))
+                .unwrap();
         }
 
         let mut evaluator = Evaluator::new(&mut environment);
@@ -36,7 +36,10 @@ fn parse_statement(pair: pest::iterators::Pair<Rule>) -> AstNode {
             let mut pair = pair.into_inner();
             let ident = parse_parameter(pair.next().unwrap());
             let generator = parse_group_or_generator(pair.next().unwrap());
-            AstNode::Apply(Box::new(ident), Box::new(generator))
+            let spread = pair
+                .next()
+                .map(|spread| Box::new(parse_spread(spread.into_inner())));
+            AstNode::Apply(Box::new(ident), Box::new(generator), spread)
         }
         Rule::select => {
             let mut pair = pair.into_inner();
@@ -49,12 +52,40 @@ fn parse_statement(pair: pest::iterators::Pair<Rule>) -> AstNode {
             let ident = parse_identifier(pair.next().unwrap());
             AstNode::Preset(Box::new(ident))
         }
+        Rule::home => AstNode::Home,
+        Rule::timing => parse_timing(pair.into_inner()),
         Rule::delay_block => parse_delay_block(pair.into_inner()),
         Rule::inline_delay => parse_inline_delay(pair.into_inner()),
         _ => panic!("Unexpected statement: {}", pair.as_str()),
     }
 }
 
+fn parse_timing(pairs: pest::iterators::Pairs<Rule>) -> AstNode {
+    let mut category = None;
+    let mut up = None;
+    let mut down = None;
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::timing_category | Rule::timing_intensity => {
+                category = Some(pair.as_str().to_owned())
+            }
+            Rule::timing_up => up = Some(Box::new(parse_time(pair.into_inner().next().unwrap()))),
+            Rule::timing_down => {
+                down = Some(Box::new(parse_time(pair.into_inner().next().unwrap())))
+            }
+            // A single time is both the up and down time
+            Rule::time => {
+                up = Some(Box::new(parse_time(pair)));
+                down = up.clone();
+            }
+            _ => panic!("Unexpected timing: {}", pair.as_str()),
+        }
+    }
+
+    AstNode::Timing(category, up, down)
+}
+
 fn parse_delay_block(mut pairs: pest::iterators::Pairs<Rule>) -> AstNode {
     let time = parse_time(pairs.next().unwrap());
     let statements = parse_statements(pairs);
@@ -92,7 +123,9 @@ fn parse_identifier(pair: pest::iterators::Pair<Rule>) -> AstNode {
 
 fn parse_group_or_generator(pair: pest::iterators::Pair<Rule>) -> AstNode {
     match pair.as_rule() {
-        Rule::group => parse_generator_group(pair.into_inner()),
+        // A function is a group with a single generator, so kelvin(3200) is
+        // the same as kelvin{3200}
+        Rule::group | Rule::function => parse_generator_group(pair.into_inner()),
         Rule::generator => parse_generator(pair),
         _ => panic!("Expected generator or group, but got {}", pair.as_str()),
     }
@@ -116,6 +149,8 @@ fn parse_generator(pair: pest::iterators::Pair<Rule>) -> AstNode {
     match pair.as_rule() {
         Rule::static_value => parse_static_value(pair),
         Rule::fade => parse_fade(pair.into_inner()),
+        Rule::oscillator => parse_oscillator(pair.into_inner()),
+        Rule::fan | Rule::fan_range => parse_fan(pair.into_inner()),
         _ => panic!("Unexpected generator: {}", pair.as_str()),
     }
 }
@@ -126,7 +161,9 @@ fn parse_static_value(pair: pest::iterators::Pair<Rule>) -> AstNode {
     let value = match pair.as_rule() {
         Rule::literal => parse_literal(pair),
         Rule::percentage => parse_percentage(pair),
+        Rule::slot => parse_slot(pair),
         Rule::current_value => return AstNode::CurrentValue,
+        Rule::home_value => return AstNode::HomeValue,
         _ => panic!("Unexpected value for static generator: {}", pair.as_str()),
     };
 
@@ -137,12 +174,78 @@ fn parse_fade(mut pairs: pest::iterators::Pairs<Rule>) -> AstNode {
     let start = parse_static_value(pairs.next().unwrap());
     let end = parse_static_value(pairs.next().unwrap());
 
-    let time = match pairs.next() {
-        Some(time) => parse_time(time),
-        None => AstNode::Time(3.0),
+    let mut time = None;
+    let mut curve = None;
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::time => time = Some(Box::new(parse_time(pair))),
+            Rule::curve => curve = Some(pair.as_str().to_owned()),
+            _ => panic!("Unexpected fade option: {}", pair.as_str()),
+        }
+    }
+
+    AstNode::Fade(Box::new(start), Box::new(end), time, curve)
+}
+
+fn parse_oscillator(mut pairs: pest::iterators::Pairs<Rule>) -> AstNode {
+    let waveform = pairs.next().unwrap().as_str().to_owned();
+    let low = parse_static_value(pairs.next().unwrap());
+    let high = parse_static_value(pairs.next().unwrap());
+    let time = parse_time(pairs.next().unwrap());
+    let phase = pairs.next().map(|phase| Box::new(parse_percentage(phase)));
+
+    AstNode::Oscillator(
+        waveform,
+        Box::new(low),
+        Box::new(high),
+        Box::new(time),
+        phase,
+    )
+}
+
+fn parse_fan(mut pairs: pest::iterators::Pairs<Rule>) -> AstNode {
+    let from = parse_static_value(pairs.next().unwrap());
+    let to = parse_static_value(pairs.next().unwrap());
+    let mode = pairs.next().map(|mode| mode.as_str().to_owned());
+
+    AstNode::Fan(Box::new(from), Box::new(to), mode)
+}
+
+fn parse_spread(pairs: pest::iterators::Pairs<Rule>) -> AstNode {
+    let mut amount = None;
+    let mut options = Vec::new();
+
+    // A spread without an amount only places the fixtures, and has options.
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::percentage => amount = Some(Box::new(parse_percentage(pair))),
+            Rule::time => amount = Some(Box::new(parse_time(pair))),
+            Rule::spread_option => {
+                options.push(parse_spread_option(pair.into_inner().next().unwrap()))
+            }
+            _ => panic!("Unexpected spread: {}", pair.as_str()),
+        }
+    }
+
+    AstNode::Spread(amount, options)
+}
+
+fn parse_spread_option(pair: pest::iterators::Pair<Rule>) -> AstNode {
+    let name = match pair.as_rule() {
+        Rule::spread_selection => "selection",
+        Rule::spread_blocks => "blocks",
+        Rule::spread_wings => "wings",
+        _ => panic!("Unexpected spread option: {}", pair.as_str()),
     };
 
-    AstNode::Fade(Box::new(start), Box::new(end), Box::new(time))
+    // Counts are checked when evaluated, as they may be too big to fit.
+    let count = pair
+        .into_inner()
+        .next()
+        .map(|count| count.as_str().to_owned());
+
+    AstNode::SpreadOption(name.to_owned(), count)
 }
 
 fn parse_time(pair: pest::iterators::Pair<Rule>) -> AstNode {
@@ -170,6 +273,11 @@ fn parse_percentage(pair: pest::iterators::Pair<Rule>) -> AstNode {
     AstNode::Percentage(number)
 }
 
+fn parse_slot(pair: pest::iterators::Pair<Rule>) -> AstNode {
+    let name = pair.into_inner().next().unwrap();
+    AstNode::Slot(name.as_str().to_owned())
+}
+
 fn parse_query(pair: pest::iterators::Pair<Rule>) -> AstNode {
     let mut query_nodes = Vec::new();
 
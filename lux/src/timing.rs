@@ -0,0 +1,141 @@
+use std::{collections::HashMap, time::Duration};
+
+use lumen::parameter::{Category, Param};
+
+// The fade time used when nothing else is given
+const DEFAULT_FADE_TIME: Duration = Duration::from_secs(3);
+
+// The up and down time of a fade. Without a down time, fading down takes the
+// up time. Only intensity fades have a down time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeTime {
+    pub up: Duration,
+    pub down: Option<Duration>,
+}
+
+impl FadeTime {
+    pub fn new(up: Duration) -> Self {
+        Self { up, down: None }
+    }
+
+    // Sets either time, leaving the other as it is.
+    fn update(&mut self, up: Option<Duration>, down: Option<Duration>) {
+        if let Some(up) = up {
+            self.up = up;
+        }
+        if down.is_some() {
+            self.down = down;
+        }
+    }
+}
+
+// The fade times for fades that aren't given a time, which can be set for all
+// parameters, or for the parameters in a category.
+#[derive(Debug, Clone)]
+pub struct Timing {
+    default: FadeTime,
+    categories: HashMap<Category, FadeTime>,
+}
+
+impl Timing {
+    pub fn new() -> Self {
+        Self {
+            default: FadeTime::new(DEFAULT_FADE_TIME),
+            categories: HashMap::new(),
+        }
+    }
+
+    // Setting the time of every parameter sets it for each category too.
+    pub fn set(&mut self, up: Option<Duration>, down: Option<Duration>) {
+        self.default.update(up, down);
+        for fade_time in self.categories.values_mut() {
+            fade_time.update(up, down);
+        }
+    }
+
+    pub fn set_category(
+        &mut self,
+        category: Category,
+        up: Option<Duration>,
+        down: Option<Duration>,
+    ) {
+        self.categories
+            .entry(category)
+            .or_insert(self.default)
+            .update(up, down);
+    }
+
+    // A down time set for every parameter only applies to intensity, as
+    // anything else going up or down makes no difference to how it looks.
+    pub fn get(&self, param: &Param) -> FadeTime {
+        let category = param.category();
+        let mut fade_time = self
+            .categories
+            .get(&category)
+            .copied()
+            .unwrap_or(self.default);
+
+        if category != Category::Intensity {
+            fade_time.down = None;
+        }
+
+        fade_time
+    }
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Option<Duration> {
+        Some(Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn parameters_take_the_default_time() {
+        let mut timing = Timing::new();
+        assert_eq!(timing.get(&Param::Pan), FadeTime::new(DEFAULT_FADE_TIME));
+
+        timing.set(secs(2), None);
+        assert_eq!(
+            timing.get(&Param::Pan),
+            FadeTime::new(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn categories_start_from_the_default_time() {
+        let mut timing = Timing::new();
+        timing.set(secs(2), secs(4));
+        timing.set_category(Category::Intensity, secs(1), None);
+
+        let intensity = timing.get(&Param::Intensity);
+        assert_eq!(intensity.up, Duration::from_secs(1));
+        assert_eq!(intensity.down, secs(4));
+        assert_eq!(timing.get(&Param::Pan).up, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn setting_every_parameter_sets_each_category() {
+        let mut timing = Timing::new();
+        timing.set_category(Category::Position, secs(5), None);
+        timing.set(secs(2), None);
+
+        assert_eq!(timing.get(&Param::Pan).up, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn only_intensity_has_a_down_time() {
+        let mut timing = Timing::new();
+        timing.set(secs(2), secs(4));
+
+        assert_eq!(timing.get(&Param::Intensity).down, secs(4));
+        assert_eq!(timing.get(&Param::Pan).down, None);
+    }
+}